@@ -0,0 +1,179 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Match archival
+
+use {
+    crate::{utils, DecodeLimits, Diff, Error, Proof, ProofAction, RootProof, State},
+    alloc::vec::Vec,
+};
+
+/// [Proof] that keeps every diff applied to it, including those whose actions were pruned
+pub struct ProofArchive<S: State> {
+    proof: Proof<S>,
+    diffs: Vec<Diff<S>>,
+}
+
+impl<S: State> ProofArchive<S> {
+    /// Constructs an empty archive from a root proof.
+    pub fn new(root: RootProof<S>) -> Self {
+        Self {
+            proof: Proof::new(root),
+            diffs: Vec::new(),
+        }
+    }
+
+    /// Gets the archived proof.
+    pub fn proof(&self) -> &Proof<S> {
+        &self.proof
+    }
+
+    /// Gets the diffs applied to the archive, oldest first.
+    pub fn diffs(&self) -> &[Diff<S>] {
+        &self.diffs
+    }
+
+    /// Verifies and applies a cryptographically constructed diff to the proof, then archives it.
+    ///
+    /// See [Proof::apply].
+    pub fn apply(&mut self, diff: &Diff<S>) -> Result<(), Error> {
+        self.proof.apply(diff)?;
+        self.diffs.push(diff.clone());
+
+        Ok(())
+    }
+
+    /// Exports a complete transcript that replays from the root proof to the current state.
+    pub fn transcript(&self) -> Transcript<S> {
+        Transcript {
+            root: self.proof.root.clone(),
+            diffs: self.diffs.clone(),
+        }
+    }
+}
+
+/// Complete match history, from the root proof
+pub struct Transcript<S: State> {
+    root: RootProof<S>,
+    diffs: Vec<Diff<S>>,
+}
+
+impl<S: State> Transcript<S> {
+    /// Constructs a transcript from its binary representation.
+    ///
+    /// `data` must have been constructed using [Transcript::serialize].
+    /// This does not check that the diffs apply; see [Transcript::replay].
+    pub fn deserialize(data: &[u8], no_version_check: bool) -> Result<Self, Error> {
+        Self::deserialize_with_limits(data, no_version_check, &Default::default())
+    }
+
+    /// Constructs a transcript from its binary representation, within the given limits.
+    ///
+    /// See [Transcript::deserialize].
+    pub fn deserialize_with_limits(
+        data: &[u8],
+        no_version_check: bool,
+        limits: &DecodeLimits,
+    ) -> Result<Self, Error> {
+        Self::deserialize_unchecked(data, no_version_check, limits).map_err(Error::decoding)
+    }
+
+    /// Generates a binary representation that can be used to reconstruct the transcript.
+    ///
+    /// See [Transcript::deserialize].
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        let root = self.root.serialize();
+
+        utils::write_u32_usize(&mut data, root.len()).unwrap();
+        data.extend(root);
+
+        utils::write_u32_usize(&mut data, self.diffs.len()).unwrap();
+
+        for diff in &self.diffs {
+            let diff = diff.serialize();
+
+            utils::write_u32_usize(&mut data, diff.len()).unwrap();
+            data.extend(diff);
+        }
+
+        data
+    }
+
+    /// Gets the root proof of the transcript.
+    pub fn root(&self) -> &RootProof<S> {
+        &self.root
+    }
+
+    /// Gets the diffs of the transcript, oldest first.
+    pub fn diffs(&self) -> &[Diff<S>] {
+        &self.diffs
+    }
+
+    /// Gets every action in the transcript, in order, starting with the root proof's.
+    pub fn actions(&self) -> impl Iterator<Item = &ProofAction<S>> {
+        self.root
+            .actions
+            .iter()
+            .chain(self.diffs.iter().flat_map(|diff| diff.actions.iter()))
+    }
+
+    /// Verifies and applies every diff in order, starting from the root proof.
+    pub fn replay(&self) -> Result<Proof<S>, Error> {
+        let mut proof = Proof::new(self.root.clone());
+
+        for diff in &self.diffs {
+            proof.apply(diff)?;
+        }
+
+        Ok(proof)
+    }
+
+    fn deserialize_unchecked(
+        mut data: &[u8],
+        no_version_check: bool,
+        limits: &DecodeLimits,
+    ) -> Result<Self, Error> {
+        let size = utils::read_u32_usize(&mut data)?;
+
+        crate::forbid!(data.len() < size);
+
+        let root = RootProof::deserialize_with_limits(&data[..size], no_version_check, limits)?;
+
+        data = &data[size..];
+
+        let length = utils::read_u32_usize(&mut data)?;
+
+        let mut diffs = Vec::with_capacity(length.min(data.len()));
+
+        for _ in 0..length {
+            let size = utils::read_u32_usize(&mut data)?;
+
+            crate::forbid!(data.len() < size);
+            diffs.push(Diff::deserialize_with_limits(
+                &data[..size],
+                root.state().id(),
+                limits,
+            )?);
+            data = &data[size..];
+        }
+
+        crate::forbid!(!data.is_empty());
+
+        Ok(Self { root, diffs })
+    }
+}
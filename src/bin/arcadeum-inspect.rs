@@ -0,0 +1,176 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Inspects root proofs, proofs, diffs and stores without knowledge of the game.
+//!
+//! ```text
+//! arcadeum-inspect [--ed25519 | --eip712] [--id <hex>] [--version <hex>]
+//!     <root|proof|diff|store> <file> [<kind> <file> ...]
+//! ```
+//!
+//! Files may be binary, or hex with an optional `0x` prefix.
+//! Diffs are checked against the most recently inspected proof or store, and proofs against the
+//! most recently inspected root proof.
+//! `--eip712` signatures also need the binary match identifier, and diffs need the game version
+//! unless a root proof, proof or store was inspected before them.
+//! Player proof ranges are attributed to the authors of diffs inspected before the proof.
+//! Exits with a non-zero status if any signature or hash doesn't check out.
+
+use {
+    arcadeum::{
+        crypto::{format_address, Ed25519, Eip712, Hash, Secp256k1},
+        inspect::{inspect, Context, Kind, Report},
+        utils, Error,
+    },
+    std::process::exit,
+};
+
+const USAGE: &str =
+    "usage: arcadeum-inspect [--ed25519 | --eip712] [--id <hex>] [--version <hex>] \
+                     <root|proof|diff|store> <file> [<kind> <file> ...]";
+
+enum Scheme {
+    Secp256k1,
+    Ed25519,
+    Eip712,
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut scheme = Scheme::Secp256k1;
+    let mut id = Vec::new();
+    let mut version = Vec::new();
+
+    while matches!(args.first(), Some(arg) if arg.starts_with("--")) {
+        let flag = args.remove(0);
+
+        match flag.as_str() {
+            "--ed25519" => scheme = Scheme::Ed25519,
+            "--eip712" => scheme = Scheme::Eip712,
+            "--id" | "--version" if !args.is_empty() => {
+                let value = utils::unhex(&args.remove(0)).unwrap_or_else(|error| {
+                    eprintln!("{}: {}\n{}", flag, error, USAGE);
+                    exit(2);
+                });
+
+                if flag == "--id" {
+                    id = value;
+                } else {
+                    version = value;
+                }
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                exit(2);
+            }
+        }
+    }
+
+    if args.is_empty() || args.len() % 2 != 0 {
+        eprintln!("{}", USAGE);
+        exit(2);
+    }
+
+    let mut context = Context::new(id);
+
+    context.set_version(version);
+
+    let mut valid = true;
+    let mut proof: Option<Hash> = None;
+    let mut root: Option<Vec<u8>> = None;
+
+    for pair in args.chunks(2) {
+        let (kind, path) = (&pair[0], &pair[1]);
+
+        let kind: Kind = kind.parse().unwrap_or_else(|error| {
+            eprintln!("{}\n{}", error, USAGE);
+            exit(2);
+        });
+
+        let report = read(path).and_then(|data| match scheme {
+            Scheme::Secp256k1 => inspect::<Secp256k1>(&data, kind, &context),
+            Scheme::Ed25519 => inspect::<Ed25519>(&data, kind, &context),
+            Scheme::Eip712 => inspect::<Eip712>(&data, kind, &context),
+        });
+
+        let report = report.unwrap_or_else(|error| {
+            eprintln!("{}: {}", path, error);
+            exit(1);
+        });
+
+        println!("{} ({}):", path, kind);
+        print!("{}", indent(&report));
+
+        valid &= report.is_valid();
+
+        context.learn(&report);
+
+        match kind {
+            Kind::RootProof => root = report.author().map(<[u8]>::to_vec),
+            Kind::Proof => {
+                if let (Some(owner), Some(author)) = (report.owner(), &root) {
+                    if owner != &author[..] {
+                        println!(
+                            "  error: owner signer {} != inspected root author {}",
+                            format_address(owner),
+                            format_address(author),
+                        );
+
+                        valid = false;
+                    }
+                }
+
+                proof = report.hash().copied();
+            }
+            Kind::Diff => {
+                if let (Some(expected), Some(actual)) = (report.proof(), proof) {
+                    if expected != &actual {
+                        println!(
+                            "  error: proof {} != inspected proof {}",
+                            utils::hex(expected),
+                            utils::hex(&actual),
+                        );
+
+                        valid = false;
+                    }
+                }
+            }
+            Kind::Store => proof = report.hash().copied(),
+        }
+    }
+
+    if !valid {
+        exit(1);
+    }
+}
+
+fn read(path: &str) -> Result<Vec<u8>, Error> {
+    let data = std::fs::read(path).map_err(|error| Error::decode(error.to_string()))?;
+
+    match std::str::from_utf8(&data).map(|text| utils::unhex(text.trim())) {
+        Ok(Ok(hex)) => Ok(hex),
+        _ => Ok(data),
+    }
+}
+
+fn indent(report: &Report) -> String {
+    report
+        .lines()
+        .iter()
+        .map(|line| format!("  {}\n", line))
+        .collect()
+}
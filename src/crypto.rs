@@ -18,28 +18,36 @@
 
 use {
     alloc::{
-        format,
         string::{String, ToString},
         vec,
         vec::Vec,
     },
     core::{
         column,
-        convert::TryInto,
+        convert::{TryFrom, TryInto},
         file,
-        fmt::{Error, Formatter},
+        fmt::{Debug, Error, Formatter},
         line,
         mem::size_of,
     },
 };
 
+#[cfg(not(feature = "no-crypto"))]
+use alloc::format;
+
+mod eip712;
+
+pub use eip712::Eip712;
+
 #[cfg(not(feature = "no-crypto"))]
 pub use libsecp256k1::SecretKey;
 
 #[cfg(feature = "no-crypto")]
 pub type SecretKey = Address;
 
-/// Public key address
+/// Ethereum address
+///
+/// See [SignatureScheme::Address].
 pub type Address = [u8; 20];
 
 /// Message signature
@@ -73,7 +81,12 @@ pub fn sign(message: &[u8], secret: &SecretKey) -> Signature {
     ]
     .concat();
 
-    let message = libsecp256k1::Message::parse(&keccak256(&message));
+    sign_digest(&keccak256(&message), secret)
+}
+
+#[cfg(not(feature = "no-crypto"))]
+pub(crate) fn sign_digest(digest: &Hash, secret: &SecretKey) -> Signature {
+    let message = libsecp256k1::Message::parse(digest);
 
     let (mut signature, recovery) = libsecp256k1::sign(&message, secret);
     signature.normalize_s();
@@ -113,6 +126,11 @@ pub fn sign(_message: &[u8], secret: &SecretKey) -> Signature {
     signature
 }
 
+#[cfg(feature = "no-crypto")]
+pub(crate) fn sign_digest(_digest: &Hash, secret: &SecretKey) -> Signature {
+    sign(&[], secret)
+}
+
 #[cfg(all(not(feature = "no-crypto"), feature = "std"))]
 /// Recovers the address of the key that signed a message.
 ///
@@ -133,8 +151,12 @@ pub fn sign(_message: &[u8], secret: &SecretKey) -> Signature {
 ///     Ok(b"\xdf\x55\x60\xB8\x13\x8C\xfa\x93\x86\x4B\xBD\xDe\x4D\xe4\xfF\xBD\x6C\x54\x69\xBF"),
 /// );
 /// ```
-pub fn recover(message: &[u8], signature: &[u8]) -> Result<Address, String> {
-    crate::forbid!(signature.len() != size_of::<Signature>());
+pub fn recover(message: &[u8], signature: &[u8]) -> Result<Address, crate::Error> {
+    if signature.len() != size_of::<Signature>() {
+        return Err(crate::Error::bad_signature(
+            "signature.len() != size_of::<Signature>()",
+        ));
+    }
 
     let message = [
         format!("\x19Ethereum Signed Message:\n{}", message.len()).as_bytes(),
@@ -142,14 +164,25 @@ pub fn recover(message: &[u8], signature: &[u8]) -> Result<Address, String> {
     ]
     .concat();
 
-    _cached_recover(keccak256(&message), signature.try_into().unwrap())
+    recover_digest(&keccak256(&message), signature)
+}
+
+#[cfg(all(not(feature = "no-crypto"), feature = "std"))]
+pub(crate) fn recover_digest(digest: &Hash, signature: &[u8]) -> Result<Address, crate::Error> {
+    if signature.len() != size_of::<Signature>() {
+        return Err(crate::Error::bad_signature(
+            "signature.len() != size_of::<Signature>()",
+        ));
+    }
+
+    _cached_recover(*digest, signature.try_into().unwrap())
 }
 
 #[cfg(all(not(feature = "no-crypto"), feature = "std"))]
 cached::cached! {
-    RECOVER_CACHE: cached::SizedCache<(Hash, Signature), Result<Address, String>> = cached::SizedCache::with_size(256);
+    RECOVER_CACHE: cached::SizedCache<(Hash, Signature), Result<Address, crate::Error>> = cached::SizedCache::with_size(256);
 
-    fn _cached_recover(digest: Hash, signature: Signature) -> Result<Address, String> = {
+    fn _cached_recover(digest: Hash, signature: Signature) -> Result<Address, crate::Error> = {
         let message = libsecp256k1::Message::parse(&digest);
 
         let recovery =
@@ -158,16 +191,18 @@ cached::cached! {
                 1 | 28 => 1,
                 2 | 29 => 2,
                 3 | 30 => 3,
-                recovery => return Err(format!("recovery == {}", recovery)),
+                recovery => {
+                    return Err(crate::Error::bad_signature(format!("recovery == {}", recovery)))
+                }
             })
-            .map_err(|error| format!("{:?}", error))?;
+            .map_err(|error| crate::Error::bad_signature(format!("{:?}", error)))?;
 
         let signature =
             libsecp256k1::Signature::parse_standard_slice(&signature[..size_of::<Signature>() - 1])
-                .map_err(|error| format!("{:?}", error))?;
+                .map_err(|error| crate::Error::bad_signature(format!("{:?}", error)))?;
 
         let public = libsecp256k1::recover(&message, &signature, &recovery)
-            .map_err(|error| format!("{:?}", error))?;
+            .map_err(|error| crate::Error::bad_signature(format!("{:?}", error)))?;
 
         Ok(address(&public))
     }
@@ -193,8 +228,12 @@ cached::cached! {
 ///     Ok(b"\xdf\x55\x60\xB8\x13\x8C\xfa\x93\x86\x4B\xBD\xDe\x4D\xe4\xfF\xBD\x6C\x54\x69\xBF"),
 /// );
 /// ```
-pub fn recover(message: &[u8], signature: &[u8]) -> Result<Address, String> {
-    crate::forbid!(signature.len() != size_of::<Signature>());
+pub fn recover(message: &[u8], signature: &[u8]) -> Result<Address, crate::Error> {
+    if signature.len() != size_of::<Signature>() {
+        return Err(crate::Error::bad_signature(
+            "signature.len() != size_of::<Signature>()",
+        ));
+    }
 
     let message = [
         format!("\x19Ethereum Signed Message:\n{}", message.len()).as_bytes(),
@@ -202,22 +241,38 @@ pub fn recover(message: &[u8], signature: &[u8]) -> Result<Address, String> {
     ]
     .concat();
 
-    let message = libsecp256k1::Message::parse(&keccak256(&message));
+    recover_digest(&keccak256(&message), signature)
+}
+
+#[cfg(all(not(feature = "no-crypto"), not(feature = "std")))]
+pub(crate) fn recover_digest(digest: &Hash, signature: &[u8]) -> Result<Address, crate::Error> {
+    if signature.len() != size_of::<Signature>() {
+        return Err(crate::Error::bad_signature(
+            "signature.len() != size_of::<Signature>()",
+        ));
+    }
+
+    let message = libsecp256k1::Message::parse(digest);
 
     let recovery = libsecp256k1::RecoveryId::parse(match signature[size_of::<Signature>() - 1] {
         0 | 27 => 0,
         1 | 28 => 1,
         2 | 29 => 2,
         3 | 30 => 3,
-        recovery => return Err(format!("recovery == {}", recovery)),
+        recovery => {
+            return Err(crate::Error::bad_signature(format!(
+                "recovery == {}",
+                recovery
+            )))
+        }
     })
-    .map_err(|error| format!("{:?}", error))?;
+    .map_err(|error| crate::Error::bad_signature(format!("{:?}", error)))?;
 
     let signature = libsecp256k1::Signature::parse_slice(&signature[..size_of::<Signature>() - 1])
-        .map_err(|error| format!("{:?}", error))?;
+        .map_err(|error| crate::Error::bad_signature(format!("{:?}", error)))?;
 
     let public = libsecp256k1::recover(&message, &signature, &recovery)
-        .map_err(|error| format!("{:?}", error))?;
+        .map_err(|error| crate::Error::bad_signature(format!("{:?}", error)))?;
 
     Ok(address(&public))
 }
@@ -242,16 +297,331 @@ pub fn recover(message: &[u8], signature: &[u8]) -> Result<Address, String> {
 ///     Ok(b"\xdf\x55\x60\xB8\x13\x8C\xfa\x93\x86\x4B\xBD\xDe\x4D\xe4\xfF\xBD\x6C\x54\x69\xBF"),
 /// );
 /// ```
-pub fn recover(_message: &[u8], signature: &[u8]) -> Result<Address, String> {
-    crate::forbid!(signature.len() != size_of::<Signature>());
+pub fn recover(_message: &[u8], signature: &[u8]) -> Result<Address, crate::Error> {
+    if signature.len() != size_of::<Signature>() {
+        return Err(crate::Error::bad_signature(
+            "signature.len() != size_of::<Signature>()",
+        ));
+    }
 
-    crate::forbid!(
-        signature[size_of::<Address>()..] != [0; size_of::<Signature>() - size_of::<Address>()][..]
-    );
+    if signature[size_of::<Address>()..] != [0; size_of::<Signature>() - size_of::<Address>()][..] {
+        return Err(crate::Error::bad_signature(
+            "signature[size_of::<Address>()..] != [0; _]",
+        ));
+    }
 
     signature[..size_of::<Address>()]
         .try_into()
-        .map_err(|error| format!("{}", error))
+        .map_err(crate::Error::bad_signature)
+}
+
+#[cfg(feature = "no-crypto")]
+pub(crate) fn recover_digest(_digest: &Hash, signature: &[u8]) -> Result<Address, crate::Error> {
+    recover(&[], signature)
+}
+
+/// Signature scheme trait
+pub trait SignatureScheme: 'static {
+    /// Secret key type
+    type SecretKey: Clone + crate::store::MaybeSend;
+
+    /// Key address type
+    type Address: Copy
+        + Debug
+        + Eq
+        + Ord
+        + AsRef<[u8]>
+        + for<'a> TryFrom<&'a [u8]>
+        + crate::store::MaybeSend;
+
+    /// Message signature type
+    type Signature: Copy
+        + Debug
+        + Eq
+        + AsRef<[u8]>
+        + for<'a> TryFrom<&'a [u8]>
+        + crate::store::MaybeSend;
+
+    /// Size of a binary address
+    const ADDRESS_SIZE: usize;
+
+    /// Size of a binary signature
+    const SIGNATURE_SIZE: usize;
+
+    /// Constructs a secret key from a seed.
+    fn secret_key(seed: &[u8; 32]) -> Result<Self::SecretKey, crate::Error>;
+
+    /// Gets the address of a secret key.
+    fn address(secret: &Self::SecretKey) -> Self::Address;
+
+    /// Signs a message.
+    ///
+    /// `message` must have been constructed using [SignatureScheme::encode].
+    fn sign(message: &[u8], secret: &Self::SecretKey) -> Result<Self::Signature, crate::Error>;
+
+    /// Recovers the address of the key that signed a message.
+    ///
+    /// `message` must have been constructed using [SignatureScheme::encode].
+    fn recover(message: &[u8], signature: &[u8]) -> Result<Self::Address, crate::Error>;
+
+    /// Encodes a message for signing.
+    ///
+    /// By default, messages are signed as opaque binary data, ignoring the domain.
+    fn encode(message: &Message<'_>) -> Vec<u8> {
+        match message {
+            Message::Proof { state, actions, .. } => [*state, *actions].concat(),
+            Message::Diff {
+                proof,
+                actions,
+                proof_signature,
+                ..
+            } => [&proof[..], actions, proof_signature].concat(),
+            Message::Certify {
+                challenge,
+                expiry: None,
+                ..
+            } => challenge.as_bytes().to_vec(),
+            Message::Certify {
+                challenge,
+                expiry: Some(expiry),
+                ..
+            } => [
+                challenge.as_bytes(),
+                b"\nExpiry: ",
+                crate::utils::hex(expiry).as_bytes(),
+                b"\n",
+            ]
+            .concat(),
+            Message::Approve { approval, .. } => approval.as_bytes().to_vec(),
+            Message::Result { proof, outcome, .. } => [&proof[..], outcome.as_bytes()].concat(),
+        }
+    }
+
+    /// Constructs an address from its binary representation.
+    fn parse_address(data: &[u8]) -> Result<Self::Address, crate::Error> {
+        if data.len() != Self::ADDRESS_SIZE {
+            return Err(crate::Error::decode("data.len() != Self::ADDRESS_SIZE"));
+        }
+
+        Self::Address::try_from(data)
+            .map_err(|_| crate::Error::decode("Self::Address::try_from(data).is_err()"))
+    }
+
+    /// Constructs a signature from its binary representation.
+    fn signature(data: &[u8]) -> Result<Self::Signature, crate::Error> {
+        if data.len() != Self::SIGNATURE_SIZE {
+            return Err(crate::Error::bad_signature(
+                "data.len() != Self::SIGNATURE_SIZE",
+            ));
+        }
+
+        Self::Signature::try_from(data)
+            .map_err(|_| crate::Error::bad_signature("Self::Signature::try_from(data).is_err()"))
+    }
+}
+
+/// Domain of a signed message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Domain<'a> {
+    /// The ABI version of the [State](crate::State).
+    pub version: &'a [u8],
+
+    /// The hash of the match identifier.
+    pub salt: Hash,
+}
+
+/// Message to be signed
+///
+/// See [SignatureScheme::encode].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message<'a> {
+    /// A proof of a state and the actions following it.
+    Proof {
+        /// The domain of the message.
+        domain: Domain<'a>,
+
+        /// The binary representation of the state.
+        state: &'a [u8],
+
+        /// The binary representation of the actions.
+        actions: &'a [u8],
+    },
+
+    /// A diff.
+    Diff {
+        /// The domain of the message.
+        domain: Domain<'a>,
+
+        /// The hash of the proof the diff applies to.
+        proof: &'a Hash,
+
+        /// The binary representation of the actions.
+        actions: &'a [u8],
+
+        /// The signature of the resulting proof.
+        proof_signature: &'a [u8],
+    },
+
+    /// A subkey certification challenge.
+    Certify {
+        /// The domain of the message.
+        domain: Domain<'a>,
+
+        /// The challenge.
+        ///
+        /// See [State::challenge](crate::State::challenge).
+        challenge: &'a str,
+
+        /// The binary representation of the subkey address.
+        subkey: &'a [u8],
+
+        /// The binary representation of the last nonce for which the subkey is valid, if any.
+        expiry: Option<&'a [u8]>,
+    },
+
+    /// A subkey approval.
+    Approve {
+        /// The domain of the message.
+        domain: Domain<'a>,
+
+        /// The approval.
+        ///
+        /// See [State::approval](crate::State::approval).
+        approval: &'a str,
+
+        /// The binary representation of the player address.
+        player: &'a [u8],
+
+        /// The binary representation of the subkey address.
+        subkey: &'a [u8],
+    },
+
+    /// A match result.
+    Result {
+        /// The domain of the message.
+        domain: Domain<'a>,
+
+        /// The hash of the final proof.
+        proof: &'a Hash,
+
+        /// The description of the outcome.
+        ///
+        /// See [Outcome](crate::Outcome).
+        outcome: &'a str,
+    },
+}
+
+/// Key address of a [State](crate::State)
+pub type StateAddress<S> = <<S as crate::State>::Scheme as SignatureScheme>::Address;
+
+/// Message signature of a [State](crate::State)
+pub type StateSignature<S> = <<S as crate::State>::Scheme as SignatureScheme>::Signature;
+
+/// Ethereum personal-sign secp256k1 ECDSA signature scheme
+///
+/// See [sign] and [recover].
+pub struct Secp256k1;
+
+impl SignatureScheme for Secp256k1 {
+    type SecretKey = SecretKey;
+    type Address = Address;
+    type Signature = Signature;
+
+    const ADDRESS_SIZE: usize = size_of::<Address>();
+    const SIGNATURE_SIZE: usize = size_of::<Signature>();
+
+    #[cfg(not(feature = "no-crypto"))]
+    fn secret_key(seed: &[u8; 32]) -> Result<Self::SecretKey, crate::Error> {
+        SecretKey::parse(seed).map_err(|error| crate::Error::bad_signature(format!("{:?}", error)))
+    }
+
+    #[cfg(feature = "no-crypto")]
+    fn secret_key(seed: &[u8; 32]) -> Result<Self::SecretKey, crate::Error> {
+        Ok(seed[..size_of::<Address>()].try_into().unwrap())
+    }
+
+    fn address(secret: &Self::SecretKey) -> Address {
+        secret.address()
+    }
+
+    fn sign(message: &[u8], secret: &Self::SecretKey) -> Result<Self::Signature, crate::Error> {
+        Ok(sign(message, secret))
+    }
+
+    fn recover(message: &[u8], signature: &[u8]) -> Result<Address, crate::Error> {
+        recover(message, signature)
+    }
+}
+
+/// Ed25519 signature scheme
+///
+/// Messages are signed as is.
+/// Signatures are the 64-byte Ed25519 signature followed by the 32-byte public key.
+/// Addresses are the public keys.
+///
+/// # Examples
+///
+/// ```
+/// use arcadeum::crypto::{Ed25519, SignatureScheme};
+///
+/// let secret = Ed25519::secret_key(&[1; 32]).unwrap();
+/// let message = b"quod erat demonstrandum";
+/// let signature = Ed25519::sign(message, &secret).unwrap();
+///
+/// assert_eq!(
+///     Ed25519::recover(message, &signature).unwrap(),
+///     Ed25519::address(&secret)
+/// );
+/// ```
+pub struct Ed25519;
+
+impl SignatureScheme for Ed25519 {
+    type SecretKey = [u8; 32];
+    type Address = [u8; 32];
+    type Signature = [u8; 96];
+
+    const ADDRESS_SIZE: usize = size_of::<Self::Address>();
+    const SIGNATURE_SIZE: usize = size_of::<Self::Signature>();
+
+    fn secret_key(seed: &[u8; 32]) -> Result<Self::SecretKey, crate::Error> {
+        Ok(*seed)
+    }
+
+    fn address(secret: &Self::SecretKey) -> Self::Address {
+        *ed25519_compact::KeyPair::from_seed(ed25519_compact::Seed::new(*secret)).pk
+    }
+
+    fn sign(message: &[u8], secret: &Self::SecretKey) -> Result<Self::Signature, crate::Error> {
+        let pair = ed25519_compact::KeyPair::from_seed(ed25519_compact::Seed::new(*secret));
+
+        let mut signature = [0; size_of::<Self::Signature>()];
+        signature[..ed25519_compact::Signature::BYTES]
+            .copy_from_slice(pair.sk.sign(message, None).as_ref());
+        signature[ed25519_compact::Signature::BYTES..].copy_from_slice(pair.pk.as_ref());
+        Ok(signature)
+    }
+
+    fn recover(message: &[u8], signature: &[u8]) -> Result<Self::Address, crate::Error> {
+        if signature.len() != Self::SIGNATURE_SIZE {
+            return Err(crate::Error::bad_signature(
+                "signature.len() != Self::SIGNATURE_SIZE",
+            ));
+        }
+
+        let public =
+            ed25519_compact::PublicKey::from_slice(&signature[ed25519_compact::Signature::BYTES..])
+                .map_err(|error| crate::Error::bad_signature(error.to_string()))?;
+
+        let ed25519 =
+            ed25519_compact::Signature::from_slice(&signature[..ed25519_compact::Signature::BYTES])
+                .map_err(|error| crate::Error::bad_signature(error.to_string()))?;
+
+        public
+            .verify(message, &ed25519)
+            .map_err(|error| crate::Error::bad_signature(error.to_string()))?;
+
+        Ok(*public)
+    }
 }
 
 /// Addressable trait
@@ -410,8 +780,18 @@ pub fn eip55(address: &Address) -> String {
     String::from_utf8(address).unwrap()
 }
 
-pub(crate) fn fmt_address(address: &impl Addressable, f: &mut Formatter<'_>) -> Result<(), Error> {
-    write!(f, "{}", address.eip55())
+/// Formats the binary representation of an address.
+///
+/// Ethereum addresses are formatted using [eip55], other addresses in hexadecimal.
+pub fn format_address(address: &[u8]) -> String {
+    match address.try_into() {
+        Ok(address) => eip55(address),
+        Err(_) => crate::utils::hex(address),
+    }
+}
+
+pub(crate) fn fmt_address(address: &impl AsRef<[u8]>, f: &mut Formatter<'_>) -> Result<(), Error> {
+    write!(f, "{}", format_address(address.as_ref()))
 }
 
 /// Computes the hash specified by the Keccak SHA-3 submission.
@@ -532,19 +912,38 @@ impl<T: MerkleLeaf> MerkleTree<T> {
     /// Constructs a Merkle tree from its binary representation.
     ///
     /// `data` must have been constructed using [MerkleTree::serialize].
-    pub fn deserialize(mut data: &[u8]) -> Result<Self, String> {
+    pub fn deserialize(data: &[u8]) -> Result<Self, crate::Error> {
+        Self::deserialize_with_limits(data, &Default::default())
+    }
+
+    /// Constructs a Merkle tree from its binary representation, within the given limits.
+    ///
+    /// See [MerkleTree::deserialize].
+    pub fn deserialize_with_limits(
+        data: &[u8],
+        limits: &crate::DecodeLimits,
+    ) -> Result<Self, crate::Error> {
+        Self::deserialize_unchecked(data, limits).map_err(crate::Error::decoding)
+    }
+
+    fn deserialize_unchecked(
+        mut data: &[u8],
+        limits: &crate::DecodeLimits,
+    ) -> Result<Self, crate::Error> {
         crate::forbid!(data.len() < size_of::<u32>());
 
         let length = crate::utils::read_u32_usize(&mut data)?;
 
+        crate::forbid!(length > limits.max_merkle_leaves);
+
         let elements = {
-            let mut elements = Vec::with_capacity(length);
+            let mut elements = Vec::with_capacity(length.min(data.len()));
 
             for _ in 0..length {
                 let size = crate::utils::read_u32_usize(&mut data)?;
 
                 crate::forbid!(data.len() < size);
-                elements.push(T::deserialize(&data[..size])?);
+                elements.push(T::deserialize(&data[..size]).map_err(crate::Error::decode)?);
                 data = &data[size..];
             }
 
@@ -615,7 +1014,7 @@ impl<T: MerkleLeaf> MerkleTree<T> {
     }
 
     /// Generates a Merkle proof for the element at the given index.
-    pub fn proof(&self, index: usize) -> Result<MerkleProof<T>, String> {
+    pub fn proof(&self, index: usize) -> Result<MerkleProof<T>, crate::Error> {
         crate::forbid!(index >= self.len());
 
         let hashes = {
@@ -680,7 +1079,24 @@ impl<T: MerkleLeaf> MerkleProof<T> {
     /// Constructs a Merkle proof from its binary representation.
     ///
     /// `data` must have been constructed using [MerkleProof::serialize].
-    pub fn deserialize(mut data: &[u8]) -> Result<Self, String> {
+    pub fn deserialize(data: &[u8]) -> Result<Self, crate::Error> {
+        Self::deserialize_with_limits(data, &Default::default())
+    }
+
+    /// Constructs a Merkle proof from its binary representation, within the given limits.
+    ///
+    /// See [MerkleProof::deserialize].
+    pub fn deserialize_with_limits(
+        data: &[u8],
+        limits: &crate::DecodeLimits,
+    ) -> Result<Self, crate::Error> {
+        Self::deserialize_unchecked(data, limits).map_err(crate::Error::decoding)
+    }
+
+    fn deserialize_unchecked(
+        mut data: &[u8],
+        limits: &crate::DecodeLimits,
+    ) -> Result<Self, crate::Error> {
         crate::forbid!(
             data.len() < size_of::<u32>() + size_of::<u32>() + size_of::<u32>() + size_of::<u32>()
         );
@@ -688,7 +1104,7 @@ impl<T: MerkleLeaf> MerkleProof<T> {
         let size = crate::utils::read_u32_usize(&mut data)?;
 
         crate::forbid!(data.len() < size);
-        let element = T::deserialize(&data[..size])?;
+        let element = T::deserialize(&data[..size]).map_err(crate::Error::decode)?;
         data = &data[size..];
 
         let size = crate::utils::read_u32_usize(&mut data)?;
@@ -705,6 +1121,7 @@ impl<T: MerkleLeaf> MerkleProof<T> {
         let index = crate::utils::read_u32_usize(&mut data)?;
         let length = crate::utils::read_u32_usize(&mut data)?;
 
+        crate::forbid!(length > limits.max_merkle_leaves);
         crate::forbid!(data.len() % size_of::<Hash>() != 0);
 
         let hashes = data
@@ -773,7 +1190,7 @@ impl<T: MerkleLeaf> MerkleProof<T> {
         &self.root
     }
 
-    fn compute_root(&self) -> Result<Hash, String> {
+    fn compute_root(&self) -> Result<Hash, crate::Error> {
         let mut root = keccak256(&match &self.salt {
             Some(salt) => [self.element.serialize().as_slice(), salt].concat(),
             None => self.element.serialize(),
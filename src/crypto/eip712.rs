@@ -0,0 +1,479 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! EIP 712 typed structured data signing
+
+use {
+    super::{keccak256, Address, Hash, Message, SecretKey, Signature, SignatureScheme},
+    crate::utils,
+    alloc::{format, string::String, vec::Vec},
+    core::{convert::TryInto, mem::size_of},
+};
+
+const DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,bytes32 salt)";
+const PROOF_TYPE: &str = "Proof(bytes state,bytes actions)";
+const DIFF_TYPE: &str = "Diff(bytes32 proof,bytes actions,bytes proofSignature)";
+const CERTIFY_TYPE: &str = "Certify(string challenge,address subkey,bytes expiry)";
+const APPROVE_TYPE: &str = "Approve(string approval,address player,address subkey)";
+const RESULT_TYPE: &str = "Result(bytes32 proof,string outcome)";
+
+const PROOF: u8 = 0;
+const DIFF: u8 = 1;
+const CERTIFY: u8 = 2;
+const APPROVE: u8 = 3;
+const RESULT: u8 = 4;
+
+/// EIP 712 typed structured data secp256k1 ECDSA signature scheme
+///
+/// Every message is signed as a typed struct in the domain
+/// `EIP712Domain(string name,string version,bytes32 salt)`, where `name` is [Eip712::NAME], `version` is
+/// the hexadecimal [State::version](crate::State::version), and `salt` is [Domain::salt](super::Domain::salt).
+///
+/// The typed structs are:
+///
+/// - `Proof(bytes state,bytes actions)`
+/// - `Diff(bytes32 proof,bytes actions,bytes proofSignature)`
+/// - `Certify(string challenge,address subkey,bytes expiry)`
+/// - `Approve(string approval,address player,address subkey)`
+/// - `Result(bytes32 proof,string outcome)`
+///
+/// The `expiry` of a `Certify` struct is empty if the subkey doesn't expire.
+/// Messages passed to signers are in a compact binary form.
+/// Wallets supporting `eth_signTypedData_v4` can sign the JSON returned by [Eip712::typed_data].
+///
+/// # Examples
+///
+/// ```
+/// use arcadeum::crypto::{Domain, Eip712, Message, SignatureScheme};
+///
+/// let secret = Eip712::secret_key(&[1; 32]).unwrap();
+///
+/// let message = Eip712::encode(&Message::Certify {
+///     domain: Domain {
+///         version: b"quod erat demonstrandum",
+///         salt: [2; 32],
+///     },
+///     challenge: "Sign to play!",
+///     subkey: &[3; 20],
+///     expiry: None,
+/// });
+///
+/// let signature = Eip712::sign(&message, &secret).unwrap();
+///
+/// assert_eq!(
+///     Eip712::recover(&message, &signature).unwrap(),
+///     Eip712::address(&secret)
+/// );
+///
+/// assert!(Eip712::typed_data(&message)
+///     .unwrap()
+///     .contains(r#""primaryType":"Certify""#));
+/// ```
+pub struct Eip712;
+
+impl Eip712 {
+    /// Name of the EIP 712 domain
+    pub const NAME: &'static str = "Arcadeum";
+
+    /// Computes the EIP 712 digest of a message.
+    ///
+    /// `message` must have been constructed using [Eip712::encode](SignatureScheme::encode).
+    pub fn digest(message: &[u8]) -> Result<Hash, crate::Error> {
+        let typed = Typed::decode(message)?;
+
+        Ok(keccak256(
+            &[
+                &b"\x19\x01"[..],
+                &typed.domain_separator(),
+                &typed.struct_hash(),
+            ]
+            .concat(),
+        ))
+    }
+
+    /// Generates the `eth_signTypedData_v4` JSON representation of a message.
+    ///
+    /// `message` must have been constructed using [Eip712::encode](SignatureScheme::encode).
+    pub fn typed_data(message: &[u8]) -> Result<String, crate::Error> {
+        let typed = Typed::decode(message)?;
+
+        let (primary, fields, values) = match &typed.message {
+            TypedMessage::Proof { state, actions } => (
+                "Proof",
+                r#"[{"name":"state","type":"bytes"},{"name":"actions","type":"bytes"}]"#,
+                format!(
+                    r#"{{"state":"{}","actions":"{}"}}"#,
+                    utils::hex(state),
+                    utils::hex(actions)
+                ),
+            ),
+            TypedMessage::Diff {
+                proof,
+                actions,
+                proof_signature,
+            } => (
+                "Diff",
+                r#"[{"name":"proof","type":"bytes32"},{"name":"actions","type":"bytes"},{"name":"proofSignature","type":"bytes"}]"#,
+                format!(
+                    r#"{{"proof":"{}","actions":"{}","proofSignature":"{}"}}"#,
+                    utils::hex(proof),
+                    utils::hex(actions),
+                    utils::hex(proof_signature)
+                ),
+            ),
+            TypedMessage::Certify {
+                challenge,
+                subkey,
+                expiry,
+            } => (
+                "Certify",
+                r#"[{"name":"challenge","type":"string"},{"name":"subkey","type":"address"},{"name":"expiry","type":"bytes"}]"#,
+                format!(
+                    r#"{{"challenge":"{}","subkey":"{}","expiry":"{}"}}"#,
+                    escape(challenge),
+                    super::eip55(subkey),
+                    utils::hex(expiry)
+                ),
+            ),
+            TypedMessage::Approve {
+                approval,
+                player,
+                subkey,
+            } => (
+                "Approve",
+                r#"[{"name":"approval","type":"string"},{"name":"player","type":"address"},{"name":"subkey","type":"address"}]"#,
+                format!(
+                    r#"{{"approval":"{}","player":"{}","subkey":"{}"}}"#,
+                    escape(approval),
+                    super::eip55(player),
+                    super::eip55(subkey)
+                ),
+            ),
+            TypedMessage::Result { proof, outcome } => (
+                "Result",
+                r#"[{"name":"proof","type":"bytes32"},{"name":"outcome","type":"string"}]"#,
+                format!(
+                    r#"{{"proof":"{}","outcome":"{}"}}"#,
+                    utils::hex(proof),
+                    escape(outcome)
+                ),
+            ),
+        };
+
+        Ok(format!(
+            concat!(
+                r#"{{"types":{{"EIP712Domain":[{{"name":"name","type":"string"}},{{"name":"version","type":"string"}},{{"name":"salt","type":"bytes32"}}],"{}":{}}},"#,
+                r#""primaryType":"{}","domain":{{"name":"{}","version":"{}","salt":"{}"}},"message":{}}}"#,
+            ),
+            primary,
+            fields,
+            primary,
+            Self::NAME,
+            utils::hex(typed.version),
+            utils::hex(&typed.salt),
+            values,
+        ))
+    }
+}
+
+impl SignatureScheme for Eip712 {
+    type SecretKey = SecretKey;
+    type Address = Address;
+    type Signature = Signature;
+
+    const ADDRESS_SIZE: usize = size_of::<Address>();
+    const SIGNATURE_SIZE: usize = size_of::<Signature>();
+
+    fn secret_key(seed: &[u8; 32]) -> Result<Self::SecretKey, crate::Error> {
+        super::Secp256k1::secret_key(seed)
+    }
+
+    fn address(secret: &Self::SecretKey) -> Address {
+        super::Secp256k1::address(secret)
+    }
+
+    fn sign(message: &[u8], secret: &Self::SecretKey) -> Result<Self::Signature, crate::Error> {
+        Ok(super::sign_digest(&Self::digest(message)?, secret))
+    }
+
+    fn recover(message: &[u8], signature: &[u8]) -> Result<Address, crate::Error> {
+        super::recover_digest(&Self::digest(message)?, signature)
+    }
+
+    fn encode(message: &Message<'_>) -> Vec<u8> {
+        let (domain, kind) = match message {
+            Message::Proof { domain, .. } => (domain, PROOF),
+            Message::Diff { domain, .. } => (domain, DIFF),
+            Message::Certify { domain, .. } => (domain, CERTIFY),
+            Message::Approve { domain, .. } => (domain, APPROVE),
+            Message::Result { domain, .. } => (domain, RESULT),
+        };
+
+        let mut data = Vec::new();
+
+        utils::write_u32_usize(&mut data, domain.version.len()).unwrap();
+        data.extend(domain.version);
+        data.extend(&domain.salt);
+        data.push(kind);
+
+        match message {
+            Message::Proof { state, actions, .. } => {
+                utils::write_u32_usize(&mut data, state.len()).unwrap();
+                data.extend(*state);
+                data.extend(*actions);
+            }
+            Message::Diff {
+                proof,
+                actions,
+                proof_signature,
+                ..
+            } => {
+                data.extend(*proof);
+                utils::write_u32_usize(&mut data, actions.len()).unwrap();
+                data.extend(*actions);
+                data.extend(*proof_signature);
+            }
+            Message::Certify {
+                challenge,
+                subkey,
+                expiry,
+                ..
+            } => {
+                let expiry = expiry.unwrap_or_default();
+
+                data.extend(*subkey);
+                utils::write_u32_usize(&mut data, expiry.len()).unwrap();
+                data.extend(expiry);
+                data.extend(challenge.as_bytes());
+            }
+            Message::Approve {
+                approval,
+                player,
+                subkey,
+                ..
+            } => {
+                data.extend(*player);
+                data.extend(*subkey);
+                data.extend(approval.as_bytes());
+            }
+            Message::Result { proof, outcome, .. } => {
+                data.extend(*proof);
+                data.extend(outcome.as_bytes());
+            }
+        }
+
+        data
+    }
+}
+
+struct Typed<'a> {
+    version: &'a [u8],
+    salt: Hash,
+    message: TypedMessage<'a>,
+}
+
+enum TypedMessage<'a> {
+    Proof {
+        state: &'a [u8],
+        actions: &'a [u8],
+    },
+    Diff {
+        proof: Hash,
+        actions: &'a [u8],
+        proof_signature: &'a [u8],
+    },
+    Certify {
+        challenge: &'a str,
+        subkey: Address,
+        expiry: &'a [u8],
+    },
+    Approve {
+        approval: &'a str,
+        player: Address,
+        subkey: Address,
+    },
+    Result {
+        proof: Hash,
+        outcome: &'a str,
+    },
+}
+
+impl<'a> Typed<'a> {
+    fn decode(data: &'a [u8]) -> Result<Self, crate::Error> {
+        Self::decode_unchecked(data).map_err(crate::Error::decoding)
+    }
+
+    fn decode_unchecked(mut data: &'a [u8]) -> Result<Self, crate::Error> {
+        let size = utils::read_u32_usize(&mut data)?;
+        let version = take(&mut data, size)?;
+        let salt = take(&mut data, size_of::<Hash>())?.try_into().unwrap();
+
+        let message = match take(&mut data, 1)?[0] {
+            PROOF => {
+                let size = utils::read_u32_usize(&mut data)?;
+
+                TypedMessage::Proof {
+                    state: take(&mut data, size)?,
+                    actions: data,
+                }
+            }
+            DIFF => {
+                let proof = take(&mut data, size_of::<Hash>())?.try_into().unwrap();
+                let size = utils::read_u32_usize(&mut data)?;
+
+                TypedMessage::Diff {
+                    proof,
+                    actions: take(&mut data, size)?,
+                    proof_signature: data,
+                }
+            }
+            CERTIFY => {
+                let subkey = take(&mut data, size_of::<Address>())?.try_into().unwrap();
+                let size = utils::read_u32_usize(&mut data)?;
+
+                TypedMessage::Certify {
+                    subkey,
+                    expiry: take(&mut data, size)?,
+                    challenge: core::str::from_utf8(data).map_err(crate::Error::decode)?,
+                }
+            }
+            APPROVE => TypedMessage::Approve {
+                player: take(&mut data, size_of::<Address>())?.try_into().unwrap(),
+                subkey: take(&mut data, size_of::<Address>())?.try_into().unwrap(),
+                approval: core::str::from_utf8(data).map_err(crate::Error::decode)?,
+            },
+            RESULT => TypedMessage::Result {
+                proof: take(&mut data, size_of::<Hash>())?.try_into().unwrap(),
+                outcome: core::str::from_utf8(data).map_err(crate::Error::decode)?,
+            },
+            kind => return Err(crate::forbidden!(format!("kind == {}", kind))),
+        };
+
+        Ok(Self {
+            version,
+            salt,
+            message,
+        })
+    }
+
+    fn domain_separator(&self) -> Hash {
+        keccak256(
+            &[
+                &keccak256(DOMAIN_TYPE.as_bytes())[..],
+                &keccak256(Eip712::NAME.as_bytes()),
+                &keccak256(utils::hex(self.version).as_bytes()),
+                &self.salt,
+            ]
+            .concat(),
+        )
+    }
+
+    fn struct_hash(&self) -> Hash {
+        match &self.message {
+            TypedMessage::Proof { state, actions } => keccak256(
+                &[
+                    &keccak256(PROOF_TYPE.as_bytes())[..],
+                    &keccak256(state),
+                    &keccak256(actions),
+                ]
+                .concat(),
+            ),
+            TypedMessage::Diff {
+                proof,
+                actions,
+                proof_signature,
+            } => keccak256(
+                &[
+                    &keccak256(DIFF_TYPE.as_bytes())[..],
+                    proof,
+                    &keccak256(actions),
+                    &keccak256(proof_signature),
+                ]
+                .concat(),
+            ),
+            TypedMessage::Certify {
+                challenge,
+                subkey,
+                expiry,
+            } => keccak256(
+                &[
+                    &keccak256(CERTIFY_TYPE.as_bytes())[..],
+                    &keccak256(challenge.as_bytes()),
+                    &word(subkey),
+                    &keccak256(expiry),
+                ]
+                .concat(),
+            ),
+            TypedMessage::Approve {
+                approval,
+                player,
+                subkey,
+            } => keccak256(
+                &[
+                    &keccak256(APPROVE_TYPE.as_bytes())[..],
+                    &keccak256(approval.as_bytes()),
+                    &word(player),
+                    &word(subkey),
+                ]
+                .concat(),
+            ),
+            TypedMessage::Result { proof, outcome } => keccak256(
+                &[
+                    &keccak256(RESULT_TYPE.as_bytes())[..],
+                    proof,
+                    &keccak256(outcome.as_bytes()),
+                ]
+                .concat(),
+            ),
+        }
+    }
+}
+
+/// Consumes `size` bytes from `data`.
+fn take<'a>(data: &mut &'a [u8], size: usize) -> Result<&'a [u8], crate::Error> {
+    crate::forbid!(data.len() < size);
+
+    let bytes = &data[..size];
+
+    *data = &data[size..];
+
+    Ok(bytes)
+}
+
+/// Left-pads an address to a 32-byte ABI word.
+fn word(address: &Address) -> Hash {
+    let mut word = Hash::default();
+    word[size_of::<Hash>() - size_of::<Address>()..].copy_from_slice(address);
+    word
+}
+
+/// Escapes a string for inclusion in a JSON string literal.
+fn escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+
+    for character in string.chars() {
+        match character {
+            '"' => escaped += "\\\"",
+            '\\' => escaped += "\\\\",
+            character if character.is_control() => {
+                escaped += &format!("\\u{:04x}", u32::from(character))
+            }
+            character => escaped.push(character),
+        }
+    }
+
+    escaped
+}
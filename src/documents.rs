@@ -0,0 +1,401 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Structured serde documents for proofs, root proofs, diffs and actions
+//!
+//! Documents are decoded by rebuilding the binary representation and running the binary decoder,
+//! so every signature is checked.
+//! Authors are optional in input documents, but must match the recovered signers if present.
+
+use {
+    crate::{crypto, utils, Diff, Error, Player, Proof, ProofAction, RootProof, State},
+    alloc::{format, string::String, vec::Vec},
+    core::convert::TryFrom,
+    crypto::SignatureScheme,
+    serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer},
+};
+
+impl<S: State> Serialize for RootProof<S> {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        RootProofDocument::new(self)
+            .map_err(ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de, S: State> Deserialize<'de> for RootProof<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RootProofDocument::deserialize(deserializer)?
+            .decode()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl<S: State> Serialize for Proof<S> {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        ProofDocument::new(self)
+            .map_err(ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de, S: State> Deserialize<'de> for Proof<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ProofDocument::deserialize(deserializer)?
+            .decode()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl<S: State> Serialize for Diff<S> {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        DiffDocument {
+            id: Hex(crate::ID::serialize(&self.id)),
+            proof: Hex(self.proof.to_vec()),
+            actions: self
+                .actions
+                .iter()
+                .map(ProofActionDocument::new)
+                .collect::<Result<_, _>>()
+                .map_err(ser::Error::custom)?,
+            proof_signature: Hex(self.proof_signature.as_ref().to_vec()),
+            signature: Hex(self.signature.as_ref().to_vec()),
+            author: Some(Hex(self.author.as_ref().to_vec())),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, S: State> Deserialize<'de> for Diff<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DiffDocument::deserialize(deserializer)?
+            .decode()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl<S: State> Serialize for ProofAction<S> {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        ProofActionDocument::new(self)
+            .map_err(ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de, S: State> Deserialize<'de> for ProofAction<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ProofAction::deserialize(
+            &ProofActionDocument::deserialize(deserializer)?
+                .encode()
+                .map_err(de::Error::custom)?,
+        )
+        .map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RootProofDocument {
+    state: Hex,
+    actions: Vec<ProofActionDocument>,
+    signature: Hex,
+    #[serde(default)]
+    author: Option<Hex>,
+}
+
+impl RootProofDocument {
+    fn new<S: State>(root: &RootProof<S>) -> Result<Self, Error> {
+        Ok(Self {
+            state: Hex(root.state.serialize().unwrap()),
+            actions: root
+                .actions
+                .iter()
+                .map(ProofActionDocument::new)
+                .collect::<Result<_, _>>()?,
+            signature: Hex(root.signature.as_ref().to_vec()),
+            author: Some(Hex(root.author.as_ref().to_vec())),
+        })
+    }
+
+    fn decode<S: State>(&self) -> Result<RootProof<S>, Error> {
+        let mut data = Vec::new();
+
+        utils::write_u32_usize(&mut data, self.state.0.len())?;
+        data.extend(&self.state.0);
+        encode_actions(&mut data, &self.actions)?;
+        data.extend(&self.signature.0);
+
+        let root = RootProof::<S>::deserialize(&data, false)?;
+
+        check_author(&self.author, root.author.as_ref())?;
+
+        Ok(root)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProofDocument {
+    root: RootProofDocument,
+    state: Hex,
+    actions: Vec<ProofActionDocument>,
+    ranges: Vec<Option<RangeDocument>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RangeDocument {
+    start: usize,
+    end: usize,
+    signature: Hex,
+    #[serde(default)]
+    author: Option<Hex>,
+}
+
+impl ProofDocument {
+    fn new<S: State>(proof: &Proof<S>) -> Result<Self, Error> {
+        Ok(Self {
+            root: RootProofDocument::new(&proof.root)?,
+            state: Hex(proof
+                .proofs
+                .iter()
+                .filter_map(Option::as_ref)
+                .find(|proof| proof.range.start == 0)
+                .ok_or_else(|| Error::decode("no range starts at the root"))?
+                .state
+                .serialize()
+                .ok_or_else(|| Error::decode("state cannot be serialized"))?),
+            actions: proof
+                .actions
+                .iter()
+                .map(ProofActionDocument::new)
+                .collect::<Result<_, _>>()?,
+            ranges: proof
+                .proofs
+                .iter()
+                .map(|player| {
+                    player
+                        .as_ref()
+                        .map(|player| {
+                            Ok(RangeDocument {
+                                start: player.range.start,
+                                end: player.range.end,
+                                signature: Hex(player.signature.as_ref().to_vec()),
+                                author: Some(Hex(S::Scheme::recover(
+                                    &player.message(&proof.actions),
+                                    player.signature.as_ref(),
+                                )?
+                                .as_ref()
+                                .to_vec())),
+                            })
+                        })
+                        .transpose()
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+
+    fn decode<S: State>(&self) -> Result<Proof<S>, Error> {
+        let mut data = Vec::new();
+
+        utils::write_u32_usize(&mut data, self.state.0.len())?;
+        data.extend(&self.state.0);
+        encode_actions(&mut data, &self.actions)?;
+
+        for range in &self.ranges {
+            utils::write_u8_bool(&mut data, range.is_some());
+
+            if let Some(range) = range {
+                utils::write_u32_usize(&mut data, range.start)?;
+                utils::write_u32_usize(&mut data, range.end)?;
+                data.extend(&range.signature.0);
+            }
+        }
+
+        let mut proof = Proof::<S>::new(self.root.decode()?);
+
+        proof.deserialize(&data, false)?;
+
+        for (range, player) in self.ranges.iter().zip(&proof.proofs) {
+            if let (Some(range), Some(player)) = (range, player) {
+                check_author(
+                    &range.author,
+                    S::Scheme::recover(&player.message(&proof.actions), player.signature.as_ref())?
+                        .as_ref(),
+                )?;
+            }
+        }
+
+        Ok(proof)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DiffDocument {
+    id: Hex,
+    proof: Hex,
+    actions: Vec<ProofActionDocument>,
+    proof_signature: Hex,
+    signature: Hex,
+    #[serde(default)]
+    author: Option<Hex>,
+}
+
+impl DiffDocument {
+    fn decode<S: State>(&self) -> Result<Diff<S>, Error> {
+        let mut data = self.proof.0.clone();
+
+        encode_actions(&mut data, &self.actions)?;
+        data.extend(&self.proof_signature.0);
+        data.extend(&self.signature.0);
+
+        let mut id = &self.id.0[..];
+        let id = <S::ID as crate::ID>::deserialize(&mut id).map_err(Error::decode)?;
+
+        let diff = Diff::<S>::deserialize(&data, &id)?;
+
+        check_author(&self.author, diff.author.as_ref())?;
+
+        Ok(diff)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProofActionDocument {
+    player: Option<Player>,
+    #[serde(rename = "type")]
+    kind: ActionKind,
+    data: Hex,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+enum ActionKind {
+    Play,
+    Certify,
+    Approve,
+    Revoke,
+    Resign,
+    OfferDraw,
+    AcceptDraw,
+    Abort,
+    Checkpoint,
+}
+
+impl TryFrom<u8> for ActionKind {
+    type Error = Error;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        match byte {
+            0 => Ok(Self::Play),
+            1 => Ok(Self::Certify),
+            2 => Ok(Self::Approve),
+            3 => Ok(Self::Revoke),
+            4 => Ok(Self::Resign),
+            5 => Ok(Self::OfferDraw),
+            6 => Ok(Self::AcceptDraw),
+            7 => Ok(Self::Abort),
+            8 => Ok(Self::Checkpoint),
+            byte => Err(Error::decode(format!("byte == {}", byte))),
+        }
+    }
+}
+
+impl From<ActionKind> for u8 {
+    fn from(kind: ActionKind) -> Self {
+        match kind {
+            ActionKind::Play => 0,
+            ActionKind::Certify => 1,
+            ActionKind::Approve => 2,
+            ActionKind::Revoke => 3,
+            ActionKind::Resign => 4,
+            ActionKind::OfferDraw => 5,
+            ActionKind::AcceptDraw => 6,
+            ActionKind::Abort => 7,
+            ActionKind::Checkpoint => 8,
+        }
+    }
+}
+
+impl ProofActionDocument {
+    fn new<S: State>(action: &ProofAction<S>) -> Result<Self, Error> {
+        let data = action.serialize();
+
+        Ok(Self {
+            player: data[0].checked_sub(1),
+            kind: ActionKind::try_from(data[1])?,
+            data: Hex(data[2..].to_vec()),
+        })
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::with_capacity(2 + self.data.0.len());
+
+        utils::write_u8(
+            &mut data,
+            match self.player {
+                None => 0,
+                Some(player) => player
+                    .checked_add(1)
+                    .ok_or_else(|| Error::decode(format!("player == {}", player)))?,
+            },
+        );
+
+        utils::write_u8(&mut data, self.kind.into());
+        data.extend(&self.data.0);
+
+        Ok(data)
+    }
+}
+
+fn encode_actions(data: &mut Vec<u8>, actions: &[ProofActionDocument]) -> Result<(), Error> {
+    utils::write_u32_usize(data, actions.len())?;
+
+    for action in actions {
+        let action = action.encode()?;
+
+        utils::write_u32_usize(data, action.len())?;
+        data.extend(action);
+    }
+
+    Ok(())
+}
+
+fn check_author(expected: &Option<Hex>, actual: &[u8]) -> Result<(), Error> {
+    if let Some(expected) = expected {
+        crate::forbid!(expected.0[..] != actual[..]);
+    }
+
+    Ok(())
+}
+
+struct Hex(Vec<u8>);
+
+impl Serialize for Hex {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        serializer.serialize_str(&utils::hex(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Hex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        utils::unhex(&String::deserialize(deserializer)?)
+            .map(Hex)
+            .map_err(de::Error::custom)
+    }
+}
@@ -16,41 +16,259 @@
 
 //! Error utilities
 
-use {alloc::string::String, core::fmt::Debug};
+use {
+    alloc::{
+        boxed::Box,
+        string::{String, ToString},
+        vec::Vec,
+    },
+    core::fmt::{Display, Formatter},
+};
 
-#[derive(Debug)]
+/// Error
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
-    Soft(String),
-    Hard(String),
+    /// Malformed binary data.
+    Decode {
+        /// A description of what could not be decoded.
+        reason: String,
+    },
+
+    /// Data too large for its binary representation.
+    Encode {
+        /// A description of what could not be encoded.
+        reason: String,
+    },
+
+    /// ABI version mismatch.
+    VersionMismatch {
+        /// The version of this implementation.
+        expected: Vec<u8>,
+
+        /// The version found in the data.
+        actual: Vec<u8>,
+    },
+
+    /// Signature that could not be produced or recovered.
+    BadSignature {
+        /// A description of the failure.
+        reason: String,
+    },
+
+    /// Diff constructed on a different proof.
+    WrongProofHash {
+        /// The digest of the proof the diff was applied to.
+        expected: crate::crypto::Hash,
+
+        /// The digest of the proof the diff was constructed on.
+        actual: crate::crypto::Hash,
+    },
+
+    /// Violated precondition.
+    Forbidden {
+        /// The module containing the check.
+        module: String,
+
+        /// The line of the check.
+        line: u32,
+
+        /// The column of the check.
+        column: u32,
+
+        /// The condition that held.
+        condition: String,
+    },
+
+    /// Offence attributable to the author of a diff.
+    Slashable {
+        /// The address of the author of the offending diff.
+        author: Vec<u8>,
+
+        /// The violated rule.
+        rule: crate::Offence,
+
+        /// The offence.
+        offence: Box<Error>,
+    },
+
+    /// Move or match status change in a match that has ended.
+    Terminated {
+        /// The status of the match.
+        status: crate::MatchStatus,
+    },
+
+    /// Action rejected by domain-specific logic.
+    Rejected {
+        /// The player performing the action, or [None] if performed by the owner.
+        player: Option<crate::Player>,
+
+        /// The reason given by the domain-specific logic.
+        reason: String,
+    },
 }
 
+impl Error {
+    /// Constructs a decode error.
+    pub fn decode(reason: impl Display) -> Self {
+        Self::Decode {
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Constructs an encode error.
+    pub fn encode(reason: impl Display) -> Self {
+        Self::Encode {
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Constructs a bad signature error.
+    pub fn bad_signature(reason: impl Display) -> Self {
+        Self::BadSignature {
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Constructs a rejection by domain-specific logic.
+    pub fn rejected(player: Option<crate::Player>, reason: impl Display) -> Self {
+        Self::Rejected {
+            player,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Attributes the error to the author of a diff as a violation of a rule.
+    pub fn slashable(self, author: impl AsRef<[u8]>, rule: crate::Offence) -> Self {
+        match self {
+            Self::Slashable { .. } => self,
+            offence => Self::Slashable {
+                author: author.as_ref().to_vec(),
+                rule,
+                offence: Box::new(offence),
+            },
+        }
+    }
+
+    /// Checks if the error is attributable to the author of a diff.
+    pub fn is_slashable(&self) -> bool {
+        matches!(self, Self::Slashable { .. })
+    }
+
+    /// Gets a machine-readable name for the kind of error.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Decode { .. } => "decode",
+            Self::Encode { .. } => "encode",
+            Self::VersionMismatch { .. } => "version-mismatch",
+            Self::BadSignature { .. } => "bad-signature",
+            Self::WrongProofHash { .. } => "wrong-proof-hash",
+            Self::Forbidden { .. } => "forbidden",
+            Self::Slashable { .. } => "slashable",
+            Self::Terminated { .. } => "terminated",
+            Self::Rejected { .. } => "rejected",
+        }
+    }
+
+    pub(crate) fn decoding(self) -> Self {
+        match self {
+            Self::Forbidden { .. } | Self::Rejected { .. } => Self::decode(self),
+            error => error,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Decode { reason } => write!(f, "decode: {}", reason),
+            Self::Encode { reason } => write!(f, "encode: {}", reason),
+            Self::VersionMismatch { expected, actual } => write!(
+                f,
+                "version mismatch: {} != {}",
+                crate::utils::hex(actual),
+                crate::utils::hex(expected)
+            ),
+            Self::BadSignature { reason } => write!(f, "bad signature: {}", reason),
+            Self::WrongProofHash { expected, actual } => write!(
+                f,
+                "diff.proof != self.hash: {} != {}",
+                crate::utils::hex(actual),
+                crate::utils::hex(expected)
+            ),
+            Self::Forbidden {
+                module,
+                line,
+                column,
+                condition,
+            } => write!(f, "{}:{}:{}: {}", module, line, column, condition),
+            Self::Slashable {
+                author,
+                rule,
+                offence,
+            } => write!(
+                f,
+                "slashable ({}, {}): {}",
+                crate::crypto::format_address(author),
+                rule.name(),
+                offence
+            ),
+            Self::Terminated { status } => write!(f, "terminated: {:?}", status),
+            Self::Rejected {
+                player: Some(player),
+                reason,
+            } => write!(f, "rejected (player {}): {}", player, reason),
+            Self::Rejected {
+                player: None,
+                reason,
+            } => write!(f, "rejected (owner): {}", reason),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
 impl From<Error> for String {
     fn from(error: Error) -> Self {
-        match error {
-            Error::Soft(string) | Error::Hard(string) => string,
-        }
+        error.to_string()
     }
 }
 
-impl From<String> for Error {
-    fn from(string: String) -> Self {
-        Self::Soft(string)
+#[cfg(feature = "std")]
+impl From<Error> for wasm_bindgen::JsValue {
+    fn from(error: Error) -> Self {
+        let value = js_sys::Error::new(&error.to_string());
+
+        drop(js_sys::Reflect::set(
+            &value,
+            &"kind".into(),
+            &error.kind().into(),
+        ));
+
+        value.into()
     }
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! forbidden {
+    ($condition:expr) => {
+        $crate::Error::Forbidden {
+            module: module_path!().into(),
+            line: line!(),
+            column: column!(),
+            condition: $condition.into(),
+        }
+    };
+}
+
 #[doc(hidden)]
 #[macro_export]
 macro_rules! forbid {
     ($condition:expr) => {
         if $condition {
-            return Err(format!(
-                "{}:{}:{}: {}",
-                module_path!(),
-                line!(),
-                column!(),
-                stringify!($condition)
-            )
-            .into());
+            return Err($crate::forbidden!(stringify!($condition)).into());
         }
     };
 }
@@ -58,15 +276,9 @@ macro_rules! forbid {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! slash {
-    ($condition:expr) => {
+    ($author:expr, $rule:expr, $condition:expr) => {
         if $condition {
-            return Err($crate::error::Error::Hard(format!(
-                "{}:{}:{}: {}",
-                module_path!(),
-                line!(),
-                column!(),
-                stringify!($condition)
-            )));
+            return Err($crate::forbidden!(stringify!($condition)).slashable($author, $rule));
         }
     };
 }
@@ -0,0 +1,360 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Slashing evidence
+
+use {
+    crate::{utils, Diff, Error, Proof, RootProof, State, ID},
+    alloc::vec::Vec,
+    core::mem::size_of,
+};
+
+/// Rule whose violation is attributable to the author of a diff
+///
+/// Each rule has a stable code, so evidence remains verifiable across builds.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Offence {
+    /// The owner issued a checkpoint the proof doesn't allow, or that misrepresents the players'
+    /// signatures.
+    Checkpoint,
+
+    /// The author performed an action on behalf of someone else.
+    Impersonation,
+
+    /// The author performed an action that the state rejects.
+    InvalidAction,
+
+    /// The proof signature wasn't produced by the author.
+    ProofSignature,
+
+    /// The author signed two conflicting diffs on the same proof.
+    Equivocation,
+}
+
+impl Offence {
+    /// Gets a machine-readable name for the offence.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Checkpoint => "checkpoint",
+            Self::Impersonation => "impersonation",
+            Self::InvalidAction => "invalid-action",
+            Self::ProofSignature => "proof-signature",
+            Self::Equivocation => "equivocation",
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            Self::Checkpoint => 0,
+            Self::Impersonation => 1,
+            Self::InvalidAction => 2,
+            Self::ProofSignature => 3,
+            Self::Equivocation => 4,
+        }
+    }
+
+    fn from_code(code: u8) -> Result<Self, Error> {
+        match code {
+            0 => Ok(Self::Checkpoint),
+            1 => Ok(Self::Impersonation),
+            2 => Ok(Self::InvalidAction),
+            3 => Ok(Self::ProofSignature),
+            4 => Ok(Self::Equivocation),
+            code => Err(Error::decode(format_args!("offence == {}", code))),
+        }
+    }
+}
+
+/// Self-contained proof that the author of a diff committed a slashable offence
+///
+/// See [SlashingEvidence::verify].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlashingEvidence {
+    root: Vec<u8>,
+    proof: Vec<u8>,
+    diff: Vec<u8>,
+    offender: Vec<u8>,
+    rule: Offence,
+}
+
+impl SlashingEvidence {
+    /// Constructs evidence that applying `diff` to `proof` is a slashable offence.
+    ///
+    /// Returns [None] if `diff` is not slashable.
+    pub fn new<S: State>(proof: &Proof<S>, diff: &Diff<S>) -> Option<Self> {
+        match proof.clone().apply(diff) {
+            Err(Error::Slashable { author, rule, .. }) => Some(Self {
+                root: proof.root.serialize(),
+                proof: proof.serialize(),
+                diff: diff.serialize(),
+                offender: author,
+                rule,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Constructs evidence from its binary representation.
+    ///
+    /// `data` must have been constructed using [SlashingEvidence::serialize].
+    pub fn deserialize(data: &[u8]) -> Result<Self, Error> {
+        Self::deserialize_unchecked(data).map_err(Error::decoding)
+    }
+
+    /// Generates a binary representation that can be used to reconstruct the evidence.
+    ///
+    /// See [SlashingEvidence::deserialize].
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(
+            size_of::<u32>()
+                + self.root.len()
+                + size_of::<u32>()
+                + self.proof.len()
+                + size_of::<u32>()
+                + self.diff.len()
+                + size_of::<u32>()
+                + self.offender.len()
+                + size_of::<u8>(),
+        );
+
+        for bytes in [&self.root, &self.proof, &self.diff, &self.offender].iter() {
+            utils::write_u32_usize(&mut data, bytes.len()).unwrap();
+            data.extend(bytes.iter());
+        }
+        utils::write_u8(&mut data, self.rule.code());
+
+        data
+    }
+
+    /// Independently confirms the offence.
+    ///
+    /// Succeeds only if the diff was authored by the offender, and applying it to the proof
+    /// violates the recorded rule.
+    pub fn verify<S: State>(&self, no_version_check: bool) -> Result<(), Error> {
+        let root = RootProof::<S>::deserialize(&self.root, no_version_check)?;
+
+        let mut proof = Proof::new(root);
+
+        proof.deserialize(&self.proof, no_version_check)?;
+
+        let diff = Diff::<S>::deserialize(&self.diff, proof.state().id())?;
+
+        crate::forbid!(diff.author.as_ref() != &self.offender[..]);
+
+        match proof.apply(&diff) {
+            Err(Error::Slashable { author, rule, .. }) => {
+                crate::forbid!(author != self.offender);
+                crate::forbid!(rule != self.rule);
+
+                Ok(())
+            }
+            _ => Err(crate::forbidden!("!proof.apply(&diff).is_slashable()")),
+        }
+    }
+
+    /// Gets the binary representation of the root proof.
+    pub fn root(&self) -> &[u8] {
+        &self.root
+    }
+
+    /// Gets the binary representation of the proof before the diff.
+    pub fn proof(&self) -> &[u8] {
+        &self.proof
+    }
+
+    /// Gets the binary representation of the offending diff.
+    pub fn diff(&self) -> &[u8] {
+        &self.diff
+    }
+
+    /// Gets the address of the author of the offending diff.
+    pub fn offender(&self) -> &[u8] {
+        &self.offender
+    }
+
+    /// Gets the violated rule.
+    pub fn rule(&self) -> Offence {
+        self.rule
+    }
+
+    fn deserialize_unchecked(mut data: &[u8]) -> Result<Self, Error> {
+        let mut read = || {
+            let size = utils::read_u32_usize(&mut data)?;
+
+            crate::forbid!(data.len() < size);
+
+            let bytes = data[..size].to_vec();
+
+            data = &data[size..];
+
+            Ok(bytes)
+        };
+
+        let root = read()?;
+        let proof = read()?;
+        let diff = read()?;
+        let offender = read()?;
+
+        crate::forbid!(data.len() != size_of::<u8>());
+
+        let rule = Offence::from_code(data[0])?;
+
+        Ok(Self {
+            root,
+            proof,
+            diff,
+            offender,
+            rule,
+        })
+    }
+}
+
+/// Self-contained proof that an author signed two conflicting diffs on the same proof
+///
+/// See [EquivocationEvidence::verify].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EquivocationEvidence {
+    id: Vec<u8>,
+    first: Vec<u8>,
+    second: Vec<u8>,
+    offender: Vec<u8>,
+}
+
+impl EquivocationEvidence {
+    /// Constructs evidence that `first` and `second` conflict.
+    ///
+    /// Returns [None] unless both diffs have the same match, proof hash and author, but different
+    /// digests.
+    /// See [Diff::digest].
+    pub fn new<S: State>(first: &Diff<S>, second: &Diff<S>) -> Option<Self> {
+        if first.id != second.id
+            || first.proof != second.proof
+            || first.author != second.author
+            || first.digest() == second.digest()
+        {
+            return None;
+        }
+
+        Some(Self {
+            id: first.id.serialize(),
+            first: first.serialize(),
+            second: second.serialize(),
+            offender: first.author.as_ref().to_vec(),
+        })
+    }
+
+    /// Constructs evidence from its binary representation.
+    ///
+    /// `data` must have been constructed using [EquivocationEvidence::serialize].
+    pub fn deserialize(data: &[u8]) -> Result<Self, Error> {
+        Self::deserialize_unchecked(data).map_err(Error::decoding)
+    }
+
+    /// Generates a binary representation that can be used to reconstruct the evidence.
+    ///
+    /// See [EquivocationEvidence::deserialize].
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(
+            size_of::<u32>()
+                + self.id.len()
+                + size_of::<u32>()
+                + self.first.len()
+                + size_of::<u32>()
+                + self.second.len()
+                + size_of::<u32>()
+                + self.offender.len(),
+        );
+
+        for bytes in [&self.id, &self.first, &self.second, &self.offender].iter() {
+            utils::write_u32_usize(&mut data, bytes.len()).unwrap();
+            data.extend(bytes.iter());
+        }
+
+        data
+    }
+
+    /// Independently confirms the equivocation.
+    ///
+    /// Succeeds only if both diffs were authored by the offender on the same proof of the match,
+    /// but have different digests, so the same diff in two formats isn't an equivocation.
+    pub fn verify<S: State>(&self) -> Result<(), Error> {
+        let mut data = &self.id[..];
+        let id = S::ID::deserialize(&mut data).map_err(Error::decode)?;
+
+        crate::forbid!(!data.is_empty());
+
+        let first = Diff::<S>::deserialize(&self.first, &id)?;
+        let second = Diff::<S>::deserialize(&self.second, &id)?;
+
+        crate::forbid!(first.author.as_ref() != &self.offender[..]);
+        crate::forbid!(second.author.as_ref() != &self.offender[..]);
+        crate::forbid!(first.proof != second.proof);
+        crate::forbid!(first.digest() == second.digest());
+
+        Ok(())
+    }
+
+    /// Gets the binary representation of the identifier of the diffs' match.
+    ///
+    /// See [ID::serialize].
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    /// Gets the binary representation of the first diff.
+    pub fn first(&self) -> &[u8] {
+        &self.first
+    }
+
+    /// Gets the binary representation of the second diff.
+    pub fn second(&self) -> &[u8] {
+        &self.second
+    }
+
+    /// Gets the address of the author of both diffs.
+    pub fn offender(&self) -> &[u8] {
+        &self.offender
+    }
+
+    fn deserialize_unchecked(mut data: &[u8]) -> Result<Self, Error> {
+        let mut read = || {
+            let size = utils::read_u32_usize(&mut data)?;
+
+            crate::forbid!(data.len() < size);
+
+            let bytes = data[..size].to_vec();
+
+            data = &data[size..];
+
+            Ok(bytes)
+        };
+
+        let id = read()?;
+        let first = read()?;
+        let second = read()?;
+        let offender = read()?;
+
+        crate::forbid!(!data.is_empty());
+
+        Ok(Self {
+            id,
+            first,
+            second,
+            offender,
+        })
+    }
+}
@@ -0,0 +1,385 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Wire formats
+
+use {
+    crate::{crypto, utils, Error},
+    alloc::{borrow::Cow, vec::Vec},
+    core::{convert::TryFrom, mem::size_of},
+};
+
+/// Binary representation format of root proofs, proofs, diffs and stores
+///
+/// [Format::V1] is canonical: hashes and signatures are always computed over it.
+/// [Format::V2] is a compact encoding of the same data, with varint lengths and tighter action
+/// framing, prefixed with a format tag.
+///
+/// Decoders accept both formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Format {
+    /// Fixed-width `u32` lengths
+    V1 = 1,
+    /// Varint lengths and compact action headers
+    V2 = 2,
+}
+
+impl Format {
+    /// Formats supported by this implementation, oldest first
+    pub const SUPPORTED: [Self; 2] = [Self::V1, Self::V2];
+
+    /// Gets the newest format supported by both peers.
+    ///
+    /// `theirs` is the list of formats supported by the other peer.
+    /// Every peer supports [Format::V1].
+    pub fn negotiate(theirs: &[Self]) -> Self {
+        Self::SUPPORTED
+            .iter()
+            .rev()
+            .find(|format| theirs.contains(format))
+            .copied()
+            .unwrap_or(Self::V1)
+    }
+
+    /// Detects the format of a binary representation.
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(&V2_TAG) {
+            Self::V2
+        } else {
+            Self::V1
+        }
+    }
+}
+
+impl From<Format> for u8 {
+    fn from(format: Format) -> Self {
+        format as u8
+    }
+}
+
+impl TryFrom<u8> for Format {
+    type Error = Error;
+
+    fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        match byte {
+            1 => Ok(Self::V1),
+            2 => Ok(Self::V2),
+            byte => Err(Error::decode(alloc::format!("byte == {}", byte))),
+        }
+    }
+}
+
+/// Prefix of [Format::V2] binary representations
+///
+/// No [Format::V1] root proof, proof or store can begin with it without being at least 48 MiB.
+/// A [Format::V1] diff begins with a proof hash, which can: see [canonical].
+const V2_TAG: [u8; 4] = [0xff, 0xff, 0xff, 0x02];
+
+/// Number of low bits of a [Format::V2] action header holding the action kind
+///
+/// The remaining bits hold the size of the action's payload.
+const KIND_BITS: u32 = 4;
+
+const KIND_MASK: usize = (1 << KIND_BITS) - 1;
+
+#[derive(Clone, Copy)]
+pub(crate) enum Blob {
+    RootProof,
+    Proof,
+    Diff,
+    Store,
+}
+
+/// Re-encodes a canonical binary representation in the given format.
+pub(crate) fn encode(data: Vec<u8>, format: Format, blob: Blob, signature_size: usize) -> Vec<u8> {
+    match format {
+        Format::V1 => data,
+        Format::V2 => {
+            let mut encoded = V2_TAG.to_vec();
+
+            encoded.extend(transcode(&data, Format::V1, Format::V2, blob, signature_size).unwrap());
+
+            encoded
+        }
+    }
+}
+
+/// Gets the canonical binary representation of a blob in any supported format.
+///
+/// A blob beginning with the [Format::V2] tag is decoded as [Format::V2] if it can be, and is
+/// otherwise returned as is.
+/// Only a [Format::V1] diff whose proof hash begins with the tag can be misread this way, in which
+/// case its proof hash and signer change, so it is rejected rather than applied as another diff.
+pub(crate) fn canonical(data: &[u8], blob: Blob, signature_size: usize) -> Cow<'_, [u8]> {
+    strip_tag(data)
+        .and_then(|body| transcode(body, Format::V2, Format::V1, blob, signature_size).ok())
+        .map_or(Cow::Borrowed(data), Cow::Owned)
+}
+
+/// Gets the body of a [Format::V2] binary representation, or [None] if it lacks the tag.
+pub(crate) fn strip_tag(data: &[u8]) -> Option<&[u8]> {
+    data.strip_prefix(&V2_TAG[..])
+}
+
+fn transcode(
+    data: &[u8],
+    from: Format,
+    to: Format,
+    blob: Blob,
+    signature_size: usize,
+) -> Result<Vec<u8>, Error> {
+    let mut reader = Reader::new(data, from);
+    let mut writer = Writer::new(to, data.len());
+
+    match blob {
+        Blob::RootProof => root_proof(&mut reader, &mut writer, signature_size)?,
+        Blob::Proof => proof(&mut reader, &mut writer, signature_size)?,
+        Blob::Diff => diff(&mut reader, &mut writer, signature_size)?,
+        Blob::Store => store(&mut reader, &mut writer, signature_size)?,
+    }
+
+    crate::forbid!(!reader.data.is_empty());
+
+    Ok(writer.data)
+}
+
+fn root_proof(
+    reader: &mut Reader,
+    writer: &mut Writer,
+    signature_size: usize,
+) -> Result<(), Error> {
+    writer.sized(reader.sized()?)?;
+    actions(reader, writer)?;
+    writer.bytes(reader.bytes(signature_size)?);
+
+    Ok(())
+}
+
+fn proof(reader: &mut Reader, writer: &mut Writer, signature_size: usize) -> Result<(), Error> {
+    writer.sized(reader.sized()?)?;
+    actions(reader, writer)?;
+
+    while !reader.data.is_empty() {
+        let present = utils::read_u8_bool(&mut reader.data)?;
+
+        utils::write_u8_bool(&mut writer.data, present);
+
+        if present {
+            writer.length(reader.length()?)?;
+            writer.length(reader.length()?)?;
+            writer.bytes(reader.bytes(signature_size)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn diff(reader: &mut Reader, writer: &mut Writer, signature_size: usize) -> Result<(), Error> {
+    writer.bytes(reader.bytes(size_of::<crypto::Hash>())?);
+    actions(reader, writer)?;
+    writer.bytes(reader.bytes(signature_size + signature_size)?);
+
+    Ok(())
+}
+
+fn store(reader: &mut Reader, writer: &mut Writer, signature_size: usize) -> Result<(), Error> {
+    let tagged = reader.data.first() == Some(&crate::store::STORE_TAG);
+
+    if tagged {
+        utils::write_u8(&mut writer.data, utils::read_u8(&mut reader.data)?);
+    }
+
+    utils::write_u8(&mut writer.data, utils::read_u8(&mut reader.data)?);
+
+    let length = if tagged {
+        let length = reader.length()?;
+
+        writer.length(length)?;
+
+        length
+    } else {
+        2
+    };
+
+    secrets(reader, writer, length)?;
+    nested(reader, writer, Blob::RootProof, signature_size)?;
+    secrets(reader, writer, length)?;
+    nested(reader, writer, Blob::Proof, signature_size)?;
+
+    writer.bytes(reader.bytes(reader.data.len())?);
+
+    Ok(())
+}
+
+fn actions(reader: &mut Reader, writer: &mut Writer) -> Result<(), Error> {
+    let length = reader.length()?;
+
+    writer.length(length)?;
+
+    for _ in 0..length {
+        let (player, kind, payload) = reader.action()?;
+
+        writer.action(player, kind, payload)?;
+    }
+
+    Ok(())
+}
+
+fn secrets(reader: &mut Reader, writer: &mut Writer, length: usize) -> Result<(), Error> {
+    for _ in 0..length {
+        let present = utils::read_u8_bool(&mut reader.data)?;
+
+        utils::write_u8_bool(&mut writer.data, present);
+
+        if present {
+            writer.sized(reader.sized()?)?;
+            writer.sized(reader.sized()?)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn nested(
+    reader: &mut Reader,
+    writer: &mut Writer,
+    blob: Blob,
+    signature_size: usize,
+) -> Result<(), Error> {
+    writer.sized(&transcode(
+        reader.sized()?,
+        reader.format,
+        writer.format,
+        blob,
+        signature_size,
+    )?)
+}
+
+pub(crate) struct Reader<'a> {
+    pub(crate) data: &'a [u8],
+    format: Format,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8], format: Format) -> Self {
+        Self { data, format }
+    }
+
+    pub(crate) fn length(&mut self) -> Result<usize, Error> {
+        match self.format {
+            Format::V1 => utils::read_u32_usize(&mut self.data),
+            Format::V2 => utils::read_varint_usize(&mut self.data),
+        }
+    }
+
+    fn bytes(&mut self, size: usize) -> Result<&'a [u8], Error> {
+        crate::forbid!(self.data.len() < size);
+
+        let (bytes, data) = self.data.split_at(size);
+
+        self.data = data;
+
+        Ok(bytes)
+    }
+
+    fn sized(&mut self) -> Result<&'a [u8], Error> {
+        let size = self.length()?;
+
+        self.bytes(size)
+    }
+
+    /// Consumes an action, returning its encoded player, kind and payload.
+    pub(crate) fn action(&mut self) -> Result<(u8, u8, &'a [u8]), Error> {
+        match self.format {
+            Format::V1 => {
+                let mut action = self.sized()?;
+
+                let player = utils::read_u8(&mut action)?;
+                let kind = utils::read_u8(&mut action)?;
+
+                Ok((player, kind, action))
+            }
+            Format::V2 => {
+                let player = utils::read_varint_usize(&mut self.data)?;
+
+                crate::forbid!(player > usize::from(u8::MAX));
+
+                let header = utils::read_varint_usize(&mut self.data)?;
+
+                Ok((
+                    player as u8,
+                    (header & KIND_MASK) as u8,
+                    self.bytes(header >> KIND_BITS)?,
+                ))
+            }
+        }
+    }
+}
+
+pub(crate) struct Writer {
+    pub(crate) data: Vec<u8>,
+    format: Format,
+}
+
+impl Writer {
+    pub(crate) fn new(format: Format, capacity: usize) -> Self {
+        Self {
+            data: Vec::with_capacity(capacity),
+            format,
+        }
+    }
+
+    pub(crate) fn length(&mut self, length: usize) -> Result<(), Error> {
+        match self.format {
+            Format::V1 => utils::write_u32_usize(&mut self.data, length),
+            Format::V2 => utils::write_varint_usize(&mut self.data, length),
+        }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend(bytes);
+    }
+
+    fn sized(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.length(bytes.len())?;
+        self.bytes(bytes);
+
+        Ok(())
+    }
+
+    pub(crate) fn action(&mut self, player: u8, kind: u8, payload: &[u8]) -> Result<(), Error> {
+        match self.format {
+            Format::V1 => {
+                utils::write_u32_usize(&mut self.data, 2 + payload.len())?;
+                utils::write_u8(&mut self.data, player);
+                utils::write_u8(&mut self.data, kind);
+            }
+            Format::V2 => {
+                crate::forbid!(usize::from(kind) > KIND_MASK);
+
+                utils::write_varint_usize(&mut self.data, player.into())?;
+                utils::write_varint_usize(
+                    &mut self.data,
+                    payload.len() << KIND_BITS | usize::from(kind),
+                )?;
+            }
+        }
+
+        self.bytes(payload);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,678 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Game-agnostic inspection of binary representations
+//!
+//! Only the framing is decoded: states and [Play](crate::PlayerAction::Play) actions are shown as
+//! raw bytes.
+//! Signers are recovered using the given [SignatureScheme], with the default
+//! [State::challenge](crate::State::challenge) and [State::approval](crate::State::approval).
+//! Schemes that sign the message domain, such as [Eip712](crate::crypto::Eip712), also need the
+//! match identifier from the [Context].
+//!
+//! Player proof ranges that don't begin at the proof's first action are signed over a state that
+//! isn't part of the binary representation.
+//! Their signers are taken from the inspected diffs that signed them instead.
+
+use {
+    crate::{
+        crypto::{self, SignatureScheme},
+        utils, Error,
+    },
+    alloc::{
+        borrow::Cow,
+        format,
+        string::{String, ToString},
+        vec::Vec,
+    },
+    core::{
+        convert::TryInto,
+        fmt::{self, Display},
+        marker::PhantomData,
+        mem::size_of,
+        str::FromStr,
+    },
+};
+
+/// Kind of binary representation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// [RootProof::serialize](crate::RootProof::serialize)
+    RootProof,
+    /// [Proof::serialize](crate::Proof::serialize)
+    Proof,
+    /// [Diff::serialize](crate::Diff::serialize)
+    Diff,
+    /// [Store::serialize](crate::store::Store::serialize)
+    Store,
+}
+
+impl FromStr for Kind {
+    type Err = Error;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "root" => Ok(Self::RootProof),
+            "proof" => Ok(Self::Proof),
+            "diff" => Ok(Self::Diff),
+            "store" => Ok(Self::Store),
+            kind => Err(Error::decode(format!("kind == {:?}", kind))),
+        }
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::RootProof => "root",
+            Self::Proof => "proof",
+            Self::Diff => "diff",
+            Self::Store => "store",
+        })
+    }
+}
+
+/// Match details and signers carried from one [inspect] to the next
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    id: Vec<u8>,
+    version: Vec<u8>,
+    signers: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl Context {
+    /// Constructs a context for the match with the given binary identifier.
+    ///
+    /// See [ID::serialize](crate::ID::serialize).
+    pub fn new(id: Vec<u8>) -> Self {
+        Self {
+            id,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the game version that diffs are checked against.
+    ///
+    /// Inspecting a root proof, proof or store replaces it with the version of its state.
+    pub fn set_version(&mut self, version: Vec<u8>) {
+        self.version = version;
+    }
+
+    /// Learns the game version of an inspected state, and the author of an inspected diff.
+    ///
+    /// A diff's author is reported as the signer of any later inspected player proof range that
+    /// has the diff's proof signature.
+    pub fn learn(&mut self, report: &Report) {
+        if let Some(version) = &report.version {
+            self.version = version.clone();
+        }
+
+        if let (Some(signature), Some(author)) = (&report.signature, &report.author) {
+            self.signers.push((signature.clone(), author.clone()));
+        }
+    }
+
+    fn signer(&self, signature: &[u8]) -> Option<&[u8]> {
+        self.signers
+            .iter()
+            .find(|(known, _)| known == signature)
+            .map(|(_, signer)| &signer[..])
+    }
+}
+
+/// Result of [inspect]
+#[derive(Debug, Default)]
+pub struct Report {
+    lines: Vec<String>,
+    failures: Vec<String>,
+    hash: Option<crypto::Hash>,
+    proof: Option<crypto::Hash>,
+    author: Option<Vec<u8>>,
+    owner: Option<Vec<u8>>,
+    version: Option<Vec<u8>>,
+    signature: Option<Vec<u8>>,
+}
+
+impl Report {
+    /// Gets the lines of the report.
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// Gets the signature and hash checks that failed.
+    pub fn failures(&self) -> &[String] {
+        &self.failures
+    }
+
+    /// Checks if every signature and hash check succeeded.
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Gets the hash of the inspected root proof or proof, or of a store's proof.
+    pub fn hash(&self) -> Option<&crypto::Hash> {
+        self.hash.as_ref()
+    }
+
+    /// Gets the hash of the proof an inspected diff applies to.
+    pub fn proof(&self) -> Option<&crypto::Hash> {
+        self.proof.as_ref()
+    }
+
+    /// Gets the recovered author of the inspected root proof or diff, or of a store's root proof.
+    pub fn author(&self) -> Option<&[u8]> {
+        self.author.as_deref()
+    }
+
+    /// Gets the recovered signer of the owner's range of the inspected proof, or of a store's
+    /// proof.
+    ///
+    /// This should be the author of the proof's root proof.
+    pub fn owner(&self) -> Option<&[u8]> {
+        self.owner.as_deref()
+    }
+
+    /// Gets the game version of the inspected root proof or proof, or of a store's proof.
+    pub fn version(&self) -> Option<&[u8]> {
+        self.version.as_deref()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Inspects a binary representation in any supported [Format](crate::Format).
+///
+/// Framing errors are returned as errors.
+/// Signature and hash mismatches are recorded in the report.
+pub fn inspect<C: SignatureScheme>(
+    data: &[u8],
+    kind: Kind,
+    context: &Context,
+) -> Result<Report, Error> {
+    let blob = match kind {
+        Kind::RootProof => crate::format::Blob::RootProof,
+        Kind::Proof => crate::format::Blob::Proof,
+        Kind::Diff => crate::format::Blob::Diff,
+        Kind::Store => crate::format::Blob::Store,
+    };
+
+    let data = crate::format::canonical(data, blob, C::SIGNATURE_SIZE);
+
+    let mut inspector = Inspector::<C> {
+        report: Default::default(),
+        context,
+        salt: crypto::keccak256(&context.id),
+        version: context.version.clone(),
+        depth: 0,
+        phantom: PhantomData,
+    };
+
+    inspector.line(format!(
+        "format: {}",
+        match data {
+            Cow::Borrowed(_) => "v1",
+            Cow::Owned(_) => "v2",
+        }
+    ));
+
+    match kind {
+        Kind::RootProof => inspector.report.author = inspector.root_proof(&data)?,
+        Kind::Proof => inspector.report.owner = inspector.proof(&data)?,
+        Kind::Diff => inspector.diff(&data)?,
+        Kind::Store => inspector.store(&data)?,
+    }
+
+    Ok(inspector.report)
+}
+
+struct Inspector<'a, C: SignatureScheme> {
+    report: Report,
+    context: &'a Context,
+    salt: crypto::Hash,
+    version: Vec<u8>,
+    depth: usize,
+    phantom: PhantomData<fn() -> C>,
+}
+
+impl<C: SignatureScheme> Inspector<'_, C> {
+    fn line(&mut self, line: String) {
+        self.report
+            .lines
+            .push(format!("{:indent$}{}", "", line, indent = 2 * self.depth));
+    }
+
+    fn fail(&mut self, failure: String) {
+        self.line(format!("error: {}", failure));
+
+        self.report.failures.push(failure);
+    }
+
+    fn root_proof(&mut self, mut data: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let hash = crypto::keccak256(data);
+
+        let state = self.state(&mut data)?;
+        let actions = self.actions(&mut data)?;
+
+        crate::forbid!(data.len() != C::SIGNATURE_SIZE);
+
+        self.line(format!("signature: {}", utils::hex(data)));
+
+        let message = C::encode(&crypto::Message::Proof {
+            domain: self.domain(),
+            state,
+            actions: &actions.concat(),
+        });
+
+        let author = self.recover("author", &message, data);
+
+        self.line(format!("hash: {}", utils::hex(&hash)));
+
+        self.report.hash = Some(hash);
+
+        Ok(author)
+    }
+
+    fn proof(&mut self, mut data: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let hash = crypto::keccak256(data);
+
+        let state = self.state(&mut data)?;
+        let actions = self.actions(&mut data)?;
+
+        let mut owner = None;
+
+        for index in 0.. {
+            if data.is_empty() {
+                break;
+            }
+
+            let label = match index {
+                0 => "owner".to_string(),
+                index => format!("player {}", index - 1),
+            };
+
+            if !utils::read_u8_bool(&mut data)? {
+                self.line(format!("{}: none", label));
+
+                continue;
+            }
+
+            let start = utils::read_u32_usize(&mut data)?;
+            let end = utils::read_u32_usize(&mut data)?;
+
+            crate::forbid!(start > end || end > actions.len());
+            crate::forbid!(data.len() < C::SIGNATURE_SIZE);
+
+            let (signature, rest) = data.split_at(C::SIGNATURE_SIZE);
+
+            data = rest;
+
+            self.line(format!("{}: actions {}..{}", label, start, end));
+            self.depth += 1;
+            self.line(format!("signature: {}", utils::hex(signature)));
+
+            let context = self.context;
+
+            let signer = if start == 0 {
+                let message = C::encode(&crypto::Message::Proof {
+                    domain: self.domain(),
+                    state,
+                    actions: &actions[start..end].concat(),
+                });
+
+                self.recover("signer", &message, signature)
+            } else if let Some(signer) = context.signer(signature) {
+                self.line(format!(
+                    "signer: {} (diff author)",
+                    crypto::format_address(signer)
+                ));
+
+                Some(signer.to_vec())
+            } else {
+                self.line("signer: unknown".to_string());
+
+                None
+            };
+
+            if index == 0 {
+                owner = signer;
+            }
+
+            self.depth -= 1;
+        }
+
+        self.line(format!("hash: {}", utils::hex(&hash)));
+
+        self.report.hash = Some(hash);
+
+        Ok(owner)
+    }
+
+    fn diff(&mut self, mut data: &[u8]) -> Result<(), Error> {
+        crate::forbid!(data.len() < size_of::<crypto::Hash>());
+
+        let proof: crypto::Hash = data[..size_of::<crypto::Hash>()]
+            .try_into()
+            .map_err(Error::decode)?;
+
+        data = &data[size_of::<crypto::Hash>()..];
+
+        self.line(format!("proof: {}", utils::hex(&proof)));
+
+        let mut actions = data;
+
+        self.actions(&mut data)?;
+
+        actions = &actions[..actions.len() - data.len()];
+
+        crate::forbid!(data.len() != C::SIGNATURE_SIZE + C::SIGNATURE_SIZE);
+
+        let (proof_signature, signature) = data.split_at(C::SIGNATURE_SIZE);
+
+        self.line(format!("proof signature: {}", utils::hex(proof_signature)));
+        self.line(format!("signature: {}", utils::hex(signature)));
+
+        let message = C::encode(&crypto::Message::Diff {
+            domain: self.domain(),
+            proof: &proof,
+            actions,
+            proof_signature,
+        });
+
+        self.report.author = self.recover("author", &message, signature);
+        self.report.proof = Some(proof);
+        self.report.signature = Some(proof_signature.to_vec());
+
+        Ok(())
+    }
+
+    fn store(&mut self, mut data: &[u8]) -> Result<(), Error> {
+        match utils::read_u8(&mut data)? {
+            0 => self.line("player: none".to_string()),
+            crate::Player::MAX => self.line("player: all".to_string()),
+            byte => self.line(format!("player: {}", byte - 1)),
+        }
+
+        self.line("root secrets:".to_string());
+        self.secrets(&mut data)?;
+
+        self.line("root:".to_string());
+        let author = self.nested(&mut data, Self::root_proof)?;
+
+        self.line("secrets:".to_string());
+        self.secrets(&mut data)?;
+
+        self.line("proof:".to_string());
+        let owner = self.nested(&mut data, Self::proof)?;
+
+        if let (Some(author), Some(owner)) = (&author, &owner) {
+            if owner != author {
+                self.fail(format!(
+                    "owner signer {} != root author {}",
+                    crypto::format_address(owner),
+                    crypto::format_address(author),
+                ));
+            }
+        }
+
+        self.report.author = author;
+        self.report.owner = owner;
+
+        if utils::read_u8_bool(&mut data)? {
+            self.line(format!("seed: {}", utils::hex(data)));
+        } else {
+            crate::forbid!(!data.is_empty());
+
+            self.line("seed: none".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn nested<T>(
+        &mut self,
+        data: &mut &[u8],
+        inspect: impl FnOnce(&mut Self, &[u8]) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let nested = sized(data)?;
+
+        self.depth += 1;
+        let result = inspect(self, nested);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn secrets(&mut self, data: &mut &[u8]) -> Result<(), Error> {
+        let length = utils::read_u32_usize(data)?;
+
+        crate::forbid!(length >= usize::from(crate::Player::MAX));
+
+        self.depth += 1;
+
+        for player in 0..length {
+            if utils::read_u8_bool(data)? {
+                let secret = sized(data)?;
+                let random = sized(data)?;
+
+                self.line(format!(
+                    "player {}: {} bytes, {} random bytes",
+                    player,
+                    secret.len(),
+                    random.len(),
+                ));
+            } else {
+                self.line(format!("player {}: none", player));
+            }
+        }
+
+        self.depth -= 1;
+
+        Ok(())
+    }
+
+    fn state<'a>(&mut self, data: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+        let state = sized(data)?;
+
+        self.version = crate::state_version(state)?.to_vec();
+        self.report.version = Some(self.version.clone());
+
+        self.line(format!("state: {} bytes", state.len()));
+        self.depth += 1;
+        self.line(utils::hex(state));
+        self.depth -= 1;
+
+        Ok(state)
+    }
+
+    fn actions<'a>(&mut self, data: &mut &'a [u8]) -> Result<Vec<&'a [u8]>, Error> {
+        let length = utils::read_u32_usize(data)?;
+
+        self.line(format!("actions: {}", length));
+        self.depth += 1;
+
+        let mut actions = Vec::with_capacity(length.min(data.len()));
+
+        for index in 0..length {
+            let action = sized(data)?;
+
+            self.action(index, action)?;
+
+            actions.push(action);
+        }
+
+        self.depth -= 1;
+
+        Ok(actions)
+    }
+
+    fn action(&mut self, index: usize, data: &[u8]) -> Result<(), Error> {
+        crate::forbid!(data.len() < 2);
+
+        let player = match data[0] {
+            0 => "owner".to_string(),
+            byte => format!("player {}", byte - 1),
+        };
+
+        let (kind, data) = (data[1], &data[2..]);
+
+        match kind {
+            0 => self.line(format!("{}: {} play {}", index, player, utils::hex(data))),
+            1 => {
+                crate::forbid!(data.len() < C::ADDRESS_SIZE + C::SIGNATURE_SIZE);
+
+                let (subkey, data) = data.split_at(C::ADDRESS_SIZE);
+                let (signature, expiry) = data.split_at(C::SIGNATURE_SIZE);
+                let expiry = if expiry.is_empty() {
+                    None
+                } else {
+                    Some(expiry)
+                };
+
+                self.line(format!(
+                    "{}: {} certify {}",
+                    index,
+                    player,
+                    crypto::format_address(subkey)
+                ));
+                self.depth += 1;
+                self.line(format!("signature: {}", utils::hex(signature)));
+                self.line(format!(
+                    "expiry: {}",
+                    expiry.map_or_else(|| "none".to_string(), utils::hex)
+                ));
+                let message = C::encode(&crypto::Message::Certify {
+                    domain: self.domain(),
+                    challenge: &crate::default_challenge(subkey),
+                    subkey,
+                    expiry,
+                });
+
+                self.recover("signer", &message, signature);
+                self.depth -= 1;
+            }
+            2 => {
+                crate::forbid!(data.len() != C::ADDRESS_SIZE + C::ADDRESS_SIZE + C::SIGNATURE_SIZE);
+
+                let (approved, data) = data.split_at(C::ADDRESS_SIZE);
+                let (subkey, signature) = data.split_at(C::ADDRESS_SIZE);
+
+                self.line(format!(
+                    "{}: {} approve {} for {}",
+                    index,
+                    player,
+                    crypto::format_address(subkey),
+                    crypto::format_address(approved),
+                ));
+                self.depth += 1;
+                self.line(format!("signature: {}", utils::hex(signature)));
+                let message = C::encode(&crypto::Message::Approve {
+                    domain: self.domain(),
+                    approval: &crate::default_approval(approved, subkey),
+                    player: approved,
+                    subkey,
+                });
+
+                self.recover("signer", &message, signature);
+                self.depth -= 1;
+            }
+            3 => {
+                crate::forbid!(data.len() != C::ADDRESS_SIZE);
+
+                self.line(format!(
+                    "{}: {} revoke {}",
+                    index,
+                    player,
+                    crypto::format_address(data)
+                ));
+            }
+            4..=7 => {
+                crate::forbid!(!data.is_empty());
+
+                self.line(format!(
+                    "{}: {} {}",
+                    index,
+                    player,
+                    ["resign", "offer draw", "accept draw", "abort"][usize::from(kind - 4)],
+                ));
+            }
+            8 => {
+                crate::forbid!(data.len() % C::SIGNATURE_SIZE != 0);
+
+                self.line(format!("{}: {} checkpoint", index, player));
+                self.depth += 1;
+
+                for (player, signature) in data.chunks_exact(C::SIGNATURE_SIZE).enumerate() {
+                    self.line(format!(
+                        "player {} signature: {}",
+                        player,
+                        utils::hex(signature)
+                    ));
+                }
+
+                self.depth -= 1;
+            }
+            kind => return Err(Error::decode(format!("kind == {}", kind))),
+        }
+
+        Ok(())
+    }
+
+    fn domain(&self) -> crypto::Domain<'_> {
+        crypto::Domain {
+            version: &self.version,
+            salt: self.salt,
+        }
+    }
+
+    fn recover(&mut self, label: &str, message: &[u8], signature: &[u8]) -> Option<Vec<u8>> {
+        match C::recover(message, signature) {
+            Ok(address) => {
+                self.line(format!(
+                    "{}: {}",
+                    label,
+                    crypto::format_address(address.as_ref())
+                ));
+
+                Some(address.as_ref().to_vec())
+            }
+            Err(error) => {
+                self.fail(format!("{}: {}", label, error));
+
+                None
+            }
+        }
+    }
+}
+
+fn sized<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+    let size = utils::read_u32_usize(data)?;
+
+    crate::forbid!(data.len() < size);
+
+    let (sized, rest) = data.split_at(size);
+
+    *data = rest;
+
+    Ok(sized)
+}
@@ -21,21 +21,24 @@ extern crate alloc;
 use {
     alloc::{
         boxed::Box,
-        collections::BTreeMap,
+        collections::{BTreeMap, BTreeSet},
         format,
         string::{String, ToString},
         vec,
         vec::Vec,
     },
     core::{
+        cmp::Ordering,
         convert::TryInto,
         fmt::Debug,
-        mem::size_of,
+        mem::{size_of, take},
         ops::{Deref, DerefMut, Range},
     },
+    crypto::SignatureScheme,
 };
 
 pub mod crypto;
+pub mod settlement;
 pub mod store;
 pub mod utils;
 
@@ -48,14 +51,48 @@ pub mod version;
 #[cfg(feature = "std")]
 pub use version::tag;
 
+mod archive;
+pub use archive::{ProofArchive, Transcript};
+
+#[cfg(feature = "serde-proofs")]
+mod documents;
+
 mod error;
+pub use error::Error;
+
+mod evidence;
+pub use evidence::{EquivocationEvidence, Offence, SlashingEvidence};
+
+mod format;
+pub use format::Format;
+
+pub mod inspect;
+
+mod limits;
+pub use limits::DecodeLimits;
+
+mod result;
+pub use result::MatchResult;
+
+mod series;
+pub use series::{verify_series, Link, Series};
+
+mod verify;
+pub use verify::{verify, verify_with_limits, Attestation, Verification};
+
+mod view;
+pub use view::{DiffRef, ProofActionRef};
 
 /// Authenticated state
+///
+/// With the `serde-proofs` feature, this is also [serde::Serialize] and [serde::Deserialize] as a
+/// structured document.
 pub struct Proof<S: State> {
     root: RootProof<S>,
     actions: Vec<ProofAction<S>>,
-    proofs: [Option<PlayerProof<S>>; 3],
+    proofs: Vec<Option<PlayerProof<S>>>,
     hash: crypto::Hash,
+    size: usize,
     state: ProofState<S>,
 }
 
@@ -64,15 +101,13 @@ impl<S: State> Proof<S> {
     pub fn new(root: RootProof<S>) -> Self {
         let actions = root.actions.clone();
 
-        let proofs = [
-            Some(PlayerProof {
-                state: root.state.clone(),
-                range: 0..root.actions.len(),
-                signature: root.signature,
-            }),
-            None,
-            None,
-        ];
+        let mut proofs = vec![None; 1 + root.state.players.len()];
+
+        proofs[0] = Some(PlayerProof {
+            state: root.state.clone(),
+            range: 0..root.actions.len(),
+            signature: root.signature,
+        });
 
         let state = root.compute_state();
 
@@ -81,19 +116,48 @@ impl<S: State> Proof<S> {
             actions,
             proofs,
             hash: Default::default(),
+            size: Default::default(),
             state,
         };
 
-        proof.hash = proof.compute_hash();
+        proof.update_hash();
 
         proof
     }
 
     /// Updates the proof's state from a binary representation.
     ///
-    /// `data` must have been constructed using [Proof::serialize] on a proof with the same root.
-    pub fn deserialize(&mut self, data: &[u8], no_version_check: bool) -> Result<(), String> {
-        self.deserialize_and_init(data, |_| (), no_version_check)
+    /// `data` must have been constructed using [Proof::serialize] or [Proof::serialize_with_format]
+    /// on a proof with the same root.
+    pub fn deserialize(&mut self, data: &[u8], no_version_check: bool) -> Result<(), Error> {
+        self.deserialize_with_limits(data, no_version_check, &Default::default())
+    }
+
+    /// Updates the proof's state from a binary representation, within the given limits.
+    ///
+    /// See [Proof::deserialize].
+    pub fn deserialize_with_limits(
+        &mut self,
+        data: &[u8],
+        no_version_check: bool,
+        limits: &DecodeLimits,
+    ) -> Result<(), Error> {
+        let data = format::canonical(data, format::Blob::Proof, S::Scheme::SIGNATURE_SIZE);
+
+        self.deserialize_and_init(&data, |_| (), no_version_check, limits)
+            .map_err(Error::decoding)
+    }
+
+    /// Generates a binary representation in the given format.
+    ///
+    /// See [Proof::serialize].
+    pub fn serialize_with_format(&self, format: Format) -> Vec<u8> {
+        format::encode(
+            self.serialize(),
+            format,
+            format::Blob::Proof,
+            S::Scheme::SIGNATURE_SIZE,
+        )
     }
 
     /// Generates a binary representation that can be used to reconstruct the proof.
@@ -130,7 +194,7 @@ impl<S: State> Proof<S> {
                 utils::write_u32_usize(&mut data, proof.range.start).unwrap();
                 utils::write_u32_usize(&mut data, proof.range.end).unwrap();
 
-                data.extend(proof.signature.iter());
+                data.extend(proof.signature.as_ref());
             } else {
                 utils::write_u8_bool(&mut data, false);
             }
@@ -144,24 +208,55 @@ impl<S: State> Proof<S> {
         &self.hash
     }
 
+    /// Gets the length of the proof's binary representation.
+    pub fn serialized_size(&self) -> usize {
+        self.size
+    }
+
     /// Gets the state of the proof.
     pub fn state(&self) -> &ProofState<S> {
         &self.state
     }
 
+    /// Gets the outcome of the match.
+    ///
+    /// See [ProofState::outcome].
+    pub fn outcome(&self) -> Outcome {
+        self.state.outcome()
+    }
+
+    /// Checks if the match has a final outcome.
+    ///
+    /// No further [PlayerAction::Play] actions can be applied to a final proof.
+    pub fn is_final(&self) -> bool {
+        self.outcome().is_final()
+    }
+
+    /// Generates a compact signed result for a final proof.
+    ///
+    /// See [MatchResult::deserialize].
+    pub fn result(
+        &self,
+        sign: &mut impl FnMut(&[u8]) -> Result<crypto::StateSignature<S>, String>,
+    ) -> Result<MatchResult<S>, Error> {
+        MatchResult::new(self, sign)
+    }
+
     /// Verifies and applies a cryptographically constructed diff to the proof.
     ///
     /// `diff` must have been constructed using [Proof::diff] on a proof with the same digest.
-    pub fn apply(&mut self, diff: &Diff<S>) -> Result<(), error::Error> {
+    pub fn apply(&mut self, diff: &Diff<S>) -> Result<(), Error> {
         if diff.proof != self.hash {
-            return Err(format!(
-                "diff.proof != self.hash: {} != {}",
-                utils::hex(&diff.proof),
-                utils::hex(&self.hash)
-            )
-            .into());
+            return Err(Error::WrongProofHash {
+                expected: self.hash,
+                actual: diff.proof,
+            });
         }
 
+        forbid!(diff.id != self.root.state.id);
+
+        self.check_terminated(&diff.actions)?;
+
         let player = if diff.author == self.root.author {
             None
         } else {
@@ -170,6 +265,12 @@ impl<S: State> Proof<S> {
             player
         };
 
+        let checkpoint = diff.is_checkpoint();
+
+        if checkpoint {
+            slash!(diff.author, Offence::Checkpoint, !self.can_checkpoint());
+        }
+
         let proof = self
             .proofs
             .iter()
@@ -182,16 +283,43 @@ impl<S: State> Proof<S> {
 
         let mut latest = self.compute_state();
 
+        if checkpoint {
+            state = latest.clone();
+            start = self.actions.len();
+        }
+
         for (i, action) in diff.actions.iter().enumerate() {
-            match action.action {
-                PlayerAction::Play(_) => slash!(action.player != player),
+            match &action.action {
+                PlayerAction::Play(_) => {
+                    slash!(diff.author, Offence::Impersonation, action.player != player)
+                }
                 PlayerAction::Certify { .. } => (),
-                PlayerAction::Approve { .. } => slash!(player.is_some()),
+                PlayerAction::Approve { .. } => {
+                    slash!(diff.author, Offence::Impersonation, player.is_some())
+                }
+                PlayerAction::Revoke { .. }
+                | PlayerAction::Resign
+                | PlayerAction::OfferDraw
+                | PlayerAction::AcceptDraw
+                | PlayerAction::Abort => {
+                    slash!(diff.author, Offence::Impersonation, action.player != player)
+                }
+                PlayerAction::Checkpoint { signatures } => {
+                    slash!(diff.author, Offence::Impersonation, player.is_some());
+                    slash!(diff.author, Offence::Checkpoint, !checkpoint);
+                    slash!(
+                        diff.author,
+                        Offence::Checkpoint,
+                        *signatures != self.player_signatures()
+                    );
+                }
             }
 
-            latest.apply(action).map_err(error::Error::Hard)?;
+            latest
+                .apply(action)
+                .map_err(|error| error.slashable(diff.author, Offence::InvalidAction))?;
 
-            if latest.is_serializable() {
+            if latest.is_serializable() && !checkpoint {
                 state = latest.clone();
                 start = self.actions.len() + i + 1;
             }
@@ -209,23 +337,23 @@ impl<S: State> Proof<S> {
                     actions.extend(diff.actions[start - self.actions.len()..].iter().cloned());
                 }
 
-                let mut message = state.serialize().unwrap();
-                message.extend(actions.iter().flat_map(ProofAction::serialize));
+                let message = state.message(&actions);
 
                 slash!(
-                    crypto::recover(&message, &diff.proof_signature).map_err(error::Error::Hard)?
+                    diff.author,
+                    Offence::ProofSignature,
+                    S::Scheme::recover(&message, diff.proof_signature.as_ref())
+                        .map_err(|error| error.slashable(diff.author, Offence::ProofSignature))?
                         != self.root.author
                 );
 
-                self.proofs = [
-                    Some(PlayerProof {
-                        state,
-                        range: 0..actions.len(),
-                        signature: diff.proof_signature,
-                    }),
-                    None,
-                    None,
-                ];
+                self.proofs = vec![None; self.proofs.len()];
+
+                self.proofs[0] = Some(PlayerProof {
+                    state,
+                    range: 0..actions.len(),
+                    signature: diff.proof_signature,
+                });
 
                 self.actions = actions;
             }
@@ -269,18 +397,15 @@ impl<S: State> Proof<S> {
                 actions.extend(self.actions[offset..].iter().cloned());
                 actions.extend(diff.actions.iter().cloned());
 
-                let mut message = state.serialize().unwrap();
-
-                message.extend(
-                    actions[start - offset..]
-                        .iter()
-                        .flat_map(ProofAction::serialize),
-                );
+                let message = state.message(&actions[start - offset..]);
 
                 slash!(
+                    diff.author,
+                    Offence::ProofSignature,
                     latest.player(
-                        &crypto::recover(&message, &diff.proof_signature)
-                            .map_err(error::Error::Hard)?,
+                        &S::Scheme::recover(&message, diff.proof_signature.as_ref()).map_err(
+                            |error| { error.slashable(diff.author, Offence::ProofSignature) }
+                        )?,
                         &self.root.author,
                     ) != Some(player)
                 );
@@ -298,31 +423,131 @@ impl<S: State> Proof<S> {
                     self.proofs[0] = None;
                 }
 
-                for proof in &mut self.proofs {
-                    if let Some(proof) = proof {
-                        proof.range.start -= offset;
-                        proof.range.end -= offset;
-                    }
+                for proof in self.proofs.iter_mut().flatten() {
+                    proof.range.start -= offset;
+                    proof.range.end -= offset;
                 }
 
                 self.actions = actions;
             }
         }
 
-        self.hash = self.compute_hash();
+        self.update_hash();
         self.state = latest;
 
         Ok(())
     }
 
+    /// Rejects moves once the match has ended.
+    ///
+    /// Players can still co-sign the final state, and the owner can still checkpoint it.
+    fn check_terminated(&self, actions: &[ProofAction<S>]) -> Result<(), Error> {
+        if self.state.status.is_terminal() && actions.iter().any(is_move) {
+            return Err(Error::Terminated {
+                status: self.state.status,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks if the owner can checkpoint the proof.
+    ///
+    /// A proof can be checkpointed once every player has signed a state in it, and its latest
+    /// state is serializable.
+    ///
+    /// See [Proof::checkpoint].
+    pub fn can_checkpoint(&self) -> bool {
+        self.proofs[1..].iter().all(Option::is_some) && self.state.is_serializable()
+    }
+
+    /// Generates a checkpoint diff that compacts a proof with the same digest.
+    ///
+    /// A checkpoint is an owner diff whose only action is a [PlayerAction::Checkpoint], signing the
+    /// latest state.
+    /// Applying it discards every other action and player signature, leaving a proof equivalent to
+    /// a root proof for the latest state.
+    /// The checkpoint action, which stays in the equivalent root proof, records the latest
+    /// signature of each player.
+    /// The checkpoint commits to the digest of the proof it compacts, so a [ProofArchive] that
+    /// applies it keeps the full history.
+    ///
+    /// `sign` must sign as the owner.
+    ///
+    /// See [Proof::can_checkpoint] and [Proof::checkpoint_root].
+    pub fn checkpoint(
+        &self,
+        sign: &mut impl FnMut(&[u8]) -> Result<crypto::StateSignature<S>, String>,
+    ) -> Result<Diff<S>, Error> {
+        self.unsigned_checkpoint()?.sign_with(sign)
+    }
+
+    /// Generates a checkpoint diff whose signatures are supplied separately.
+    ///
+    /// See [Proof::checkpoint].
+    pub fn unsigned_checkpoint(&self) -> Result<UnsignedDiff<S>, Error> {
+        forbid!(!self.can_checkpoint());
+
+        self.unsigned_diff(vec![ProofAction {
+            player: None,
+            action: PlayerAction::Checkpoint {
+                signatures: self.player_signatures(),
+            },
+        }])
+    }
+
+    /// Gets the latest signature of each player who has signed a state in the proof.
+    fn player_signatures(&self) -> Vec<crypto::StateSignature<S>> {
+        self.proofs[1..]
+            .iter()
+            .flatten()
+            .map(|proof| proof.signature)
+            .collect()
+    }
+
+    /// Gets a root proof equivalent to the proof, if only the owner has signed it since the last
+    /// owner diff or checkpoint, otherwise [None].
+    ///
+    /// A proof constructed from the equivalent root proof has the same digest, so peers can
+    /// rebase onto it and release the original root proof.
+    pub fn checkpoint_root(&self) -> Option<RootProof<S>> {
+        if self.proofs[1..].iter().any(Option::is_some) {
+            return None;
+        }
+
+        let proof = self.proofs[0].as_ref()?;
+
+        let mut root = RootProof {
+            state: proof.state.clone(),
+            actions: self.actions.clone(),
+            signature: proof.signature,
+            hash: Default::default(),
+            author: self.root.author,
+            latest: self.compute_state(),
+        };
+
+        root.hash = crypto::keccak256(&root.serialize());
+
+        Some(root)
+    }
+
     /// Generates a diff that can be applied to a proof with the same digest.
     ///
     /// See [Proof::apply].
     pub fn diff(
         &self,
         actions: Vec<ProofAction<S>>,
-        sign: &mut impl FnMut(&[u8]) -> Result<crypto::Signature, String>,
-    ) -> Result<Diff<S>, String> {
+        sign: &mut impl FnMut(&[u8]) -> Result<crypto::StateSignature<S>, String>,
+    ) -> Result<Diff<S>, Error> {
+        self.unsigned_diff(actions)?.sign_with(sign)
+    }
+
+    /// Generates a diff whose signatures are supplied separately.
+    ///
+    /// See [Proof::diff].
+    pub fn unsigned_diff(&self, actions: Vec<ProofAction<S>>) -> Result<UnsignedDiff<S>, Error> {
+        self.check_terminated(&actions)?;
+
         let proof = self
             .proofs
             .iter()
@@ -335,49 +560,38 @@ impl<S: State> Proof<S> {
 
         let mut latest = self.compute_state();
 
+        let checkpoint = is_checkpoint(&actions);
+
+        if checkpoint {
+            state = latest.clone();
+            start = self.actions.len();
+        }
+
         for (i, action) in actions.iter().enumerate() {
             forbid!(action.player != actions.first().unwrap().player);
 
             latest.apply(action)?;
 
-            if latest.is_serializable() {
+            if latest.is_serializable() && !checkpoint {
                 state = latest.clone();
                 start = self.actions.len() + i + 1;
             }
         }
 
-        let mut message = state.serialize().unwrap();
-
-        if start < self.actions.len() {
-            message.extend(
-                self.actions[start..]
-                    .iter()
-                    .flat_map(ProofAction::serialize),
-            );
-
-            message.extend(actions.iter().flat_map(ProofAction::serialize));
+        let message = if start < self.actions.len() {
+            state.message(self.actions[start..].iter().chain(actions.iter()))
         } else {
-            message.extend(
-                actions[start - self.actions.len()..]
-                    .iter()
-                    .flat_map(ProofAction::serialize),
-            );
-        }
-
-        let signature = sign(&message)?;
-        let author = crypto::recover(&message, &signature)?;
-
-        if author != self.root.author {
-            let player = latest.player(&author, &self.root.author);
-
-            forbid!(player.is_none());
-
-            if let Some(action) = actions.first() {
-                forbid!(player != action.player);
-            }
-        }
+            state.message(&actions[start - self.actions.len()..])
+        };
 
-        Ok(Diff::new(self.hash, actions, signature, sign, &author)?)
+        Ok(UnsignedDiff {
+            proof: self.hash,
+            actions,
+            message,
+            latest,
+            owner: self.root.author,
+            signed: None,
+        })
     }
 
     fn deserialize_and_init(
@@ -385,22 +599,25 @@ impl<S: State> Proof<S> {
         mut data: &[u8],
         init: impl FnOnce(&mut S),
         no_version_check: bool,
-    ) -> Result<(), String> {
+        limits: &DecodeLimits,
+    ) -> Result<(), Error> {
         forbid!(
             data.len()
                 < size_of::<u32>()
                     + size_of::<u32>()
-                    + 3
+                    + 1
                     + size_of::<u32>()
                     + size_of::<u32>()
-                    + size_of::<crypto::Signature>()
+                    + S::Scheme::SIGNATURE_SIZE
         );
 
         let hash = crypto::keccak256(data);
+        let proof_size = data.len();
 
         let mut state = {
             let size = utils::read_u32_usize(&mut data)?;
 
+            forbid!(size > limits.max_state_size);
             forbid!(data.len() < size);
 
             let state =
@@ -417,7 +634,9 @@ impl<S: State> Proof<S> {
         let actions = {
             let length = utils::read_u32_usize(&mut data)?;
 
-            let mut actions = Vec::with_capacity(length);
+            forbid!(length > limits.max_actions);
+
+            let mut actions = Vec::with_capacity(length.min(data.len()));
 
             for _ in 0..length {
                 let size = utils::read_u32_usize(&mut data)?;
@@ -431,7 +650,7 @@ impl<S: State> Proof<S> {
         };
 
         let (ranges, signatures) = {
-            let mut ranges = Vec::with_capacity(3);
+            let mut ranges = Vec::with_capacity(1 + state.players.len());
             let mut signatures = Vec::with_capacity(ranges.capacity());
 
             let mut minimal = false;
@@ -453,9 +672,9 @@ impl<S: State> Proof<S> {
                     }));
 
                     signatures.push(Some({
-                        forbid!(data.len() < size_of::<crypto::Signature>());
-                        let signature = data[..size_of::<crypto::Signature>()].try_into().unwrap();
-                        data = &data[size_of::<crypto::Signature>()..];
+                        forbid!(data.len() < S::Scheme::SIGNATURE_SIZE);
+                        let signature = S::Scheme::signature(&data[..S::Scheme::SIGNATURE_SIZE])?;
+                        data = &data[S::Scheme::SIGNATURE_SIZE..];
 
                         signature
                     }));
@@ -475,7 +694,7 @@ impl<S: State> Proof<S> {
         forbid!(!data.is_empty());
 
         let proofs = {
-            let mut proofs = [None, None, None];
+            let mut proofs = vec![None; ranges.len()];
 
             for i in 0..=actions.len() {
                 let serializable = ranges
@@ -509,6 +728,27 @@ impl<S: State> Proof<S> {
                     }
                 }
 
+                for (j, range) in ranges.iter().enumerate() {
+                    if let Some(range) = range {
+                        if range.end == i {
+                            let proof = proofs[j].as_ref().unwrap();
+
+                            let author = S::Scheme::recover(
+                                &proof.message(&actions),
+                                proof.signature.as_ref(),
+                            )?;
+
+                            match j {
+                                0 => forbid!(author != self.root.author),
+                                j => forbid!(
+                                    state.player(&author, &self.root.author).map(usize::from)
+                                        != Some(j - 1)
+                                ),
+                            }
+                        }
+                    }
+                }
+
                 if i < actions.len() {
                     let action = &actions[i];
 
@@ -535,41 +775,23 @@ impl<S: State> Proof<S> {
                 }
             }
 
-            for (i, proof) in proofs.iter().enumerate() {
-                if let Some(proof) = proof {
-                    let mut message = proof.state.serialize().unwrap();
-
-                    message.extend(
-                        actions[proof.range.clone()]
-                            .iter()
-                            .flat_map(ProofAction::serialize),
-                    );
-
-                    let author = crypto::recover(&message, &proof.signature)?;
-
-                    match i {
-                        0 => forbid!(author != self.root.author),
-                        i => forbid!(
-                            state.player(&author, &self.root.author).map(usize::from)
-                                != Some(i - 1)
-                        ),
-                    }
-                }
-            }
-
             proofs
         };
 
         self.actions = actions;
         self.proofs = proofs;
         self.hash = hash;
+        self.size = proof_size;
         self.state = state;
 
         Ok(())
     }
 
-    fn compute_hash(&self) -> crypto::Hash {
-        crypto::keccak256(&self.serialize())
+    fn update_hash(&mut self) {
+        let data = self.serialize();
+
+        self.hash = crypto::keccak256(&data);
+        self.size = data.len();
     }
 
     fn compute_state(&self) -> ProofState<S> {
@@ -597,18 +819,22 @@ impl<S: State> Clone for Proof<S> {
             actions: self.actions.clone(),
             proofs: self.proofs.clone(),
             hash: self.hash,
+            size: self.size,
             state: self.compute_state(),
         }
     }
 }
 
 /// Authenticated initial state
+///
+/// With the `serde-proofs` feature, this is also [serde::Serialize] and [serde::Deserialize] as a
+/// structured document.
 pub struct RootProof<S: State> {
     state: ProofState<S>,
     actions: Vec<ProofAction<S>>,
-    signature: crypto::Signature,
+    signature: crypto::StateSignature<S>,
     hash: crypto::Hash,
-    author: crypto::Address,
+    author: crypto::StateAddress<S>,
     latest: ProofState<S>,
 }
 
@@ -619,8 +845,8 @@ impl<S: State> RootProof<S> {
     pub fn new(
         mut state: ProofState<S>,
         actions: Vec<ProofAction<S>>,
-        sign: &mut impl FnMut(&[u8]) -> Result<crypto::Signature, String>,
-    ) -> Result<Self, String> {
+        sign: &mut impl FnMut(&[u8]) -> Result<crypto::StateSignature<S>, String>,
+    ) -> Result<Self, Error> {
         let mut start = 0;
 
         let mut latest = state.clone();
@@ -636,17 +862,16 @@ impl<S: State> RootProof<S> {
 
         let actions = actions[start..].to_vec();
 
-        let mut message = state.serialize().unwrap();
-        message.extend(actions.iter().flat_map(ProofAction::serialize));
+        let message = state.message(&actions);
 
-        let signature = sign(&message)?;
+        let signature = sign(&message).map_err(Error::bad_signature)?;
 
         let mut proof = Self {
             state,
             actions,
             signature,
             hash: Default::default(),
-            author: crypto::recover(&message, &signature)?,
+            author: S::Scheme::recover(&message, signature.as_ref())?,
             latest,
         };
 
@@ -657,19 +882,69 @@ impl<S: State> RootProof<S> {
 
     /// Reads the version from a root proof's binary representation.
     ///
-    /// `data` must have been constructed using [RootProof::serialize].
-    pub fn version(mut data: &[u8]) -> Result<Vec<u8>, String> {
-        let size = utils::read_u32_usize(&mut data)?;
+    /// `data` must have been constructed using [RootProof::serialize] or
+    /// [RootProof::serialize_with_format].
+    pub fn version(data: &[u8]) -> Result<Vec<u8>, Error> {
+        (|| {
+            let data = format::canonical(data, format::Blob::RootProof, S::Scheme::SIGNATURE_SIZE);
+            let mut data = &data[..];
 
-        forbid!(data.len() < size);
-        ProofState::<S>::version(&data[..size])
+            let size = utils::read_u32_usize(&mut data)?;
+
+            forbid!(data.len() < size);
+            ProofState::<S>::version(&data[..size])
+        })()
+        .map_err(Error::decoding)
+    }
+
+    /// Reads the number of players from a root proof's binary representation.
+    ///
+    /// `data` must have been constructed using [RootProof::serialize] or
+    /// [RootProof::serialize_with_format].
+    pub fn player_count(data: &[u8]) -> Result<usize, Error> {
+        (|| {
+            let data = format::canonical(data, format::Blob::RootProof, S::Scheme::SIGNATURE_SIZE);
+            let mut data = &data[..];
+
+            let size = utils::read_u32_usize(&mut data)?;
+
+            forbid!(data.len() < size);
+            ProofState::<S>::player_count(&data[..size])
+        })()
+        .map_err(Error::decoding)
     }
 
     /// Constructs a root proof from its binary representation.
     ///
-    /// `data` must have been constructed using [RootProof::serialize].
-    pub fn deserialize(data: &[u8], no_version_check: bool) -> Result<Self, String> {
-        Self::deserialize_and_init(data, |_| (), no_version_check)
+    /// `data` must have been constructed using [RootProof::serialize] or
+    /// [RootProof::serialize_with_format].
+    pub fn deserialize(data: &[u8], no_version_check: bool) -> Result<Self, Error> {
+        Self::deserialize_with_limits(data, no_version_check, &Default::default())
+    }
+
+    /// Constructs a root proof from its binary representation, within the given limits.
+    ///
+    /// See [RootProof::deserialize].
+    pub fn deserialize_with_limits(
+        data: &[u8],
+        no_version_check: bool,
+        limits: &DecodeLimits,
+    ) -> Result<Self, Error> {
+        let data = format::canonical(data, format::Blob::RootProof, S::Scheme::SIGNATURE_SIZE);
+
+        Self::deserialize_and_init(&data, |_| (), no_version_check, limits).map_err(Error::decoding)
+    }
+
+    /// Generates a binary representation in the given format.
+    ///
+    /// See [RootProof::serialize].
+    pub fn serialize_with_format(&self, format: Format) -> Vec<u8> {
+        format::encode(
+            self.serialize(),
+            format,
+            format::Blob::RootProof,
+            S::Scheme::SIGNATURE_SIZE,
+        )
     }
 
     /// Generates a binary representation that can be used to reconstruct the root proof.
@@ -690,7 +965,7 @@ impl<S: State> RootProof<S> {
             data.extend(action);
         }
 
-        data.extend(self.signature.iter());
+        data.extend(self.signature.as_ref());
 
         data
     }
@@ -701,7 +976,7 @@ impl<S: State> RootProof<S> {
     }
 
     /// Gets the author of the root proof.
-    pub fn author(&self) -> &crypto::Address {
+    pub fn author(&self) -> &crypto::StateAddress<S> {
         &self.author
     }
 
@@ -714,20 +989,24 @@ impl<S: State> RootProof<S> {
         mut data: &[u8],
         init: impl FnOnce(&mut S),
         no_version_check: bool,
-    ) -> Result<Self, String> {
-        forbid!(data.len() < size_of::<u32>() + size_of::<u32>() + size_of::<crypto::Signature>());
+        limits: &DecodeLimits,
+    ) -> Result<Self, Error> {
+        forbid!(data.len() < size_of::<u32>() + size_of::<u32>() + S::Scheme::SIGNATURE_SIZE);
 
         let hash = crypto::keccak256(data);
 
         let size = utils::read_u32_usize(&mut data)?;
 
+        forbid!(size > limits.max_state_size);
         forbid!(data.len() < size);
         let state = ProofState::<S>::deserialize_and_init(&data[..size], init, no_version_check)?;
         data = &data[size..];
 
         let length = utils::read_u32_usize(&mut data)?;
 
-        let mut actions = Vec::with_capacity(length);
+        forbid!(length > limits.max_actions);
+
+        let mut actions = Vec::with_capacity(length.min(data.len()));
         let mut latest = state.clone();
 
         for _ in 0..length {
@@ -744,18 +1023,17 @@ impl<S: State> RootProof<S> {
             actions.push(action);
         }
 
-        forbid!(data.len() != size_of::<crypto::Signature>());
-        let signature = data.try_into().unwrap();
+        forbid!(data.len() != S::Scheme::SIGNATURE_SIZE);
+        let signature = S::Scheme::signature(data)?;
 
-        let mut message = state.serialize().unwrap();
-        message.extend(actions.iter().flat_map(ProofAction::serialize));
+        let message = state.message(&actions);
 
         Ok(Self {
             state,
             actions,
             signature,
             hash,
-            author: crypto::recover(&message, &signature)?,
+            author: S::Scheme::recover(&message, signature.as_ref())?,
             latest,
         })
     }
@@ -788,77 +1066,131 @@ impl<S: State> Clone for RootProof<S> {
 struct PlayerProof<S: State> {
     state: ProofState<S>,
     range: Range<usize>,
-    signature: crypto::Signature,
+    signature: crypto::StateSignature<S>,
+}
+
+impl<S: State> PlayerProof<S> {
+    fn message(&self, actions: &[ProofAction<S>]) -> Vec<u8> {
+        self.state.message(&actions[self.range.clone()])
+    }
 }
 
 /// Authenticated state transition
+///
+/// With the `serde-proofs` feature, this is also [serde::Serialize] and [serde::Deserialize] as a
+/// structured document.
 #[derive(derivative::Derivative, Clone)]
 #[derivative(Debug(bound = "ProofAction<S>: Debug"))]
 pub struct Diff<S: State> {
+    #[derivative(Debug = "ignore")]
+    id: S::ID,
     proof: crypto::Hash,
     actions: Vec<ProofAction<S>>,
     #[derivative(Debug(format_with = "crate::utils::fmt_hex"))]
-    proof_signature: crypto::Signature,
+    proof_signature: crypto::StateSignature<S>,
     #[derivative(Debug(format_with = "crate::utils::fmt_hex"))]
-    signature: crypto::Signature,
+    signature: crypto::StateSignature<S>,
     #[derivative(Debug(format_with = "crate::crypto::fmt_address"))]
-    author: crypto::Address,
+    author: crypto::StateAddress<S>,
 }
 
 impl<S: State> Diff<S> {
-    /// Constructs a diff from its binary representation.
+    /// Constructs a diff for the match with the given identifier from its binary representation.
     ///
-    /// `data` must have been constructed using [Diff::serialize].
-    pub fn deserialize(data: &[u8]) -> Result<Self, String> {
-        forbid!(
-            data.len()
-                < size_of::<crypto::Hash>()
-                    + size_of::<u32>()
-                    + size_of::<crypto::Signature>()
-                    + size_of::<crypto::Signature>()
-        );
+    /// `data` must have been constructed using [Diff::serialize] or [Diff::serialize_with_format].
+    /// The identifier isn't part of `data`, but its author signed it.
+    ///
+    /// See [DiffRef] to inspect a diff without decoding it.
+    pub fn deserialize(data: &[u8], id: &S::ID) -> Result<Self, Error> {
+        Self::deserialize_with_limits(data, id, &Default::default())
+    }
 
-        let author = crypto::recover(
-            &data[..data.len() - size_of::<crypto::Signature>()],
-            &data[data.len() - size_of::<crypto::Signature>()..],
-        )?;
+    /// Constructs a diff from its binary representation, within the given limits.
+    ///
+    /// See [Diff::deserialize].
+    pub fn deserialize_with_limits(
+        data: &[u8],
+        id: &S::ID,
+        limits: &DecodeLimits,
+    ) -> Result<Self, Error> {
+        DiffRef::new_with_limits(data, limits)
+            .and_then(|diff| diff.decode(id))
+            .map_err(Error::decoding)
+    }
 
-        let proof = data[..size_of::<crypto::Hash>()]
-            .try_into()
-            .map_err(|error| format!("{}", error))?;
+    /// Generates a binary representation in the given format.
+    ///
+    /// See [Diff::serialize].
+    pub fn serialize_with_format(&self, format: Format) -> Vec<u8> {
+        format::encode(
+            self.serialize(),
+            format,
+            format::Blob::Diff,
+            S::Scheme::SIGNATURE_SIZE,
+        )
+    }
 
-        let mut data = &data[size_of::<crypto::Hash>()..];
+    /// Generates a binary representation that can be used to reconstruct the diff.
+    ///
+    /// See [Diff::deserialize].
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::new();
 
-        let length = utils::read_u32_usize(&mut data)?;
+        data.extend(&self.proof);
+        data.extend(self.serialize_actions());
+        data.extend(self.proof_signature.as_ref());
+        data.extend(self.signature.as_ref());
 
-        let mut actions = Vec::with_capacity(length);
+        data
+    }
 
-        for _ in 0..length {
-            let size = utils::read_u32_usize(&mut data)?;
+    /// Gets the identifier of the diff's match.
+    pub fn id(&self) -> &S::ID {
+        &self.id
+    }
 
-            forbid!(data.len() < size);
-            actions.push(ProofAction::deserialize(&data[..size])?);
-            data = &data[size..];
-        }
+    /// Gets the hash of the proof the diff was constructed on.
+    pub fn proof(&self) -> &crypto::Hash {
+        &self.proof
+    }
 
-        forbid!(data.len() != size_of::<crypto::Signature>() + size_of::<crypto::Signature>());
+    /// Gets the address of the key that signed the diff.
+    pub fn author(&self) -> &crypto::StateAddress<S> {
+        &self.author
+    }
 
-        Ok(Self {
-            proof,
-            actions,
-            proof_signature: data[..size_of::<crypto::Signature>()].try_into().unwrap(),
-            signature: data[size_of::<crypto::Signature>()..].try_into().unwrap(),
-            author,
-        })
+    /// Checks if the diff is a checkpoint.
+    ///
+    /// See [Proof::checkpoint].
+    pub fn is_checkpoint(&self) -> bool {
+        is_checkpoint(&self.actions)
     }
 
-    /// Generates a binary representation that can be used to reconstruct the diff.
+    /// Gets the hash of the diff's contents, excluding its signature.
     ///
-    /// See [Diff::deserialize].
-    pub fn serialize(&self) -> Vec<u8> {
+    /// Diffs by the same author on the same proof with different digests are an equivocation.
+    /// See [EquivocationEvidence].
+    pub fn digest(&self) -> crypto::Hash {
         let mut data = Vec::new();
 
         data.extend(&self.proof);
+        data.extend(self.serialize_actions());
+        data.extend(self.proof_signature.as_ref());
+
+        crypto::keccak256(&data)
+    }
+
+    fn message(&self) -> Vec<u8> {
+        S::Scheme::encode(&crypto::Message::Diff {
+            domain: domain::<S>(&self.id),
+            proof: &self.proof,
+            actions: &self.serialize_actions(),
+            proof_signature: self.proof_signature.as_ref(),
+        })
+    }
+
+    fn serialize_actions(&self) -> Vec<u8> {
+        let mut data = Vec::new();
 
         utils::write_u32_usize(&mut data, self.actions.len()).unwrap();
 
@@ -868,50 +1200,100 @@ impl<S: State> Diff<S> {
             data.extend(action);
         }
 
-        data.extend(self.proof_signature.iter());
-        data.extend(self.signature.iter());
-
         data
     }
+}
 
-    /// Gets the hash of the proof the diff was constructed on.
-    pub fn proof(&self) -> &crypto::Hash {
-        &self.proof
+/// Diff awaiting its signatures
+///
+/// A diff is signed twice: first its latest state, then its contents.
+/// Generating one doesn't change the proof, so its messages can be signed asynchronously.
+///
+/// See [Proof::unsigned_diff] and [Proof::unsigned_checkpoint].
+pub struct UnsignedDiff<S: State> {
+    proof: crypto::Hash,
+    actions: Vec<ProofAction<S>>,
+    message: Vec<u8>,
+    latest: ProofState<S>,
+    owner: crypto::StateAddress<S>,
+    signed: Option<Diff<S>>,
+}
+
+impl<S: State> UnsignedDiff<S> {
+    /// Gets the message that the next signature must sign.
+    pub fn message(&self) -> Vec<u8> {
+        match &self.signed {
+            Some(diff) => diff.message(),
+            None => self.message.clone(),
+        }
     }
 
-    fn new(
-        proof: crypto::Hash,
-        actions: Vec<ProofAction<S>>,
-        proof_signature: crypto::Signature,
-        sign: &mut impl FnMut(&[u8]) -> Result<crypto::Signature, String>,
-        author: &crypto::Address,
-    ) -> Result<Self, String> {
-        let mut diff = Self {
-            proof,
-            actions,
-            proof_signature,
-            signature: [0; size_of::<crypto::Signature>()],
-            author: Default::default(),
-        };
+    /// Supplies the signature of [UnsignedDiff::message].
+    ///
+    /// Returns the diff once both of its signatures have been supplied.
+    pub fn sign(&mut self, signature: crypto::StateSignature<S>) -> Result<Option<Diff<S>>, Error> {
+        if let Some(mut diff) = self.signed.take() {
+            diff.signature = signature;
+
+            return Ok(Some(diff));
+        }
 
-        let message = diff.serialize();
-        let message = &message[..message.len() - size_of::<crypto::Signature>()];
+        let author = S::Scheme::recover(&self.message, signature.as_ref())?;
 
-        diff.signature = sign(message)?;
-        diff.author = *author;
+        if author != self.owner {
+            let player = self.latest.player(&author, &self.owner);
 
-        Ok(diff)
+            forbid!(player.is_none());
+
+            if let Some(action) = self.actions.first() {
+                forbid!(player != action.player);
+            }
+        }
+
+        self.signed = Some(Diff {
+            id: self.latest.id.clone(),
+            proof: self.proof,
+            actions: take(&mut self.actions),
+            proof_signature: signature,
+            signature: S::Scheme::signature(&vec![0; S::Scheme::SIGNATURE_SIZE])?,
+            author,
+        });
+
+        Ok(None)
+    }
+
+    pub(crate) fn sign_with(
+        mut self,
+        sign: &mut impl FnMut(&[u8]) -> Result<crypto::StateSignature<S>, String>,
+    ) -> Result<Diff<S>, Error> {
+        loop {
+            let signature = sign(&self.message()).map_err(Error::bad_signature)?;
+
+            if let Some(diff) = self.sign(signature)? {
+                return Ok(diff);
+            }
+        }
     }
 }
 
 /// Consensus state
+#[allow(clippy::type_complexity)]
 #[derive(Clone)]
 pub struct ProofState<S: State> {
     id: S::ID,
     nonce: S::Nonce,
-    players: [crypto::Address; 2],
-    signatures: BTreeMap<crypto::Address, crypto::Signature>,
-    approvals: BTreeMap<crypto::Address, (crypto::Address, crypto::Signature)>,
+    players: Vec<crypto::StateAddress<S>>,
+    signatures: BTreeMap<crypto::StateAddress<S>, (crypto::StateSignature<S>, Option<S::Nonce>)>,
+    approvals:
+        BTreeMap<crypto::StateAddress<S>, (crypto::StateAddress<S>, crypto::StateSignature<S>)>,
+    revocations: BTreeSet<crypto::StateAddress<S>>,
+    /// Status set by protocol-level actions, see [ProofState::status] for the match status
+    status: MatchStatus,
+    draw: BTreeSet<Player>,
+    abort: BTreeSet<Player>,
+    predecessors: Vec<Link>,
+    /// Layout of the binary representation
+    layout: Layout,
     state: S,
 }
 
@@ -919,8 +1301,26 @@ impl<S: State> ProofState<S> {
     /// Constructs a consensus state.
     ///
     /// `state` must be serializable.
-    pub fn new(id: S::ID, players: [crypto::Address; 2], state: S) -> Result<Self, String> {
+    /// There must be fewer than [Player::MAX] players.
+    pub fn new(id: S::ID, players: Vec<crypto::StateAddress<S>>, state: S) -> Result<Self, Error> {
+        Self::with_predecessors(id, players, state, Vec::new())
+    }
+
+    /// Constructs a consensus state linked to the final proofs of preceding matches.
+    ///
+    /// A rematch or the next game of a series has one predecessor, and a tournament match has one
+    /// for each match its players advanced from.
+    /// The links are signed along with the state.
+    ///
+    /// See [verify_series].
+    pub fn with_predecessors(
+        id: S::ID,
+        players: Vec<crypto::StateAddress<S>>,
+        state: S,
+        predecessors: Vec<Link>,
+    ) -> Result<Self, Error> {
         forbid!(!state.is_serializable());
+        forbid!(players.len() >= usize::from(Player::MAX));
 
         Ok(Self {
             id,
@@ -928,6 +1328,12 @@ impl<S: State> ProofState<S> {
             players,
             signatures: BTreeMap::new(),
             approvals: BTreeMap::new(),
+            revocations: BTreeSet::new(),
+            status: MatchStatus::InProgress,
+            draw: BTreeSet::new(),
+            abort: BTreeSet::new(),
+            predecessors,
+            layout: Layout::V2,
             state,
         })
     }
@@ -937,27 +1343,89 @@ impl<S: State> ProofState<S> {
         &self.id
     }
 
-    /// Gets the addresses of the players.
-    pub fn players(&self) -> &[crypto::Address] {
-        &self.players
+    /// Gets the nonce of the state.
+    pub fn nonce(&self) -> &S::Nonce {
+        &self.nonce
     }
 
-    /// Gets the player associated with the given `address`, if any, otherwise [None].
-    pub fn player(&self, address: &crypto::Address, owner: &crypto::Address) -> Option<Player> {
-        if let Some(player) = self.players.iter().position(|player| player == address) {
-            return player.try_into().ok();
-        }
+    /// Gets the status of the match.
+    ///
+    /// This is derived from [ProofState::outcome].
+    pub fn status(&self) -> MatchStatus {
+        self.outcome().into()
+    }
 
-        if let Some(signature) = self.signatures.get(address) {
-            if let Ok(player) = &crypto::recover(S::challenge(address).as_bytes(), signature) {
-                if let Some(player) = self.players.iter().position(|address| address == player) {
-                    return player.try_into().ok();
-                }
-            }
+    /// Gets the outcome of the match.
+    ///
+    /// A match ended by a resignation, draw or abort takes its outcome from that action, otherwise
+    /// from [State::outcome].
+    pub fn outcome(&self) -> Outcome {
+        match self.status {
+            MatchStatus::InProgress => self.state.outcome(),
+            MatchStatus::Won(player) => Outcome::Winner(player),
+            MatchStatus::Resigned(player) => Outcome::Resigned(player),
+            MatchStatus::Drawn => Outcome::Draw,
+            MatchStatus::Aborted => Outcome::Aborted,
         }
+    }
 
-        if let Some((player, signature)) = self.approvals.get(address) {
-            if crypto::recover(S::approval(player, address).as_bytes(), signature).as_ref()
+    /// Checks if the given player has offered or accepted a draw since the last
+    /// [PlayerAction::Play].
+    pub fn is_draw_offered(&self, player: Player) -> bool {
+        self.draw.contains(&player)
+    }
+
+    /// Checks if the given player has requested to abort the match since the last
+    /// [PlayerAction::Play].
+    pub fn is_abort_requested(&self, player: Player) -> bool {
+        self.abort.contains(&player)
+    }
+
+    /// Gets the links to the final proofs of preceding matches.
+    pub fn predecessors(&self) -> &[Link] {
+        &self.predecessors
+    }
+
+    /// Gets the addresses of the players.
+    pub fn players(&self) -> &[crypto::StateAddress<S>] {
+        &self.players
+    }
+
+    /// Gets the player associated with the given `address`, if any, otherwise [None].
+    ///
+    /// Revoked and expired subkeys aren't associated with any player.
+    pub fn player(
+        &self,
+        address: &crypto::StateAddress<S>,
+        owner: &crypto::StateAddress<S>,
+    ) -> Option<Player> {
+        if let Some(player) = self.players.iter().position(|player| player == address) {
+            return player.try_into().ok();
+        }
+
+        if let Some((signature, expiry)) = self.signatures.get(address) {
+            if let Some(expiry) = expiry {
+                // subkeys with an expiry are treated as expired if the nonces aren't ordered
+                if !matches!(
+                    self.nonce.order(expiry),
+                    Some(Ordering::Less) | Some(Ordering::Equal)
+                ) {
+                    return None;
+                }
+            }
+
+            if let Ok(player) = &S::Scheme::recover(
+                &self.challenge(address, expiry.as_ref()),
+                signature.as_ref(),
+            ) {
+                if let Some(player) = self.players.iter().position(|address| address == player) {
+                    return player.try_into().ok();
+                }
+            }
+        }
+
+        if let Some((player, signature)) = self.approvals.get(address) {
+            if S::Scheme::recover(&self.approval(player, address), signature.as_ref()).as_ref()
                 == Ok(owner)
             {
                 if let Some(player) = self.players.iter().position(|address| address == player) {
@@ -974,63 +1442,141 @@ impl<S: State> ProofState<S> {
         &self.state
     }
 
-    fn version(mut data: &[u8]) -> Result<Vec<u8>, String> {
+    /// Gets the message that must be signed by a player in order to certify the subkey with the
+    /// given address.
+    ///
+    /// If `expiry` is given, the subkey is only valid until the nonce exceeds `expiry`.
+    ///
+    /// See [State::challenge].
+    pub fn challenge(
+        &self,
+        address: &crypto::StateAddress<S>,
+        expiry: Option<&S::Nonce>,
+    ) -> Vec<u8> {
+        S::Scheme::encode(&crypto::Message::Certify {
+            domain: self.domain(),
+            challenge: &S::challenge(address),
+            subkey: address.as_ref(),
+            expiry: expiry.map(Nonce::serialize).as_deref(),
+        })
+    }
+
+    /// Gets the message that must be signed by the owner in order to approve a subkey for a
+    /// player.
+    ///
+    /// See [State::approval].
+    pub fn approval(
+        &self,
+        player: &crypto::StateAddress<S>,
+        subkey: &crypto::StateAddress<S>,
+    ) -> Vec<u8> {
+        S::Scheme::encode(&crypto::Message::Approve {
+            domain: self.domain(),
+            approval: &S::approval(player, subkey),
+            player: player.as_ref(),
+            subkey: subkey.as_ref(),
+        })
+    }
+
+    fn domain(&self) -> crypto::Domain<'static> {
+        domain::<S>(&self.id)
+    }
+
+    fn message<'a>(&self, actions: impl IntoIterator<Item = &'a ProofAction<S>>) -> Vec<u8>
+    where
+        S: 'a,
+    {
+        S::Scheme::encode(&crypto::Message::Proof {
+            domain: self.domain(),
+            state: &self.serialize().unwrap(),
+            actions: &actions
+                .into_iter()
+                .flat_map(ProofAction::serialize)
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn version(data: &[u8]) -> Result<Vec<u8>, Error> {
+        state_version(data).map(<[u8]>::to_vec)
+    }
+
+    fn player_count(mut data: &[u8]) -> Result<usize, Error> {
+        let layout = Layout::read(&mut data)?;
+
         let size = utils::read_u32_usize(&mut data)?;
 
         forbid!(data.len() < size);
-        Ok(data[..size].to_vec())
+        data = &data[size..];
+
+        S::ID::deserialize(&mut data).map_err(Error::decode)?;
+        S::Nonce::deserialize(&mut data).map_err(Error::decode)?;
+
+        let length = match layout {
+            Layout::V1 => 2,
+            Layout::V2 => utils::read_u32_usize(&mut data)?,
+        };
+
+        forbid!(length >= usize::from(Player::MAX));
+
+        Ok(length)
     }
 
     fn deserialize_and_init(
         mut data: &[u8],
         init: impl FnOnce(&mut S),
         no_version_check: bool,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, Error> {
+        let layout = Layout::read(&mut data)?;
+
         let version = S::version();
         let size = utils::read_u32_usize(&mut data)?;
 
-        forbid!(size != version.len());
         forbid!(data.len() < size);
 
-        if cfg!(not(feature = "no-version-check")) && !no_version_check {
-            forbid!(data[..size] != *version);
+        if cfg!(not(feature = "no-version-check")) && !no_version_check && data[..size] != *version
+        {
+            return Err(Error::VersionMismatch {
+                expected: version.to_vec(),
+                actual: data[..size].to_vec(),
+            });
         }
 
+        forbid!(size != version.len());
+
         data = &data[size..];
 
-        let id = S::ID::deserialize(&mut data)?;
-        let nonce = S::Nonce::deserialize(&mut data)?;
+        let id = S::ID::deserialize(&mut data).map_err(Error::decode)?;
+        let nonce = S::Nonce::deserialize(&mut data).map_err(Error::decode)?;
+
+        let length = match layout {
+            Layout::V1 => 2,
+            Layout::V2 => utils::read_u32_usize(&mut data)?,
+        };
+
+        forbid!(length >= usize::from(Player::MAX));
 
         forbid!(
-            data.len() < 2 * size_of::<crypto::Address>() + size_of::<u32>() + size_of::<u32>()
+            data.len() < length * S::Scheme::ADDRESS_SIZE + size_of::<u32>() + size_of::<u32>()
         );
 
-        let players: [crypto::Address; 2] = [
-            data[..size_of::<crypto::Address>()]
-                .try_into()
-                .map_err(|error| format!("{}", error))?,
-            data[size_of::<crypto::Address>()..2 * size_of::<crypto::Address>()]
-                .try_into()
-                .map_err(|error| format!("{}", error))?,
-        ];
+        let players: Vec<_> = data[..length * S::Scheme::ADDRESS_SIZE]
+            .chunks_exact(S::Scheme::ADDRESS_SIZE)
+            .map(S::Scheme::parse_address)
+            .collect::<Result<_, _>>()?;
 
-        data = &data[2 * size_of::<crypto::Address>()..];
+        data = &data[length * S::Scheme::ADDRESS_SIZE..];
 
         let length = utils::read_u32_usize(&mut data)?;
 
-        forbid!(
-            data.len() < length * (size_of::<crypto::Address>() + size_of::<crypto::Signature>())
-        );
-
         let mut signatures = BTreeMap::new();
         let mut previous = None;
 
         for _ in 0..length {
-            let address = data[..size_of::<crypto::Address>()]
-                .try_into()
-                .map_err(|error| format!("{}", error))?;
+            forbid!(data.len() < S::Scheme::ADDRESS_SIZE + S::Scheme::SIGNATURE_SIZE);
+
+            let address = S::Scheme::parse_address(&data[..S::Scheme::ADDRESS_SIZE])?;
 
-            data = &data[size_of::<crypto::Address>()..];
+            data = &data[S::Scheme::ADDRESS_SIZE..];
 
             if let Some(previous) = previous {
                 forbid!(address <= previous);
@@ -1038,11 +1584,17 @@ impl<S: State> ProofState<S> {
 
             previous = Some(address);
 
-            let signature = data[..size_of::<crypto::Signature>()].try_into().unwrap();
+            let signature = S::Scheme::signature(&data[..S::Scheme::SIGNATURE_SIZE])?;
 
-            data = &data[size_of::<crypto::Signature>()..];
+            data = &data[S::Scheme::SIGNATURE_SIZE..];
 
-            signatures.insert(address, signature);
+            let expiry = if layout == Layout::V2 && utils::read_u8_bool(&mut data)? {
+                Some(S::Nonce::deserialize(&mut data).map_err(Error::decode)?)
+            } else {
+                None
+            };
+
+            signatures.insert(address, (signature, expiry));
         }
 
         let length = utils::read_u32_usize(&mut data)?;
@@ -1050,20 +1602,18 @@ impl<S: State> ProofState<S> {
         forbid!(
             data.len()
                 < length
-                    * (size_of::<crypto::Address>()
-                        + size_of::<crypto::Address>()
-                        + size_of::<crypto::Signature>())
+                    * (S::Scheme::ADDRESS_SIZE
+                        + S::Scheme::ADDRESS_SIZE
+                        + S::Scheme::SIGNATURE_SIZE)
         );
 
         let mut approvals = BTreeMap::new();
         let mut previous = None;
 
         for _ in 0..length {
-            let subkey = data[..size_of::<crypto::Address>()]
-                .try_into()
-                .map_err(|error| format!("{}", error))?;
+            let subkey = S::Scheme::parse_address(&data[..S::Scheme::ADDRESS_SIZE])?;
 
-            data = &data[size_of::<crypto::Address>()..];
+            data = &data[S::Scheme::ADDRESS_SIZE..];
 
             if let Some(previous) = previous {
                 forbid!(subkey <= previous);
@@ -1071,27 +1621,97 @@ impl<S: State> ProofState<S> {
 
             previous = Some(subkey);
 
-            let player = data[..size_of::<crypto::Address>()]
-                .try_into()
-                .map_err(|error| format!("{}", error))?;
+            let player = S::Scheme::parse_address(&data[..S::Scheme::ADDRESS_SIZE])?;
 
-            data = &data[size_of::<crypto::Address>()..];
+            data = &data[S::Scheme::ADDRESS_SIZE..];
 
-            let signature = data[..size_of::<crypto::Signature>()].try_into().unwrap();
+            let signature = S::Scheme::signature(&data[..S::Scheme::SIGNATURE_SIZE])?;
 
-            data = &data[size_of::<crypto::Signature>()..];
+            data = &data[S::Scheme::SIGNATURE_SIZE..];
 
             approvals.insert(subkey, (player, signature));
         }
 
+        let (revocations, status, draw, abort, predecessors) = match layout {
+            Layout::V1 => (
+                BTreeSet::new(),
+                MatchStatus::InProgress,
+                BTreeSet::new(),
+                BTreeSet::new(),
+                Vec::new(),
+            ),
+            Layout::V2 => {
+                let length = utils::read_u32_usize(&mut data)?;
+
+                forbid!(data.len() < length * S::Scheme::ADDRESS_SIZE);
+
+                let mut revocations = BTreeSet::new();
+                let mut previous = None;
+
+                for _ in 0..length {
+                    let subkey = S::Scheme::parse_address(&data[..S::Scheme::ADDRESS_SIZE])?;
+
+                    data = &data[S::Scheme::ADDRESS_SIZE..];
+
+                    if let Some(previous) = previous {
+                        forbid!(subkey <= previous);
+                    }
+
+                    previous = Some(subkey);
+
+                    revocations.insert(subkey);
+                }
+
+                let status = match utils::read_u8(&mut data)? {
+                    0 => MatchStatus::InProgress,
+                    1 => {
+                        let player = utils::read_u8(&mut data)?;
+
+                        forbid!(usize::from(player) >= players.len());
+
+                        MatchStatus::Resigned(player)
+                    }
+                    2 => MatchStatus::Drawn,
+                    3 => MatchStatus::Aborted,
+                    4 => {
+                        let player = utils::read_u8(&mut data)?;
+
+                        forbid!(usize::from(player) >= players.len());
+
+                        MatchStatus::Won(player)
+                    }
+                    byte => return Err(Error::decode(format!("byte == {}", byte))),
+                };
+
+                let draw = Self::deserialize_players(&mut data, players.len())?;
+                let abort = Self::deserialize_players(&mut data, players.len())?;
+
+                let length = utils::read_u32_usize(&mut data)?;
+
+                forbid!(length > data.len() / (size_of::<crypto::Hash>() + 1));
+
+                let predecessors = (0..length)
+                    .map(|_| Link::deserialize(&mut data))
+                    .collect::<Result<_, _>>()?;
+
+                (revocations, status, draw, abort, predecessors)
+            }
+        };
+
         Ok(Self {
             id,
             nonce,
             players,
             signatures,
             approvals,
+            revocations,
+            status,
+            draw,
+            abort,
+            predecessors,
+            layout,
             state: {
-                let mut state = S::deserialize(data)?;
+                let mut state = S::deserialize(data).map_err(Error::decode)?;
 
                 init(&mut state);
 
@@ -1100,55 +1720,188 @@ impl<S: State> ProofState<S> {
         })
     }
 
+    fn deserialize_players(data: &mut &[u8], length: usize) -> Result<BTreeSet<Player>, Error> {
+        let size = utils::read_u32_usize(data)?;
+
+        forbid!(size > length);
+        forbid!(data.len() < size);
+
+        let mut players = BTreeSet::new();
+        let mut previous = None;
+
+        for &player in &data[..size] {
+            forbid!(usize::from(player) >= length);
+
+            if let Some(previous) = previous {
+                forbid!(player <= previous);
+            }
+
+            previous = Some(player);
+
+            players.insert(player);
+        }
+
+        *data = &data[size..];
+
+        Ok(players)
+    }
+
     fn is_serializable(&self) -> bool {
         TryInto::<u32>::try_into(self.signatures.len()).is_ok() && self.state.is_serializable()
     }
 
     fn serialize(&self) -> Option<Vec<u8>> {
+        if self.layout == Layout::V1 {
+            if let Some(data) = self.serialize_v1() {
+                return Some(data);
+            }
+        }
+
         let version = S::version();
         let state = self.state.serialize()?;
         let id = self.id.serialize();
         let nonce = self.nonce.serialize();
 
         let mut data = Vec::with_capacity(
-            size_of::<u32>()
+            LAYOUT_TAG.len()
+                + 1
+                + size_of::<u32>()
                 + version.len()
                 + id.len()
                 + nonce.len()
-                + self.players.len() * size_of::<crypto::Address>()
                 + size_of::<u32>()
-                + self.signatures.len()
-                    * (size_of::<crypto::Address>() + size_of::<crypto::Signature>())
+                + self.players.len() * S::Scheme::ADDRESS_SIZE
+                + size_of::<u32>()
+                + self.signatures.len() * (S::Scheme::ADDRESS_SIZE + S::Scheme::SIGNATURE_SIZE + 1)
                 + size_of::<u32>()
                 + self.approvals.len()
-                    * (size_of::<crypto::Address>()
-                        + size_of::<crypto::Address>()
-                        + size_of::<crypto::Signature>())
+                    * (S::Scheme::ADDRESS_SIZE
+                        + S::Scheme::ADDRESS_SIZE
+                        + S::Scheme::SIGNATURE_SIZE)
+                + size_of::<u32>()
+                + self.revocations.len() * S::Scheme::ADDRESS_SIZE
+                + 1
+                + 1
+                + size_of::<u32>()
+                + self.draw.len()
+                + size_of::<u32>()
+                + self.abort.len()
+                + size_of::<u32>()
+                + self.predecessors.len() * (size_of::<crypto::Hash>() + 2)
                 + state.len(),
         );
 
+        data.extend(&LAYOUT_TAG);
+        utils::write_u8(&mut data, Layout::V2 as u8);
         utils::write_u32_usize(&mut data, version.len()).ok()?;
         data.extend(version);
         data.extend(id);
         data.extend(nonce);
 
+        utils::write_u32_usize(&mut data, self.players.len()).ok()?;
+
         for player in &self.players {
-            data.extend(player);
+            data.extend(player.as_ref());
         }
 
         utils::write_u32_usize(&mut data, self.signatures.len()).ok()?;
 
-        for (address, signature) in &self.signatures {
-            data.extend(address);
-            data.extend(signature.iter());
+        for (address, (signature, expiry)) in &self.signatures {
+            data.extend(address.as_ref());
+            data.extend(signature.as_ref());
+
+            utils::write_u8_bool(&mut data, expiry.is_some());
+
+            if let Some(expiry) = expiry {
+                data.extend(expiry.serialize());
+            }
         }
 
         utils::write_u32_usize(&mut data, self.approvals.len()).ok()?;
 
         for (subkey, (player, signature)) in &self.approvals {
-            data.extend(subkey);
-            data.extend(player);
-            data.extend(signature.iter());
+            data.extend(subkey.as_ref());
+            data.extend(player.as_ref());
+            data.extend(signature.as_ref());
+        }
+
+        utils::write_u32_usize(&mut data, self.revocations.len()).ok()?;
+
+        for subkey in &self.revocations {
+            data.extend(subkey.as_ref());
+        }
+
+        match self.status {
+            MatchStatus::InProgress => utils::write_u8(&mut data, 0),
+            MatchStatus::Resigned(player) => {
+                utils::write_u8(&mut data, 1);
+                utils::write_u8(&mut data, player);
+            }
+            MatchStatus::Drawn => utils::write_u8(&mut data, 2),
+            MatchStatus::Aborted => utils::write_u8(&mut data, 3),
+            MatchStatus::Won(player) => {
+                utils::write_u8(&mut data, 4);
+                utils::write_u8(&mut data, player);
+            }
+        }
+
+        utils::write_u32_usize(&mut data, self.draw.len()).ok()?;
+        data.extend(&self.draw);
+
+        utils::write_u32_usize(&mut data, self.abort.len()).ok()?;
+        data.extend(&self.abort);
+
+        utils::write_u32_usize(&mut data, self.predecessors.len()).ok()?;
+
+        for predecessor in &self.predecessors {
+            predecessor.serialize(&mut data);
+        }
+
+        data.extend(state);
+
+        Some(data)
+    }
+
+    /// Generates a [Layout::V1] binary representation, if the state can be represented in it.
+    fn serialize_v1(&self) -> Option<Vec<u8>> {
+        if self.players.len() != 2
+            || self.signatures.values().any(|(_, expiry)| expiry.is_some())
+            || !self.revocations.is_empty()
+            || self.status != MatchStatus::InProgress
+            || !self.draw.is_empty()
+            || !self.abort.is_empty()
+            || !self.predecessors.is_empty()
+        {
+            return None;
+        }
+
+        let version = S::version();
+        let state = self.state.serialize()?;
+
+        let mut data = Vec::new();
+
+        utils::write_u32_usize(&mut data, version.len()).ok()?;
+        data.extend(version);
+        data.extend(self.id.serialize());
+        data.extend(self.nonce.serialize());
+
+        for player in &self.players {
+            data.extend(player.as_ref());
+        }
+
+        utils::write_u32_usize(&mut data, self.signatures.len()).ok()?;
+
+        for (address, (signature, _)) in &self.signatures {
+            data.extend(address.as_ref());
+            data.extend(signature.as_ref());
+        }
+
+        utils::write_u32_usize(&mut data, self.approvals.len()).ok()?;
+
+        for (subkey, (player, signature)) in &self.approvals {
+            data.extend(subkey.as_ref());
+            data.extend(player.as_ref());
+            data.extend(signature.as_ref());
         }
 
         data.extend(state);
@@ -1156,26 +1909,46 @@ impl<S: State> ProofState<S> {
         Some(data)
     }
 
-    fn apply(&mut self, action: &ProofAction<S>) -> Result<(), String> {
+    fn apply(&mut self, action: &ProofAction<S>) -> Result<(), Error> {
         let player = action.player;
 
         forbid!(player.is_some() && usize::from(player.unwrap()) >= self.players.len());
 
+        if is_move(action) {
+            forbid!(self.status.is_terminal());
+            forbid!(self.state.outcome().is_final());
+        }
+
         match &action.action {
-            PlayerAction::Play(action) => self.state.apply(player, &action)?,
+            PlayerAction::Play(action) => {
+                self.state
+                    .apply(player, action)
+                    .map_err(|error| Error::rejected(player, error))?;
 
-            PlayerAction::Certify { address, signature } => {
+                self.draw.clear();
+                self.abort.clear();
+            }
+
+            PlayerAction::Certify {
+                address,
+                signature,
+                expiry,
+            } => {
                 forbid!(player.is_none());
 
                 forbid!(self.signatures.contains_key(address));
                 forbid!(self.approvals.contains_key(address));
+                forbid!(self.revocations.contains(address));
 
                 forbid!(
-                    crypto::recover(S::challenge(address).as_bytes(), signature)?
-                        != self.players[usize::from(player.unwrap())]
+                    S::Scheme::recover(
+                        &self.challenge(address, expiry.as_ref()),
+                        signature.as_ref()
+                    )? != self.players[usize::from(player.unwrap())]
                 );
 
-                self.signatures.insert(*address, *signature);
+                self.signatures
+                    .insert(*address, (*signature, expiry.clone()));
             }
 
             PlayerAction::Approve {
@@ -1187,9 +1960,70 @@ impl<S: State> ProofState<S> {
 
                 forbid!(self.signatures.contains_key(subkey));
                 forbid!(self.approvals.contains_key(subkey));
+                forbid!(self.revocations.contains(subkey));
 
                 self.approvals.insert(*subkey, (*player, *signature));
             }
+
+            PlayerAction::Revoke { subkey } => {
+                match player {
+                    Some(player) => {
+                        forbid!(!self.signatures.contains_key(subkey));
+
+                        let (signature, expiry) = &self.signatures[subkey];
+
+                        forbid!(
+                            S::Scheme::recover(
+                                &self.challenge(subkey, expiry.as_ref()),
+                                signature.as_ref()
+                            )? != self.players[usize::from(player)]
+                        );
+
+                        self.signatures.remove(subkey);
+                    }
+                    None => {
+                        forbid!(self.approvals.remove(subkey).is_none());
+                    }
+                }
+
+                self.revocations.insert(*subkey);
+            }
+
+            PlayerAction::Resign => {
+                forbid!(player.is_none());
+
+                self.status = MatchStatus::Resigned(player.unwrap());
+            }
+
+            PlayerAction::OfferDraw => {
+                forbid!(player.is_none());
+                forbid!(!self.draw.is_empty());
+
+                self.draw.insert(player.unwrap());
+            }
+
+            PlayerAction::AcceptDraw => {
+                forbid!(player.is_none());
+                forbid!(self.draw.is_empty());
+                forbid!(!self.draw.insert(player.unwrap()));
+            }
+
+            PlayerAction::Abort => {
+                forbid!(player.is_none());
+                forbid!(!self.abort.insert(player.unwrap()));
+            }
+
+            PlayerAction::Checkpoint { .. } => {
+                forbid!(player.is_some());
+            }
+        }
+
+        if self.draw.len() == self.players.len() {
+            self.status = MatchStatus::Drawn;
+        }
+
+        if self.abort.len() == self.players.len() {
+            self.status = MatchStatus::Aborted;
         }
 
         self.nonce = self.nonce.next();
@@ -1198,7 +2032,47 @@ impl<S: State> ProofState<S> {
     }
 }
 
+/// Layout of a binary [ProofState]
+///
+/// Hashes and signatures are computed over the layout a state was decoded from, so proofs remain
+/// verifiable after the layout changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Layout {
+    /// Untagged layout for two players, without subkey expiries, revocations, match status or
+    /// predecessors
+    ///
+    /// A state decoded from it keeps it until it has any of those.
+    V1 = 1,
+    /// Layout tagged with [LAYOUT_TAG]
+    V2 = 2,
+}
+
+impl Layout {
+    /// Consumes the layout tag of a binary [ProofState], if any.
+    fn read(data: &mut &[u8]) -> Result<Self, Error> {
+        match data.strip_prefix(&LAYOUT_TAG[..]) {
+            Some(rest) => {
+                *data = rest;
+
+                match utils::read_u8(data)? {
+                    2 => Ok(Self::V2),
+                    byte => Err(Error::decode(format!("byte == {}", byte))),
+                }
+            }
+            None => Ok(Self::V1),
+        }
+    }
+}
+
+/// Prefix of tagged [ProofState] layouts
+///
+/// A [Layout::V1] state begins with the length of its version, which is never this large.
+const LAYOUT_TAG: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+
 /// Attributable state transition
+///
+/// With the `serde-proofs` feature, this is also [serde::Serialize] and [serde::Deserialize] as a
+/// structured document.
 #[derive(derivative::Derivative, Clone)]
 #[derivative(Debug(bound = "PlayerAction<S>: Debug"))]
 pub struct ProofAction<S: State> {
@@ -1210,47 +2084,60 @@ pub struct ProofAction<S: State> {
 }
 
 impl<S: State> ProofAction<S> {
-    fn deserialize(mut data: &[u8]) -> Result<Self, String> {
+    fn deserialize(mut data: &[u8]) -> Result<Self, Error> {
         let player = match utils::read_u8(&mut data)? {
             0 => None,
             byte => Some(byte - 1),
         };
 
         let action = match utils::read_u8(&mut data)? {
-            0 => PlayerAction::Play(S::Action::deserialize(data)?),
+            0 => PlayerAction::Play(S::Action::deserialize(data).map_err(Error::decode)?),
             1 => {
-                forbid!(
-                    data.len() != size_of::<crypto::Address>() + size_of::<crypto::Signature>()
-                );
+                forbid!(data.len() < S::Scheme::ADDRESS_SIZE + S::Scheme::SIGNATURE_SIZE);
+
+                let address = S::Scheme::parse_address(&data[..S::Scheme::ADDRESS_SIZE])?;
 
-                let address = data[..size_of::<crypto::Address>()]
-                    .try_into()
-                    .map_err(|error| format!("{}", error))?;
+                let signature = S::Scheme::signature(
+                    &data[S::Scheme::ADDRESS_SIZE
+                        ..S::Scheme::ADDRESS_SIZE + S::Scheme::SIGNATURE_SIZE],
+                )?;
 
-                let signature = data[size_of::<crypto::Address>()..].try_into().unwrap();
+                data = &data[S::Scheme::ADDRESS_SIZE + S::Scheme::SIGNATURE_SIZE..];
+
+                let expiry = if data.is_empty() {
+                    None
+                } else {
+                    let expiry = S::Nonce::deserialize(&mut data).map_err(Error::decode)?;
+
+                    forbid!(!data.is_empty());
+
+                    Some(expiry)
+                };
 
-                PlayerAction::Certify { address, signature }
+                PlayerAction::Certify {
+                    address,
+                    signature,
+                    expiry,
+                }
             }
             2 => {
                 forbid!(
                     data.len()
-                        != size_of::<crypto::Address>()
-                            + size_of::<crypto::Address>()
-                            + size_of::<crypto::Signature>()
+                        != S::Scheme::ADDRESS_SIZE
+                            + S::Scheme::ADDRESS_SIZE
+                            + S::Scheme::SIGNATURE_SIZE
                 );
 
-                let player = data[..size_of::<crypto::Address>()]
-                    .try_into()
-                    .map_err(|error| format!("{}", error))?;
+                let player = S::Scheme::parse_address(&data[..S::Scheme::ADDRESS_SIZE])?;
 
-                let subkey = data[size_of::<crypto::Address>()
-                    ..size_of::<crypto::Address>() + size_of::<crypto::Address>()]
-                    .try_into()
-                    .map_err(|error| format!("{}", error))?;
+                let subkey = S::Scheme::parse_address(
+                    &data[S::Scheme::ADDRESS_SIZE
+                        ..S::Scheme::ADDRESS_SIZE + S::Scheme::ADDRESS_SIZE],
+                )?;
 
-                let signature = data[size_of::<crypto::Address>() + size_of::<crypto::Address>()..]
-                    .try_into()
-                    .unwrap();
+                let signature = S::Scheme::signature(
+                    &data[S::Scheme::ADDRESS_SIZE + S::Scheme::ADDRESS_SIZE..],
+                )?;
 
                 PlayerAction::Approve {
                     player,
@@ -1258,7 +2145,44 @@ impl<S: State> ProofAction<S> {
                     signature,
                 }
             }
-            byte => return Err(format!("byte == {}", byte)),
+            3 => {
+                forbid!(data.len() != S::Scheme::ADDRESS_SIZE);
+
+                PlayerAction::Revoke {
+                    subkey: S::Scheme::parse_address(data)?,
+                }
+            }
+            4 => {
+                forbid!(!data.is_empty());
+
+                PlayerAction::Resign
+            }
+            5 => {
+                forbid!(!data.is_empty());
+
+                PlayerAction::OfferDraw
+            }
+            6 => {
+                forbid!(!data.is_empty());
+
+                PlayerAction::AcceptDraw
+            }
+            7 => {
+                forbid!(!data.is_empty());
+
+                PlayerAction::Abort
+            }
+            8 => {
+                forbid!(data.len() % S::Scheme::SIGNATURE_SIZE != 0);
+
+                PlayerAction::Checkpoint {
+                    signatures: data
+                        .chunks_exact(S::Scheme::SIGNATURE_SIZE)
+                        .map(S::Scheme::signature)
+                        .collect::<Result<_, _>>()?,
+                }
+            }
+            byte => return Err(Error::decode(format!("byte == {}", byte))),
         };
 
         Ok(Self { player, action })
@@ -1280,14 +2204,21 @@ impl<S: State> ProofAction<S> {
                 data.extend(action);
             }
 
-            PlayerAction::Certify { address, signature } => {
+            PlayerAction::Certify {
+                address,
+                signature,
+                expiry,
+            } => {
+                let expiry = expiry.as_ref().map(Nonce::serialize).unwrap_or_default();
+
                 data.reserve_exact(
-                    1 + size_of::<crypto::Address>() + size_of::<crypto::Signature>(),
+                    1 + S::Scheme::ADDRESS_SIZE + S::Scheme::SIGNATURE_SIZE + expiry.len(),
                 );
 
                 utils::write_u8(&mut data, 1);
-                data.extend(address);
-                data.extend(signature.iter());
+                data.extend(address.as_ref());
+                data.extend(signature.as_ref());
+                data.extend(expiry);
             }
 
             PlayerAction::Approve {
@@ -1296,15 +2227,37 @@ impl<S: State> ProofAction<S> {
                 signature,
             } => {
                 data.reserve_exact(
-                    1 + size_of::<crypto::Address>()
-                        + size_of::<crypto::Address>()
-                        + size_of::<crypto::Signature>(),
+                    1 + S::Scheme::ADDRESS_SIZE
+                        + S::Scheme::ADDRESS_SIZE
+                        + S::Scheme::SIGNATURE_SIZE,
                 );
 
                 utils::write_u8(&mut data, 2);
-                data.extend(player);
-                data.extend(subkey);
-                data.extend(signature.iter());
+                data.extend(player.as_ref());
+                data.extend(subkey.as_ref());
+                data.extend(signature.as_ref());
+            }
+
+            PlayerAction::Revoke { subkey } => {
+                data.reserve_exact(1 + S::Scheme::ADDRESS_SIZE);
+
+                utils::write_u8(&mut data, 3);
+                data.extend(subkey.as_ref());
+            }
+
+            PlayerAction::Resign => utils::write_u8(&mut data, 4),
+            PlayerAction::OfferDraw => utils::write_u8(&mut data, 5),
+            PlayerAction::AcceptDraw => utils::write_u8(&mut data, 6),
+            PlayerAction::Abort => utils::write_u8(&mut data, 7),
+
+            PlayerAction::Checkpoint { signatures } => {
+                data.reserve_exact(1 + signatures.len() * S::Scheme::SIGNATURE_SIZE);
+
+                utils::write_u8(&mut data, 8);
+
+                for signature in signatures {
+                    data.extend(signature.as_ref());
+                }
             }
         }
 
@@ -1324,31 +2277,178 @@ pub enum PlayerAction<S: State> {
     Certify {
         /// The subkey address.
         #[derivative(Debug(format_with = "crate::crypto::fmt_address"))]
-        address: crypto::Address,
+        address: crypto::StateAddress<S>,
 
         /// The signature of the subkey challenge.
         ///
-        /// See [State::challenge].
+        /// See [ProofState::challenge].
         #[derivative(Debug(format_with = "crate::utils::fmt_hex"))]
-        signature: crypto::Signature,
+        signature: crypto::StateSignature<S>,
+
+        /// The last nonce for which the subkey is valid, if any.
+        #[derivative(Debug(format_with = "fmt_expiry"))]
+        expiry: Option<S::Nonce>,
     },
 
     /// A subkey approval.
     Approve {
         /// The player address.
         #[derivative(Debug(format_with = "crate::crypto::fmt_address"))]
-        player: crypto::Address,
+        player: crypto::StateAddress<S>,
 
         /// The subkey address.
         #[derivative(Debug(format_with = "crate::crypto::fmt_address"))]
-        subkey: crypto::Address,
+        subkey: crypto::StateAddress<S>,
 
         /// The owner's signature of the subkey approval.
         ///
         /// See [State::approval].
         #[derivative(Debug(format_with = "crate::utils::fmt_hex"))]
-        signature: crypto::Signature,
+        signature: crypto::StateSignature<S>,
     },
+
+    /// A subkey revocation.
+    ///
+    /// Players can revoke their certified subkeys, and the root author can revoke approved subkeys.
+    /// Revoked subkeys can't be certified or approved again.
+    Revoke {
+        /// The subkey address.
+        #[derivative(Debug(format_with = "crate::crypto::fmt_address"))]
+        subkey: crypto::StateAddress<S>,
+    },
+
+    /// A resignation, ending the match.
+    Resign,
+
+    /// A draw offer.
+    ///
+    /// Offers lapse on the next [PlayerAction::Play].
+    OfferDraw,
+
+    /// An acceptance of another player's draw offer.
+    ///
+    /// The match is drawn once every player has offered or accepted a draw.
+    AcceptDraw,
+
+    /// A request to abort the match.
+    ///
+    /// The match is aborted once every player has requested to abort it.
+    /// Requests lapse on the next [PlayerAction::Play].
+    Abort,
+
+    /// A checkpoint by the root author.
+    ///
+    /// See [Proof::checkpoint].
+    Checkpoint {
+        /// The latest signature of each player.
+        #[derivative(Debug(format_with = "crate::utils::fmt_hexes"))]
+        signatures: Vec<crypto::StateSignature<S>>,
+    },
+}
+
+/// Match status
+///
+/// See [Outcome], from which it is derived.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchStatus {
+    /// The match is in progress.
+    InProgress,
+
+    /// The match was won by a player.
+    Won(Player),
+
+    /// The match ended with the resignation of a player.
+    Resigned(Player),
+
+    /// The match ended in a draw.
+    Drawn,
+
+    /// The match was aborted by agreement.
+    Aborted,
+}
+
+impl MatchStatus {
+    /// Checks if the match has ended.
+    pub fn is_terminal(&self) -> bool {
+        *self != Self::InProgress
+    }
+}
+
+impl From<Outcome> for MatchStatus {
+    fn from(outcome: Outcome) -> Self {
+        match outcome {
+            Outcome::InProgress => Self::InProgress,
+            Outcome::Winner(player) => Self::Won(player),
+            Outcome::Draw => Self::Drawn,
+            Outcome::Resigned(player) => Self::Resigned(player),
+            Outcome::Aborted => Self::Aborted,
+        }
+    }
+}
+
+/// Match outcome
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The match is in progress.
+    InProgress,
+
+    /// The match was won by a player.
+    Winner(Player),
+
+    /// The match ended in a draw.
+    Draw,
+
+    /// The match ended with the resignation of a player.
+    Resigned(Player),
+
+    /// The match was aborted by agreement.
+    Aborted,
+}
+
+impl Outcome {
+    /// Checks if the outcome is final.
+    pub fn is_final(&self) -> bool {
+        *self != Self::InProgress
+    }
+
+    fn deserialize(data: &mut &[u8]) -> Result<Self, Error> {
+        match utils::read_u8(data)? {
+            0 => Ok(Self::InProgress),
+            1 => Ok(Self::Winner(utils::read_u8(data)?)),
+            2 => Ok(Self::Draw),
+            3 => Ok(Self::Resigned(utils::read_u8(data)?)),
+            4 => Ok(Self::Aborted),
+            byte => Err(Error::decode(format!("byte == {}", byte))),
+        }
+    }
+
+    fn serialize(&self, data: &mut Vec<u8>) {
+        match self {
+            Self::InProgress => utils::write_u8(data, 0),
+            Self::Winner(player) => {
+                utils::write_u8(data, 1);
+                utils::write_u8(data, *player);
+            }
+            Self::Draw => utils::write_u8(data, 2),
+            Self::Resigned(player) => {
+                utils::write_u8(data, 3);
+                utils::write_u8(data, *player);
+            }
+            Self::Aborted => utils::write_u8(data, 4),
+        }
+    }
+}
+
+impl core::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InProgress => write!(f, "in progress"),
+            Self::Winner(player) => write!(f, "player {} won", player),
+            Self::Draw => write!(f, "draw"),
+            Self::Resigned(player) => write!(f, "player {} resigned", player),
+            Self::Aborted => write!(f, "aborted"),
+        }
+    }
 }
 
 /// Player identifier
@@ -1365,26 +2465,27 @@ pub trait State: Clone {
     /// Action type
     type Action: Action;
 
+    /// Signature scheme type
+    ///
+    /// Use [crypto::Secp256k1] for Ethereum compatibility.
+    type Scheme: crypto::SignatureScheme;
+
     /// Gets the ABI version of this implementation.
     ///
     /// See [tag] and [version::version] for potentially helpful utilities.
     fn version() -> &'static [u8];
 
     /// Gets the challenge that must be signed in order to certify the subkey with the given address.
-    fn challenge(address: &crypto::Address) -> String {
-        format!(
-            "Sign to play! This won't cost anything.\n\n{}\n",
-            crypto::Addressable::eip55(address)
-        )
+    fn challenge(address: &crypto::StateAddress<Self>) -> String {
+        default_challenge(address.as_ref())
     }
 
     /// Gets the approval that must be signed by the owner in order to approve a subkey for a player.
-    fn approval(player: &crypto::Address, subkey: &crypto::Address) -> String {
-        format!(
-            "Approve {} for {}.",
-            crypto::Addressable::eip55(subkey),
-            crypto::Addressable::eip55(player),
-        )
+    fn approval(
+        player: &crypto::StateAddress<Self>,
+        subkey: &crypto::StateAddress<Self>,
+    ) -> String {
+        default_approval(player.as_ref(), subkey.as_ref())
     }
 
     /// Constructs a state from its binary representation.
@@ -1407,22 +2508,30 @@ pub trait State: Clone {
 
     /// Applies an action by a given player to the state.
     fn apply(&mut self, player: Option<Player>, action: &Self::Action) -> Result<(), String>;
+
+    /// Gets the outcome of the game.
+    ///
+    /// Once the outcome is final, no further [PlayerAction::Play] actions are accepted.
+    fn outcome(&self) -> Outcome {
+        Outcome::InProgress
+    }
 }
 
 impl<S: State> State for Box<S> {
     type ID = S::ID;
     type Nonce = S::Nonce;
     type Action = S::Action;
+    type Scheme = S::Scheme;
 
     fn version() -> &'static [u8] {
         S::version()
     }
 
-    fn challenge(address: &crypto::Address) -> String {
+    fn challenge(address: &crypto::StateAddress<S>) -> String {
         S::challenge(address)
     }
 
-    fn approval(player: &crypto::Address, subkey: &crypto::Address) -> String {
+    fn approval(player: &crypto::StateAddress<S>, subkey: &crypto::StateAddress<S>) -> String {
         S::approval(player, subkey)
     }
 
@@ -1441,6 +2550,76 @@ impl<S: State> State for Box<S> {
     fn apply(&mut self, player: Option<Player>, action: &Self::Action) -> Result<(), String> {
         self.deref_mut().apply(player, action)
     }
+
+    fn outcome(&self) -> Outcome {
+        self.deref().outcome()
+    }
+}
+
+/// Checks if an action is a move or changes the match status, which a match that has ended no
+/// longer accepts.
+fn is_move<S: State>(action: &ProofAction<S>) -> bool {
+    matches!(
+        action.action,
+        PlayerAction::Play(_)
+            | PlayerAction::Resign
+            | PlayerAction::OfferDraw
+            | PlayerAction::AcceptDraw
+            | PlayerAction::Abort
+    )
+}
+
+/// Gets the signing domain of the match with the given identifier.
+pub(crate) fn domain<S: State>(id: &S::ID) -> crypto::Domain<'static> {
+    crypto::Domain {
+        version: S::version(),
+        salt: crypto::keccak256(&id.serialize()),
+    }
+}
+
+/// Reads the game version from a binary [ProofState].
+pub(crate) fn state_version(mut data: &[u8]) -> Result<&[u8], Error> {
+    Layout::read(&mut data)?;
+
+    let size = utils::read_u32_usize(&mut data)?;
+
+    forbid!(data.len() < size);
+    Ok(&data[..size])
+}
+
+fn is_checkpoint<S: State>(actions: &[ProofAction<S>]) -> bool {
+    matches!(
+        actions,
+        [ProofAction {
+            player: None,
+            action: PlayerAction::Checkpoint { .. },
+        }]
+    )
+}
+
+fn fmt_expiry(
+    expiry: &Option<impl Nonce>,
+    f: &mut core::fmt::Formatter<'_>,
+) -> Result<(), core::fmt::Error> {
+    match expiry {
+        Some(expiry) => write!(f, "Some({})", utils::hex(&expiry.serialize())),
+        None => f.write_str("None"),
+    }
+}
+
+fn default_challenge(address: &[u8]) -> String {
+    format!(
+        "Sign to play! This won't cost anything.\n\n{}\n",
+        crypto::format_address(address)
+    )
+}
+
+fn default_approval(player: &[u8], subkey: &[u8]) -> String {
+    format!(
+        "Approve {} for {}.",
+        crypto::format_address(subkey),
+        crypto::format_address(player),
+    )
 }
 
 /// Domain-specific identifier trait
@@ -1487,6 +2666,13 @@ pub trait Nonce: Clone + Default {
 
     /// Gets the next nonce in sequence.
     fn next(&self) -> Self;
+
+    /// Compares the nonce with another, if they're ordered.
+    ///
+    /// Subkeys certified with an expiry are only valid for nonces that are ordered.
+    fn order(&self, _other: &Self) -> Option<Ordering> {
+        None
+    }
 }
 
 macro_rules! impl_Nonce {
@@ -1510,6 +2696,10 @@ macro_rules! impl_Nonce {
                 fn next(&self) -> Self {
                     self + 1
                 }
+
+                fn order(&self, other: &Self) -> Option<Ordering> {
+                    Some(self.cmp(other))
+                }
             }
         )*
     };
@@ -0,0 +1,59 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Decoding limits
+
+/// Bounds on binary representations from untrusted sources
+///
+/// Lengths are checked against these limits before anything is allocated for them.
+/// Decoders without a `limits` argument use [DecodeLimits::default].
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DecodeLimits {
+    /// Maximum number of actions in a diff, proof or root proof
+    pub max_actions: usize,
+    /// Maximum size in bytes of a serialized proof state
+    pub max_state_size: usize,
+    /// Maximum size in bytes of a serialized secret
+    pub max_secret_size: usize,
+    /// Maximum number of leaves in a Merkle tree or proof
+    pub max_merkle_leaves: usize,
+}
+
+impl DecodeLimits {
+    /// Default maximum number of actions in a diff, proof or root proof
+    pub const DEFAULT_MAX_ACTIONS: usize = 1 << 16;
+
+    /// Default maximum size in bytes of a serialized proof state
+    pub const DEFAULT_MAX_STATE_SIZE: usize = 1 << 24;
+
+    /// Default maximum size in bytes of a serialized secret
+    pub const DEFAULT_MAX_SECRET_SIZE: usize = 1 << 20;
+
+    /// Default maximum number of leaves in a Merkle tree or proof
+    pub const DEFAULT_MAX_MERKLE_LEAVES: usize = 1 << 16;
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_actions: Self::DEFAULT_MAX_ACTIONS,
+            max_state_size: Self::DEFAULT_MAX_STATE_SIZE,
+            max_secret_size: Self::DEFAULT_MAX_SECRET_SIZE,
+            max_merkle_leaves: Self::DEFAULT_MAX_MERKLE_LEAVES,
+        }
+    }
+}
@@ -0,0 +1,162 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Signed match results
+
+use {
+    crate::{crypto, Error, Outcome, Proof, State, ID},
+    alloc::{
+        string::{String, ToString},
+        vec::Vec,
+    },
+    core::{convert::TryInto, mem::size_of},
+    crypto::SignatureScheme,
+};
+
+/// Compact signed outcome of a final proof
+///
+/// See [Proof::result].
+#[derive(derivative::Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = "S::ID: core::fmt::Debug"))]
+pub struct MatchResult<S: State> {
+    id: S::ID,
+    proof: crypto::Hash,
+    outcome: Outcome,
+    signature: crypto::StateSignature<S>,
+    author: crypto::StateAddress<S>,
+}
+
+impl<S: State> MatchResult<S> {
+    pub(crate) fn new(
+        proof: &Proof<S>,
+        sign: &mut impl FnMut(&[u8]) -> Result<crypto::StateSignature<S>, String>,
+    ) -> Result<Self, Error> {
+        let outcome = proof.outcome();
+
+        crate::forbid!(!outcome.is_final());
+
+        let id = proof.state().id().clone();
+        let message = Self::message(&id, proof.hash(), outcome);
+        let signature = sign(&message).map_err(Error::bad_signature)?;
+
+        let result = Self {
+            author: S::Scheme::recover(&message, signature.as_ref())?,
+            id,
+            proof: *proof.hash(),
+            outcome,
+            signature,
+        };
+
+        result.verify(proof)?;
+
+        Ok(result)
+    }
+
+    /// Checks that the result belongs to the proof's match and was signed by its owner or one of
+    /// its players.
+    ///
+    /// Players may sign with any subkey that is current in `proof`.
+    pub fn verify(&self, proof: &Proof<S>) -> Result<(), Error> {
+        let owner = &proof.root.author;
+
+        crate::forbid!(self.id != *proof.state().id());
+
+        crate::forbid!(
+            self.author != *owner && proof.state().player(&self.author, owner).is_none()
+        );
+
+        Ok(())
+    }
+
+    /// Constructs a result from its binary representation.
+    ///
+    /// `data` must have been constructed using [MatchResult::serialize].
+    /// Its author must still be checked against the match using [MatchResult::verify].
+    pub fn deserialize(data: &[u8]) -> Result<Self, Error> {
+        Self::deserialize_unchecked(data).map_err(Error::decoding)
+    }
+
+    /// Generates a binary representation that can be used to reconstruct the result.
+    ///
+    /// See [MatchResult::deserialize].
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = self.id.serialize();
+
+        data.extend(&self.proof);
+
+        self.outcome.serialize(&mut data);
+
+        data.extend(self.signature.as_ref());
+
+        data
+    }
+
+    /// Gets the identifier of the match.
+    pub fn id(&self) -> &S::ID {
+        &self.id
+    }
+
+    /// Gets the hash of the final proof.
+    pub fn proof(&self) -> &crypto::Hash {
+        &self.proof
+    }
+
+    /// Gets the outcome of the match.
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+
+    /// Gets the address of the key that signed the result.
+    pub fn author(&self) -> &crypto::StateAddress<S> {
+        &self.author
+    }
+
+    fn message(id: &S::ID, proof: &crypto::Hash, outcome: Outcome) -> Vec<u8> {
+        S::Scheme::encode(&crypto::Message::Result {
+            domain: crate::domain::<S>(id),
+            proof,
+            outcome: &outcome.to_string(),
+        })
+    }
+
+    fn deserialize_unchecked(mut data: &[u8]) -> Result<Self, Error> {
+        let id = S::ID::deserialize(&mut data).map_err(Error::decode)?;
+
+        crate::forbid!(data.len() < size_of::<crypto::Hash>());
+
+        let proof: crypto::Hash = data[..size_of::<crypto::Hash>()]
+            .try_into()
+            .map_err(Error::decode)?;
+
+        data = &data[size_of::<crypto::Hash>()..];
+
+        let outcome = Outcome::deserialize(&mut data)?;
+
+        crate::forbid!(!outcome.is_final());
+
+        crate::forbid!(data.len() != S::Scheme::SIGNATURE_SIZE);
+
+        let signature = S::Scheme::signature(data)?;
+
+        Ok(Self {
+            author: S::Scheme::recover(&Self::message(&id, &proof, outcome), signature.as_ref())?,
+            id,
+            proof,
+            outcome,
+            signature,
+        })
+    }
+}
@@ -0,0 +1,162 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Linked match series
+
+use {
+    crate::{crypto, verify, Error, Outcome, Proof, State, Verification},
+    alloc::vec::Vec,
+    core::{convert::TryInto, mem::size_of},
+};
+
+/// Commitment to the final proof of a preceding match
+///
+/// See [ProofState::with_predecessors](crate::ProofState::with_predecessors).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Link {
+    proof: crypto::Hash,
+    outcome: Outcome,
+}
+
+impl Link {
+    /// Constructs a link to a final proof.
+    ///
+    /// See [Proof::is_final].
+    pub fn new<T: State>(proof: &Proof<T>) -> Result<Self, Error> {
+        crate::forbid!(!proof.is_final());
+
+        Ok(Self {
+            proof: *proof.hash(),
+            outcome: proof.outcome(),
+        })
+    }
+
+    /// Gets the hash of the preceding match's final proof.
+    pub fn proof(&self) -> &crypto::Hash {
+        &self.proof
+    }
+
+    /// Gets the outcome of the preceding match.
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+
+    pub(crate) fn deserialize(data: &mut &[u8]) -> Result<Self, Error> {
+        crate::forbid!(data.len() < size_of::<crypto::Hash>());
+
+        let proof = data[..size_of::<crypto::Hash>()]
+            .try_into()
+            .map_err(Error::decode)?;
+
+        *data = &data[size_of::<crypto::Hash>()..];
+
+        let outcome = Outcome::deserialize(data)?;
+
+        crate::forbid!(!outcome.is_final());
+
+        Ok(Self { proof, outcome })
+    }
+
+    pub(crate) fn serialize(&self, data: &mut Vec<u8>) {
+        data.extend(&self.proof);
+
+        self.outcome.serialize(data);
+    }
+}
+
+/// Validates a series of matches, each linked to the one before it.
+///
+/// Each element of `matches` is a root proof and a proof, as passed to [verify].
+/// Every match after the first must list a [Link] to the final proof of the previous match among
+/// its predecessors.
+///
+/// Matches with several predecessors, such as tournament brackets, can be validated one path at a
+/// time.
+pub fn verify_series<S: State>(
+    matches: &[(&[u8], &[u8])],
+    no_version_check: bool,
+) -> Result<Series<S>, Error> {
+    let mut verifications: Vec<Verification<S>> = Vec::with_capacity(matches.len());
+
+    for (root, proof) in matches {
+        let verification = verify::<S>(root, proof, no_version_check)?;
+
+        if let Some(previous) = verifications.last() {
+            crate::forbid!(!previous.state().outcome().is_final());
+
+            crate::forbid!(!verification.state().predecessors().contains(&Link {
+                proof: *previous.hash(),
+                outcome: previous.state().outcome(),
+            }));
+        }
+
+        verifications.push(verification);
+    }
+
+    Ok(Series {
+        matches: verifications,
+    })
+}
+
+/// Result of [verify_series]
+pub struct Series<S: State> {
+    matches: Vec<Verification<S>>,
+}
+
+impl<S: State> Series<S> {
+    /// Gets the verified matches, in order.
+    pub fn matches(&self) -> &[Verification<S>] {
+        &self.matches
+    }
+
+    /// Gets the outcome of each match, in order.
+    pub fn outcomes(&self) -> Vec<Outcome> {
+        self.matches
+            .iter()
+            .map(|verification| verification.state().outcome())
+            .collect()
+    }
+
+    /// Gets the address of the winner of each match, in order, or [None] if it had no winner.
+    ///
+    /// In two-player matches, the opponent of a resigning player is the winner.
+    pub fn winners(&self) -> Vec<Option<crypto::StateAddress<S>>> {
+        self.matches
+            .iter()
+            .map(|verification| {
+                let players = verification.state().players();
+
+                match verification.state().outcome() {
+                    Outcome::Winner(player) => players.get(usize::from(player)).copied(),
+                    Outcome::Resigned(player) if players.len() == 2 => {
+                        players.get(1 - usize::from(player)).copied()
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Counts the wins of the given address across the series.
+    ///
+    /// See [Series::winners].
+    pub fn wins(&self, address: &crypto::StateAddress<S>) -> usize {
+        self.winners()
+            .iter()
+            .filter(|winner| winner.as_ref() == Some(address))
+            .count()
+    }
+}
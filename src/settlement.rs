@@ -0,0 +1,307 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Ethereum settlement
+//!
+//! A [Settlement] is the ABI encoding of a final [Proof] for [VERIFIER], a reference Solidity
+//! contract that checks its signatures with `ecrecover`.
+//! Only [crypto::Secp256k1] proofs can be settled.
+
+use {
+    crate::{crypto, utils, Error, Proof, ProofAction, ProofState, State},
+    alloc::{vec, vec::Vec},
+    core::{convert::TryInto, mem::size_of},
+};
+
+/// Source of the reference Solidity verifier
+///
+/// See [verify] for a Rust equivalent of its `verify(bytes)` function.
+/// `cargo test -- --ignored` checks both agree in an EVM, given `solc` or `$SOLC`.
+pub const VERIFIER: &str = include_str!("settlement/Verifier.sol");
+
+const WORD: usize = 32;
+
+/// ABI-encodable final proof
+///
+/// Mirrors the layout of [Proof::serialize]: the head state, the actions, and the signed action
+/// range of each player, owner first.
+/// Ranges that don't start at the head carry the state they were signed against.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settlement {
+    state: Vec<u8>,
+    actions: Vec<Vec<u8>>,
+    ranges: Vec<Option<SignedRange>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct SignedRange {
+    start: usize,
+    end: usize,
+    state: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl Settlement {
+    /// Constructs a settlement for a final proof.
+    ///
+    /// See [Proof::is_final].
+    pub fn new<S: State<Scheme = crypto::Secp256k1>>(proof: &Proof<S>) -> Result<Self, Error> {
+        crate::forbid!(!proof.is_final());
+
+        let serialize = |state: &ProofState<S>| {
+            state
+                .serialize()
+                .ok_or_else(|| crate::forbidden!("state.serialize().is_none()"))
+        };
+
+        let state = proof
+            .proofs
+            .iter()
+            .filter_map(Option::as_ref)
+            .find(|proof| proof.range.start == 0)
+            .ok_or_else(|| crate::forbidden!("no signed range starts at 0"))?;
+
+        Ok(Self {
+            state: serialize(&state.state)?,
+            actions: proof.actions.iter().map(ProofAction::serialize).collect(),
+            ranges: proof
+                .proofs
+                .iter()
+                .map(|proof| {
+                    proof
+                        .as_ref()
+                        .map(|proof| {
+                            Ok(SignedRange {
+                                start: proof.range.start,
+                                end: proof.range.end,
+                                state: if proof.range.start == 0 {
+                                    Vec::new()
+                                } else {
+                                    serialize(&proof.state)?
+                                },
+                                signature: proof.signature.as_ref().to_vec(),
+                            })
+                        })
+                        .transpose()
+                })
+                .collect::<Result<_, Error>>()?,
+        })
+    }
+
+    /// Generates the argument to the verifier's `verify(bytes)` function.
+    ///
+    /// This is `abi.encode(bytes state, bytes[] actions, Range[] ranges)`, where
+    /// `Range` is `(uint32 start, uint32 end, bytes state, bytes signature)`.
+    /// Absent ranges are encoded with empty signatures.
+    pub fn encode(&self) -> Vec<u8> {
+        encode_sequence(&[
+            Token::Bytes(&self.state),
+            Token::Array(
+                self.actions
+                    .iter()
+                    .map(|action| Token::Bytes(action))
+                    .collect(),
+            ),
+            Token::Array(
+                self.ranges
+                    .iter()
+                    .map(|range| match range {
+                        Some(range) => Token::Tuple(vec![
+                            Token::Uint(range.start),
+                            Token::Uint(range.end),
+                            Token::Bytes(&range.state),
+                            Token::Bytes(&range.signature),
+                        ]),
+                        None => Token::Tuple(vec![
+                            Token::Uint(0),
+                            Token::Uint(0),
+                            Token::Bytes(&[]),
+                            Token::Bytes(&[]),
+                        ]),
+                    })
+                    .collect(),
+            ),
+        ])
+    }
+}
+
+/// Verifies an encoded settlement exactly as [VERIFIER] does.
+///
+/// `data` must have been constructed using [Settlement::encode].
+/// Returns the hash of the settled proof, and the signer of each range, owner first.
+pub fn verify(data: &[u8]) -> Result<(crypto::Hash, Vec<Option<crypto::Address>>), Error> {
+    let state = read_bytes(tail(data, 0)?)?;
+
+    let actions = tail(data, WORD)?;
+    let actions = (0..read_uint(actions)?)
+        .map(|i| read_bytes(tail(&actions[WORD..], i * WORD)?))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut serialized = Vec::new();
+
+    utils::write_u32_usize(&mut serialized, state.len())?;
+    serialized.extend(state);
+    utils::write_u32_usize(&mut serialized, actions.len())?;
+
+    for action in &actions {
+        utils::write_u32_usize(&mut serialized, action.len())?;
+        serialized.extend(*action);
+    }
+
+    let ranges = tail(data, 2 * WORD)?;
+    let mut signers = Vec::new();
+
+    for i in 0..read_uint(ranges)? {
+        let range = tail(&ranges[WORD..], i * WORD)?;
+
+        let start = read_uint(range)?;
+        let end = read_uint(&range[WORD..])?;
+        let range_state = read_bytes(tail(range, 2 * WORD)?)?;
+        let signature = read_bytes(tail(range, 3 * WORD)?)?;
+
+        if signature.is_empty() {
+            crate::forbid!(start != 0 || end != 0 || !range_state.is_empty());
+
+            utils::write_u8_bool(&mut serialized, false);
+            signers.push(None);
+
+            continue;
+        }
+
+        crate::forbid!(start > end || end > actions.len());
+        crate::forbid!((start == 0) != range_state.is_empty());
+
+        let mut message = if start == 0 { state } else { range_state }.to_vec();
+
+        for action in &actions[start..end] {
+            message.extend(*action);
+        }
+
+        // ecrecover only accepts the Ethereum recovery ids
+
+        crate::forbid!(!matches!(signature.last(), Some(27) | Some(28)));
+
+        signers.push(Some(crypto::recover(&message, signature)?));
+
+        utils::write_u8_bool(&mut serialized, true);
+        utils::write_u32_usize(&mut serialized, start)?;
+        utils::write_u32_usize(&mut serialized, end)?;
+        serialized.extend(signature);
+    }
+
+    Ok((crypto::keccak256(&serialized), signers))
+}
+
+enum Token<'a> {
+    Uint(usize),
+    Bytes(&'a [u8]),
+    Array(Vec<Token<'a>>),
+    Tuple(Vec<Token<'a>>),
+}
+
+impl Token<'_> {
+    fn is_dynamic(&self) -> bool {
+        match self {
+            Token::Uint(_) => false,
+            Token::Bytes(_) | Token::Array(_) => true,
+            Token::Tuple(tokens) => tokens.iter().any(Token::is_dynamic),
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Token::Uint(value) => word(*value),
+            Token::Bytes(bytes) => {
+                let mut data = word(bytes.len());
+
+                data.extend(*bytes);
+                data.resize(WORD + bytes.len().div_ceil(WORD) * WORD, 0);
+
+                data
+            }
+            Token::Array(tokens) => [word(tokens.len()), encode_sequence(tokens)].concat(),
+            Token::Tuple(tokens) => encode_sequence(tokens),
+        }
+    }
+}
+
+fn encode_sequence(tokens: &[Token<'_>]) -> Vec<u8> {
+    let encodings: Vec<_> = tokens.iter().map(Token::encode).collect();
+
+    let mut offset: usize = tokens
+        .iter()
+        .zip(&encodings)
+        .map(|(token, encoding)| {
+            if token.is_dynamic() {
+                WORD
+            } else {
+                encoding.len()
+            }
+        })
+        .sum();
+
+    let mut heads = Vec::new();
+    let mut tails = Vec::new();
+
+    for (token, encoding) in tokens.iter().zip(encodings) {
+        if token.is_dynamic() {
+            heads.extend(word(offset));
+            offset += encoding.len();
+            tails.extend(encoding);
+        } else {
+            heads.extend(encoding);
+        }
+    }
+
+    [heads, tails].concat()
+}
+
+fn word(value: usize) -> Vec<u8> {
+    let mut data = vec![0; WORD];
+
+    data[WORD - size_of::<u64>()..].copy_from_slice(&(value as u64).to_be_bytes());
+
+    data
+}
+
+fn read_uint(data: &[u8]) -> Result<usize, Error> {
+    crate::forbid!(data.len() < WORD);
+    crate::forbid!(data[..WORD - size_of::<u32>()]
+        .iter()
+        .any(|byte| *byte != 0));
+
+    u32::from_be_bytes(data[WORD - size_of::<u32>()..WORD].try_into().unwrap())
+        .try_into()
+        .map_err(Error::decode)
+}
+
+fn read_bytes(data: &[u8]) -> Result<&[u8], Error> {
+    let length = read_uint(data)?;
+
+    crate::forbid!(data.len() - WORD < length);
+
+    Ok(&data[WORD..WORD + length])
+}
+
+fn tail(data: &[u8], head: usize) -> Result<&[u8], Error> {
+    crate::forbid!(data.len() < head);
+
+    let offset = read_uint(&data[head..])?;
+
+    crate::forbid!(data.len() < offset);
+
+    Ok(&data[offset..])
+}
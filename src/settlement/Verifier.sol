@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: Apache-2.0
+pragma solidity ^0.8.0;
+
+/// @title Reference verifier for arcadeum settlements
+/// @notice Checks settlements constructed using `arcadeum::settlement::Settlement::encode`.
+/// @dev `arcadeum::settlement::verify` mirrors this contract step by step.
+contract ArcadeumVerifier {
+    struct Range {
+        uint32 start;
+        uint32 end;
+        bytes state;
+        bytes signature;
+    }
+
+    /// @notice Verifies the signatures of a settlement.
+    /// @param settlement `abi.encode(bytes state, bytes[] actions, Range[] ranges)`
+    /// @return proof The hash of the settled proof, as computed by `Proof::hash`.
+    /// @return signers The signer of each range, owner first, or the zero address if absent.
+    function verify(bytes calldata settlement)
+        external
+        pure
+        returns (bytes32 proof, address[] memory signers)
+    {
+        (bytes memory state, bytes[] memory actions, Range[] memory ranges) = abi.decode(
+            settlement,
+            (bytes, bytes[], Range[])
+        );
+
+        bytes memory serialized = abi.encodePacked(_u32(state.length), state, _u32(actions.length));
+
+        for (uint256 i = 0; i < actions.length; i++) {
+            serialized = abi.encodePacked(serialized, _u32(actions[i].length), actions[i]);
+        }
+
+        signers = new address[](ranges.length);
+
+        for (uint256 i = 0; i < ranges.length; i++) {
+            Range memory range = ranges[i];
+
+            if (range.signature.length == 0) {
+                require(range.start == 0 && range.end == 0 && range.state.length == 0, "range");
+
+                serialized = abi.encodePacked(serialized, bytes1(0x00));
+
+                continue;
+            }
+
+            require(range.start <= range.end && range.end <= actions.length, "range");
+            require((range.start == 0) == (range.state.length == 0), "state");
+
+            bytes memory message = range.start == 0 ? state : range.state;
+
+            for (uint256 j = range.start; j < range.end; j++) {
+                message = abi.encodePacked(message, actions[j]);
+            }
+
+            signers[i] = _recover(message, range.signature);
+
+            serialized = abi.encodePacked(
+                serialized,
+                bytes1(0x01),
+                _u32(range.start),
+                _u32(range.end),
+                range.signature
+            );
+        }
+
+        proof = keccak256(serialized);
+    }
+
+    function _recover(bytes memory message, bytes memory signature)
+        private
+        pure
+        returns (address signer)
+    {
+        require(signature.length == 65, "signature");
+
+        bytes32 r;
+        bytes32 s;
+        uint8 v;
+
+        assembly {
+            r := mload(add(signature, 32))
+            s := mload(add(signature, 64))
+            v := byte(0, mload(add(signature, 96)))
+        }
+
+        signer = ecrecover(
+            keccak256(
+                abi.encodePacked("\x19Ethereum Signed Message:\n", _decimal(message.length), message)
+            ),
+            v,
+            r,
+            s
+        );
+
+        require(signer != address(0), "signature");
+    }
+
+    function _u32(uint256 value) private pure returns (bytes4) {
+        require(value <= type(uint32).max, "u32");
+
+        return
+            bytes4(
+                uint32(
+                    ((value << 24) & 0xff000000) |
+                        ((value << 8) & 0xff0000) |
+                        ((value >> 8) & 0xff00) |
+                        (value >> 24)
+                )
+            );
+    }
+
+    function _decimal(uint256 value) private pure returns (bytes memory digits) {
+        if (value == 0) {
+            return "0";
+        }
+
+        for (uint256 rest = value; rest != 0; rest /= 10) {
+            digits = abi.encodePacked(bytes1(uint8(48 + (rest % 10))), digits);
+        }
+    }
+}
@@ -0,0 +1,310 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Asynchronous client store
+
+use {
+    super::{
+        shared::{Lock, Shared},
+        MaybeSend, State, Store, StoreBuilder, StoreDiff, StoreHooks, StoreState,
+    },
+    alloc::{boxed::Box, collections::VecDeque, string::String, vec::Vec},
+    core::{
+        future::Future,
+        mem::take,
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    },
+};
+
+type Signature<S> = crate::crypto::StateSignature<StoreState<S>>;
+
+type SignatureFuture<S> = Pin<Box<dyn_send!(Future<Output = Result<Signature<S>, String>>)>>;
+
+type AsyncSigner<S> = Box<dyn_send!(FnMut(&[u8]) -> SignatureFuture<S>)>;
+
+/// Output of an [AsyncStore]
+#[derive(derivative::Derivative)]
+#[derivative(Debug(
+    bound = "StoreDiff<S>: core::fmt::Debug, S: core::fmt::Debug, S::Event: core::fmt::Debug"
+))]
+pub enum StoreEvent<S: State> {
+    /// A diff to send to the other participants
+    Send(StoreDiff<S>),
+    /// An event logged by the state
+    Log(Option<crate::Player>, S::Event),
+    /// The state is ready for the next action.
+    Ready(S),
+}
+
+/// Client [State] store with an asynchronous signer
+///
+/// Diffs to send, logs, and readiness notifications are delivered through [AsyncStore::events]
+/// instead of callbacks.
+///
+/// Diffs are generated unsigned, so the store is unchanged until the signer has produced each of
+/// their signatures.
+/// See [crate::UnsignedDiff].
+pub struct AsyncStore<S: State> {
+    store: Store<S>,
+    sign: AsyncSigner<S>,
+    outbox: Shared<Lock<Outbox<S>>>,
+}
+
+impl<S: State + 'static> AsyncStore<S>
+where
+    StoreEvent<S>: MaybeSend,
+{
+    /// Constructs a new store.
+    ///
+    /// You should call [AsyncStore::flush] on the new store.
+    ///
+    /// See [StoreBuilder::build].
+    pub fn new(
+        builder: StoreBuilder<S, AsyncHooks<S>>,
+        root: &[u8],
+        secrets: Vec<Option<(S::Secret, [u8; 16])>>,
+        sign: impl FnMut(&[u8]) -> SignatureFuture<S> + MaybeSend + 'static,
+    ) -> Result<Self, crate::Error> {
+        let outbox = builder.hooks().outbox.clone();
+
+        Ok(Self {
+            store: builder.build(root, secrets)?,
+            sign: Box::new(sign),
+            outbox,
+        })
+    }
+
+    /// Constructs a store from its binary representation.
+    ///
+    /// You should call [AsyncStore::flush] on the new store.
+    ///
+    /// See [StoreBuilder::deserialize].
+    pub fn deserialize(
+        builder: StoreBuilder<S, AsyncHooks<S>>,
+        data: &[u8],
+        sign: impl FnMut(&[u8]) -> SignatureFuture<S> + MaybeSend + 'static,
+    ) -> Result<Self, crate::Error> {
+        let outbox = builder.hooks().outbox.clone();
+
+        Ok(Self {
+            store: builder.deserialize(data)?,
+            sign: Box::new(sign),
+            outbox,
+        })
+    }
+
+    /// Gets the underlying store.
+    ///
+    /// Signing operations on the underlying store fail, since it has no synchronous signer.
+    pub fn store(&self) -> &Store<S> {
+        &self.store
+    }
+
+    /// Gets the stream of diffs to send, logs, and readiness notifications.
+    ///
+    /// The stream ends once the store is dropped and its remaining events are consumed.
+    /// Every pending stream is woken by a new event, but each event is delivered to only one of
+    /// them.
+    pub fn events(&self) -> StoreEvents<S> {
+        StoreEvents {
+            outbox: self.outbox.clone(),
+        }
+    }
+
+    /// Generates a diff that can be applied to a store with the same state.
+    ///
+    /// See [Store::diff].
+    pub async fn diff(
+        &mut self,
+        actions: Vec<crate::ProofAction<StoreState<S>>>,
+    ) -> Result<StoreDiff<S>, crate::Error> {
+        let diff = self.store.unsigned_diff(actions)?;
+
+        self.sign(diff).await
+    }
+
+    /// Verifies and applies a cryptographically constructed diff to the store, then flushes it.
+    ///
+    /// Resolves once any actions the client is required to send have been signed and queued.
+    ///
+    /// See [Store::apply].
+    pub async fn apply(&mut self, diff: &StoreDiff<S>) -> Result<(), crate::Error> {
+        self.store.raw_apply(diff)?;
+
+        self.flush().await
+    }
+
+    /// Dispatches any actions the client is required to send.
+    ///
+    /// See [Store::flush].
+    pub async fn flush(&mut self) -> Result<(), crate::Error> {
+        loop {
+            let actions = self.store.flush_actions(true)?;
+
+            let diff = if !actions.is_empty() {
+                let player = self.store.player();
+
+                self.diff(
+                    actions
+                        .into_iter()
+                        .map(|action| crate::ProofAction {
+                            player,
+                            action: crate::PlayerAction::Play(action),
+                        })
+                        .collect(),
+                )
+                .await?
+            } else if self.store.is_checkpoint_due() {
+                let diff = self.store.unsigned_checkpoint()?;
+
+                self.sign(diff).await?
+            } else {
+                break;
+            };
+
+            push(&self.outbox, StoreEvent::Send(diff.clone()));
+
+            self.store.raw_apply(&diff)?;
+        }
+
+        // no actions remain and no checkpoint is due, so this only notifies readiness
+
+        self.store.flush()
+    }
+
+    async fn sign(
+        &mut self,
+        mut diff: crate::UnsignedDiff<StoreState<S>>,
+    ) -> Result<StoreDiff<S>, crate::Error> {
+        loop {
+            let signature = (self.sign)(&diff.message())
+                .await
+                .map_err(crate::Error::bad_signature)?;
+
+            if let Some(diff) = diff.sign(signature)? {
+                return Ok(diff);
+            }
+        }
+    }
+}
+
+impl<S: State> Drop for AsyncStore<S> {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut outbox = self.outbox.borrow_mut();
+
+            outbox.closed = true;
+            take(&mut outbox.wakers)
+        };
+
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// Stream of [StoreEvent]s from an [AsyncStore]
+///
+/// See [AsyncStore::events].
+pub struct StoreEvents<S: State> {
+    outbox: Shared<Lock<Outbox<S>>>,
+}
+
+impl<S: State> futures_core::Stream for StoreEvents<S> {
+    type Item = StoreEvent<S>;
+
+    fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        let mut outbox = self.outbox.borrow_mut();
+
+        match outbox.events.pop_front() {
+            Some(event) => Poll::Ready(Some(event)),
+            None if outbox.closed => Poll::Ready(None),
+            None => {
+                if !outbox
+                    .wakers
+                    .iter()
+                    .any(|waker| waker.will_wake(context.waker()))
+                {
+                    outbox.wakers.push(context.waker().clone());
+                }
+
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[derive(derivative::Derivative)]
+#[derivative(Default(bound = ""))]
+struct Outbox<S: State> {
+    events: VecDeque<StoreEvent<S>>,
+    wakers: Vec<Waker>,
+    closed: bool,
+}
+
+fn push<S: State>(outbox: &Shared<Lock<Outbox<S>>>, event: StoreEvent<S>) {
+    let wakers = {
+        let mut outbox = outbox.borrow_mut();
+
+        outbox.events.push_back(event);
+        take(&mut outbox.wakers)
+    };
+
+    wakers.into_iter().for_each(Waker::wake);
+}
+
+/// [StoreHooks] of an [AsyncStore]
+///
+/// Diffs to send, logs, and readiness notifications are queued for [AsyncStore::events].
+/// Messages can't be signed synchronously, since [AsyncStore] signs them with its own signer.
+pub struct AsyncHooks<S: State> {
+    outbox: Shared<Lock<Outbox<S>>>,
+    random: Box<dyn_send!(rand::RngCore)>,
+}
+
+impl<S: State> AsyncHooks<S> {
+    /// Constructs hooks with the given source of randomness.
+    pub fn new(random: impl rand::RngCore + MaybeSend + 'static) -> Self {
+        Self {
+            outbox: Default::default(),
+            random: Box::new(random),
+        }
+    }
+}
+
+impl<S: State> StoreHooks<S> for AsyncHooks<S>
+where
+    StoreEvent<S>: MaybeSend,
+{
+    fn on_ready(&mut self, state: &S, _secrets: &[Option<&S::Secret>]) {
+        push(&self.outbox, StoreEvent::Ready(state.clone()));
+    }
+
+    fn sign(&mut self, _message: &[u8]) -> Result<Signature<S>, String> {
+        Err("AsyncStore signs asynchronously".into())
+    }
+
+    fn send(&mut self, diff: &StoreDiff<S>) {
+        push(&self.outbox, StoreEvent::Send(diff.clone()));
+    }
+
+    fn on_event(&mut self, player: Option<crate::Player>, event: S::Event) {
+        push(&self.outbox, StoreEvent::Log(player, event));
+    }
+
+    fn random(&mut self) -> &mut dyn rand::RngCore {
+        &mut *self.random
+    }
+}
@@ -16,7 +16,10 @@
 
 //! WebAssembly-specific utilities
 
-use core::{convert::TryInto, num::NonZeroU32};
+use {
+    alloc::{format, string::String, vec::Vec},
+    core::{convert::TryInto, num::NonZeroU32},
+};
 
 /// Generates WebAssembly bindings for a [super::State].
 #[macro_export]
@@ -26,6 +29,7 @@ macro_rules! bind {
         pub struct WasmMatch {
             store: $crate::store::Store<$type>,
             send: js_sys::Function,
+            format: std::rc::Rc<std::cell::Cell<$crate::Format>>,
         }
 
         #[wasm_bindgen::prelude::wasm_bindgen]
@@ -42,90 +46,55 @@ macro_rules! bind {
                 log: js_sys::Function,
                 random: js_sys::Function,
                 no_version_check: Option<bool>,
+                limits: wasm_bindgen::JsValue,
             ) -> Result<WasmMatch, wasm_bindgen::JsValue> {
+                let limits = $crate::utils::from_js::<Option<$crate::DecodeLimits>>(limits)?
+                    .unwrap_or_default();
+
+                let format = std::rc::Rc::new(std::cell::Cell::new($crate::Format::V1));
+
                 Ok(Self {
                     store: {
-                        $crate::store::Store::new(
-                            player,
-                            root,
-                            match player {
-                                None => $crate::utils::from_js(secret)?,
-                                Some(0) => [Some($crate::utils::from_js(secret)?), None],
-                                Some(1) => [None, Some($crate::utils::from_js(secret)?)],
-                                _ => return Err("player.is_some() && player.unwrap() >= 2".into()),
-                            },
-                            p2p,
-                            move |state, secrets| {
-                                if let Ok(state) = $crate::utils::to_js(state) {
-                                    match secrets {
-                                        [Some(secret1), Some(secret2)] => {
-                                            drop(
-                                                ready.call3(
-                                                    &wasm_bindgen::JsValue::UNDEFINED,
-                                                    &state,
-                                                    &$crate::utils::to_js(secret1)
-                                                        .unwrap_or(wasm_bindgen::JsValue::NULL),
-                                                    &$crate::utils::to_js(secret2)
-                                                        .unwrap_or(wasm_bindgen::JsValue::NULL),
-                                                ),
-                                            );
-                                        }
-                                        [Some(secret), None] | [None, Some(secret)] => {
-                                            drop(
-                                                ready.call2(
-                                                    &wasm_bindgen::JsValue::UNDEFINED,
-                                                    &state,
-                                                    &$crate::utils::to_js(secret)
-                                                        .unwrap_or(wasm_bindgen::JsValue::NULL),
-                                                ),
-                                            );
-                                        }
-                                        [None, None] => {
-                                            drop(
-                                                ready.call1(
-                                                    &wasm_bindgen::JsValue::UNDEFINED,
-                                                    &state,
-                                                ),
-                                            );
-                                        }
-                                    }
-                                }
-                            },
-                            move |message| {
-                                std::convert::TryInto::try_into($crate::utils::from_js::<Vec<_>>(
-                                    sign.call1(
-                                        &wasm_bindgen::JsValue::UNDEFINED,
-                                        &$crate::utils::to_js(message)?,
-                                    )
-                                    .map_err(|error| format!("{:?}", error))?,
-                                )?)
-                                .map_err(|error| format!("{:?}", error))
-                            },
-                            {
-                                let send = send.clone();
-
-                                move |diff| {
-                                    if let Ok(value) = &$crate::utils::to_js(&diff.serialize()) {
-                                        drop(send.call1(&wasm_bindgen::JsValue::UNDEFINED, value));
-                                    }
-                                }
+                        let secrets = match player {
+                            None => $crate::utils::from_js(secret)?,
+                            Some(player) => {
+                                let players = $crate::RootProof::<
+                                    $crate::store::StoreState<$type>,
+                                >::player_count(root)?;
+
+                                let mut secrets = vec![None; players];
+
+                                *secrets
+                                    .get_mut(usize::from(player))
+                                    .ok_or("usize::from(player) >= players")? =
+                                    Some($crate::utils::from_js(secret)?);
+
+                                secrets
+                            }
+                        };
+
+                        let mut store = $crate::store::StoreBuilder::new(
+                            $crate::store::bindings::JsHooks {
+                                ready,
+                                sign,
+                                send: send.clone(),
+                                log,
+                                random: $crate::store::bindings::JsRng(random),
+                                format: format.clone(),
                             },
-                            move |target, event| {
-                                if let (Ok(target), Ok(event)) =
-                                    ($crate::utils::to_js(&target), $crate::utils::to_js(&event))
-                                {
-                                    drop(log.call2(
-                                        &wasm_bindgen::JsValue::UNDEFINED,
-                                        &target,
-                                        &event,
-                                    ));
-                                }
-                            },
-                            $crate::store::bindings::JsRng(random),
-                            no_version_check.unwrap_or(false),
-                        )?
+                        )
+                        .player(player)
+                        .p2p(p2p)
+                        .no_version_check(no_version_check.unwrap_or(false))
+                        .limits(limits)
+                        .build(root, secrets)?;
+
+                        store.set_clock($crate::store::bindings::JsClock);
+
+                        store
                     },
                     send,
+                    format,
                 })
             }
 
@@ -139,94 +108,71 @@ macro_rules! bind {
                 log: js_sys::Function,
                 random: js_sys::Function,
                 no_version_check: Option<bool>,
+                limits: wasm_bindgen::JsValue,
             ) -> Result<WasmMatch, wasm_bindgen::JsValue> {
+                let limits = $crate::utils::from_js::<Option<$crate::DecodeLimits>>(limits)?
+                    .unwrap_or_default();
+
+                let format = std::rc::Rc::new(std::cell::Cell::new($crate::Format::V1));
+
                 Ok(Self {
                     store: {
-                        $crate::store::Store::deserialize(
-                            data,
-                            p2p,
-                            move |state, secrets| {
-                                if let Ok(state) = $crate::utils::to_js(state) {
-                                    match secrets {
-                                        [Some(secret1), Some(secret2)] => {
-                                            drop(
-                                                ready.call3(
-                                                    &wasm_bindgen::JsValue::UNDEFINED,
-                                                    &state,
-                                                    &$crate::utils::to_js(secret1)
-                                                        .unwrap_or(wasm_bindgen::JsValue::NULL),
-                                                    &$crate::utils::to_js(secret2)
-                                                        .unwrap_or(wasm_bindgen::JsValue::NULL),
-                                                ),
-                                            );
-                                        }
-                                        [Some(secret), None] | [None, Some(secret)] => {
-                                            drop(
-                                                ready.call2(
-                                                    &wasm_bindgen::JsValue::UNDEFINED,
-                                                    &state,
-                                                    &$crate::utils::to_js(secret)
-                                                        .unwrap_or(wasm_bindgen::JsValue::NULL),
-                                                ),
-                                            );
-                                        }
-                                        [None, None] => {
-                                            drop(
-                                                ready.call1(
-                                                    &wasm_bindgen::JsValue::UNDEFINED,
-                                                    &state,
-                                                ),
-                                            );
-                                        }
-                                    }
-                                }
-                            },
-                            move |message| {
-                                std::convert::TryInto::try_into($crate::utils::from_js::<Vec<_>>(
-                                    sign.call1(
-                                        &wasm_bindgen::JsValue::UNDEFINED,
-                                        &$crate::utils::to_js(message)?,
-                                    )
-                                    .map_err(|error| format!("{:?}", error))?,
-                                )?)
-                                .map_err(|error| format!("{:?}", error))
-                            },
-                            {
-                                let send = send.clone();
-
-                                move |diff| {
-                                    if let Ok(value) = &$crate::utils::to_js(&diff.serialize()) {
-                                        drop(send.call1(&wasm_bindgen::JsValue::UNDEFINED, value));
-                                    }
-                                }
-                            },
-                            move |target, event| {
-                                if let (Ok(target), Ok(event)) =
-                                    ($crate::utils::to_js(&target), $crate::utils::to_js(&event))
-                                {
-                                    drop(log.call2(
-                                        &wasm_bindgen::JsValue::UNDEFINED,
-                                        &target,
-                                        &event,
-                                    ));
-                                }
+                        let mut store = $crate::store::StoreBuilder::new(
+                            $crate::store::bindings::JsHooks {
+                                ready,
+                                sign,
+                                send: send.clone(),
+                                log,
+                                random: $crate::store::bindings::JsRng(random),
+                                format: format.clone(),
                             },
-                            $crate::store::bindings::JsRng(random),
-                            no_version_check.unwrap_or(false),
-                        )?
+                        )
+                        .p2p(p2p)
+                        .no_version_check(no_version_check.unwrap_or(false))
+                        .limits(limits)
+                        .deserialize(data)?;
+
+                        store.set_clock($crate::store::bindings::JsClock);
+
+                        store
                     },
                     send,
+                    format,
                 })
             }
 
             #[wasm_bindgen::prelude::wasm_bindgen]
-            pub fn serialize(&self, secret_knowledge: u8) -> Vec<u8> {
-                self.store.serialize(match secret_knowledge {
-                    1 => $crate::store::SecretKnowledge::Some(0),
-                    2 => $crate::store::SecretKnowledge::Some(1),
-                    3 => $crate::store::SecretKnowledge::Both,
-                    _ => $crate::store::SecretKnowledge::None,
-                })
+            pub fn serialize(
+                &self,
+                secret_knowledge: u8,
+                format: Option<u8>,
+            ) -> Result<Vec<u8>, wasm_bindgen::JsValue> {
+                let format: $crate::Format = match format {
+                    Some(format) => std::convert::TryFrom::try_from(format)?,
+                    None => $crate::Format::V1,
+                };
+
+                Ok(self.store.serialize_with_format(
+                    secret_knowledge.into(),
+                    format,
+                ))
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(getter)]
+            pub fn format(&self) -> u8 {
+                self.format.get().into()
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = negotiateFormat)]
+            pub fn negotiate_format(&mut self, theirs: Vec<u8>) -> u8 {
+                let theirs: Vec<$crate::Format> = theirs
+                    .into_iter()
+                    .filter_map(|format| std::convert::TryFrom::try_from(format).ok())
+                    .collect();
+
+                self.format.set($crate::Format::negotiate(&theirs));
+
+                self.format.get().into()
             }
 
             #[wasm_bindgen::prelude::wasm_bindgen(getter)]
@@ -247,7 +193,7 @@ macro_rules! bind {
                         .state()
                         .players()
                         .iter()
-                        .map($crate::crypto::Addressable::eip55)
+                        .map(|player| $crate::crypto::format_address(player.as_ref()))
                         .collect::<Vec<_>>(),
                 )?)
             }
@@ -293,6 +239,26 @@ macro_rules! bind {
                 )?)
             }
 
+            #[wasm_bindgen::prelude::wasm_bindgen(getter)]
+            pub fn status(&self) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+                Ok($crate::utils::to_js(&self.store.status())?)
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(getter)]
+            pub fn outcome(&self) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+                Ok($crate::utils::to_js(&self.store.outcome())?)
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(getter, js_name = isFinal)]
+            pub fn is_final(&self) -> bool {
+                self.store.is_final()
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = getResult)]
+            pub fn result(&mut self) -> Result<Vec<u8>, wasm_bindgen::JsValue> {
+                Ok(self.store.result()?.serialize())
+            }
+
             #[wasm_bindgen::prelude::wasm_bindgen(getter, js_name = pendingPlayer)]
             pub fn pending_player(&self) -> Result<Option<$crate::Player>, wasm_bindgen::JsValue> {
                 Ok(self.store.pending_player()?)
@@ -321,7 +287,7 @@ macro_rules! bind {
                     &self.store.state().state().simulate(
                         player,
                         &$crate::utils::from_js(action)?,
-                        $crate::utils::from_js(using_secrets)?,
+                        &$crate::utils::from_js::<Vec<bool>>(using_secrets)?,
                     )?,
                 )?)
             }
@@ -345,17 +311,38 @@ macro_rules! bind {
 
                 self.send.call1(
                     &wasm_bindgen::JsValue::UNDEFINED,
-                    &$crate::utils::to_js(&diff.serialize())?,
+                    &$crate::utils::to_js(&diff.serialize_with_format(self.format.get()))?,
                 )?;
 
                 Ok(self.store.apply(&diff)?)
             }
 
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = dispatchResign)]
+            pub fn dispatch_resign(&mut self) -> Result<(), wasm_bindgen::JsValue> {
+                Ok(self.store.resign()?)
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = dispatchOfferDraw)]
+            pub fn dispatch_offer_draw(&mut self) -> Result<(), wasm_bindgen::JsValue> {
+                Ok(self.store.offer_draw()?)
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = dispatchAcceptDraw)]
+            pub fn dispatch_accept_draw(&mut self) -> Result<(), wasm_bindgen::JsValue> {
+                Ok(self.store.accept_draw()?)
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = dispatchAbort)]
+            pub fn dispatch_abort(&mut self) -> Result<(), wasm_bindgen::JsValue> {
+                Ok(self.store.abort()?)
+            }
+
             #[wasm_bindgen::prelude::wasm_bindgen(js_name = dispatchCertify)]
             pub fn dispatch_certify(
                 &mut self,
                 address: &str,
                 signature: &str,
+                expiry: Option<Vec<u8>>,
             ) -> Result<(), wasm_bindgen::JsValue> {
                 let address =
                     std::convert::TryInto::<_>::try_into($crate::utils::unhex(address)?.as_slice())
@@ -370,20 +357,52 @@ macro_rules! bind {
                     return Ok(());
                 }
 
-                let signature: $crate::crypto::Signature =
-                    std::convert::TryInto::try_into($crate::utils::unhex(signature)?)
-                        .map_err(|error| format!("{:?}", error))?;
+                let signature =
+                    <<$type as $crate::store::State>::Scheme as $crate::crypto::SignatureScheme>::signature(
+                        &$crate::utils::unhex(signature)?,
+                    )?;
 
-                let player = self.store.state().player(&$crate::crypto::recover(<$crate::store::StoreState<$type> as $crate::State>::challenge(&address).as_bytes(), &signature)?, self.store.owner()).ok_or("self.store.state().player(&$crate::crypto::recover(<$crate::store::StoreState<$type> as $crate::State>::challenge(&address).as_bytes(), &signature)?, self.store.owner()).is_none()")?;
+                let expiry = expiry
+                    .map(|expiry| {
+                        <<$type as $crate::store::State>::Nonce as $crate::Nonce>::deserialize(
+                            &mut expiry.as_slice(),
+                        )
+                    })
+                    .transpose()?;
+
+                let player = self.store.state().player(&<<$type as $crate::store::State>::Scheme as $crate::crypto::SignatureScheme>::recover(&self.store.state().challenge(&address, expiry.as_ref()), signature.as_ref())?, self.store.owner()).ok_or("self.store.state().player(&<<$type as $crate::store::State>::Scheme as $crate::crypto::SignatureScheme>::recover(&self.store.state().challenge(&address, expiry.as_ref()), signature.as_ref())?, self.store.owner()).is_none()")?;
 
                 let diff = self.store.diff(vec![$crate::ProofAction {
                     player: Some(player),
-                    action: $crate::PlayerAction::Certify { address, signature },
+                    action: $crate::PlayerAction::Certify {
+                        address,
+                        signature,
+                        expiry,
+                    },
                 }])?;
 
                 self.send.call1(
                     &wasm_bindgen::JsValue::UNDEFINED,
-                    &$crate::utils::to_js(&diff.serialize())?,
+                    &$crate::utils::to_js(&diff.serialize_with_format(self.format.get()))?,
+                )?;
+
+                Ok(self.store.apply(&diff)?)
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = dispatchRevoke)]
+            pub fn dispatch_revoke(&mut self, subkey: &str) -> Result<(), wasm_bindgen::JsValue> {
+                let subkey =
+                    std::convert::TryInto::<_>::try_into($crate::utils::unhex(subkey)?.as_slice())
+                        .map_err(|error| format!("{}", error))?;
+
+                let diff = self.store.diff(vec![$crate::ProofAction {
+                    player: self.store.player(),
+                    action: $crate::PlayerAction::Revoke { subkey },
+                }])?;
+
+                self.send.call1(
+                    &wasm_bindgen::JsValue::UNDEFINED,
+                    &$crate::utils::to_js(&diff.serialize_with_format(self.format.get()))?,
                 )?;
 
                 Ok(self.store.apply(&diff)?)
@@ -413,9 +432,10 @@ macro_rules! bind {
                     std::convert::TryInto::<_>::try_into($crate::utils::unhex(player)?.as_slice())
                         .map_err(|error| format!("{}", error))?;
 
-                let signature: $crate::crypto::Signature =
-                    std::convert::TryInto::try_into($crate::utils::unhex(signature)?)
-                        .map_err(|error| format!("{:?}", error))?;
+                let signature =
+                    <<$type as $crate::store::State>::Scheme as $crate::crypto::SignatureScheme>::signature(
+                        &$crate::utils::unhex(signature)?,
+                    )?;
 
                 let diff = self.store.diff(vec![$crate::ProofAction {
                     player: None,
@@ -428,7 +448,7 @@ macro_rules! bind {
 
                 self.send.call1(
                     &wasm_bindgen::JsValue::UNDEFINED,
-                    &$crate::utils::to_js(&diff.serialize())?,
+                    &$crate::utils::to_js(&diff.serialize_with_format(self.format.get()))?,
                 )?;
 
                 Ok(self.store.apply(&diff)?)
@@ -441,13 +461,99 @@ macro_rules! bind {
                     .map_err(wasm_bindgen::JsValue::from)
             }
 
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = setDeadlines)]
+            pub fn set_deadlines(
+                &mut self,
+                random_commit: Option<f64>,
+                random_reply: Option<f64>,
+                random_reveal: Option<f64>,
+                reveal: Option<f64>,
+            ) {
+                let millis = |millis: Option<f64>| millis.map(|millis| std::time::Duration::from_millis(millis as u64));
+
+                self.store.set_deadlines($crate::store::Deadlines {
+                    random_commit: millis(random_commit),
+                    random_reply: millis(random_reply),
+                    random_reveal: millis(random_reveal),
+                    reveal: millis(reveal),
+                });
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(getter)]
+            pub fn deadline(&self) -> Option<f64> {
+                self.store
+                    .deadline()
+                    .map(|deadline| deadline.as_millis() as f64)
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = pollDeadlines)]
+            pub fn poll_deadlines(&mut self) -> Result<bool, wasm_bindgen::JsValue> {
+                Ok(self
+                    .store
+                    .poll_deadlines($crate::store::Clock::now(&$crate::store::bindings::JsClock))?)
+            }
+
             #[wasm_bindgen::prelude::wasm_bindgen]
             pub fn apply(&mut self, diff: &[u8]) -> Result<(), wasm_bindgen::JsValue> {
-                Ok(self.store.apply(&$crate::Diff::deserialize(diff)?)?)
+                Ok(self.store.apply(&$crate::Diff::deserialize_with_limits(
+                    diff,
+                    self.store.state().id(),
+                    self.store.decode_limits(),
+                )?)?)
             }
             #[wasm_bindgen::prelude::wasm_bindgen]
             pub fn raw_apply(&mut self, diff: &[u8]) -> Result<(), wasm_bindgen::JsValue> {
-                Ok(self.store.raw_apply(&$crate::Diff::deserialize(diff)?)?)
+                Ok(self.store.raw_apply(&$crate::Diff::deserialize_with_limits(
+                    diff,
+                    self.store.state().id(),
+                    self.store.decode_limits(),
+                )?)?)
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen]
+            pub fn checkpoint(&mut self) -> Result<(), wasm_bindgen::JsValue> {
+                Ok(self.store.checkpoint()?)
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = setCheckpointThreshold)]
+            pub fn set_checkpoint_threshold(&mut self, threshold: usize) {
+                self.store.set_checkpoint_threshold(threshold);
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = setDecodeLimits)]
+            pub fn set_decode_limits(
+                &mut self,
+                limits: wasm_bindgen::JsValue,
+            ) -> Result<(), wasm_bindgen::JsValue> {
+                self.store
+                    .set_decode_limits($crate::utils::from_js(limits)?);
+
+                Ok(())
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = getPendingDiffs)]
+            pub fn pending_diffs(&self) -> Result<wasm_bindgen::JsValue, wasm_bindgen::JsValue> {
+                Ok($crate::utils::to_js(
+                    &self
+                        .store
+                        .pending()
+                        .map($crate::Diff::serialize)
+                        .collect::<Vec<_>>(),
+                )?)
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = expirePendingDiffs)]
+            pub fn expire_pending_diffs(&mut self, expired: js_sys::Function) {
+                self.store.expire_pending(|diff| {
+                    $crate::utils::to_js(&diff.serialize())
+                        .ok()
+                        .and_then(|diff| {
+                            expired
+                                .call1(&wasm_bindgen::JsValue::UNDEFINED, &diff)
+                                .ok()
+                        })
+                        .map_or(false, |expired| expired.is_truthy())
+                })
             }
 
             #[wasm_bindgen::prelude::wasm_bindgen(js_name = getApproval)]
@@ -465,6 +571,40 @@ macro_rules! bind {
                     ),
                 )
             }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = getChallengeMessage)]
+            pub fn challenge_message(
+                &self,
+                address: &str,
+                expiry: Option<Vec<u8>>,
+            ) -> Result<Vec<u8>, wasm_bindgen::JsValue> {
+                Ok(self.store.state().challenge(
+                    &std::convert::TryInto::<_>::try_into($crate::utils::unhex(address)?.as_slice())
+                        .map_err(|error| format!("{}", error))?,
+                    expiry
+                        .map(|expiry| {
+                            <<$type as $crate::store::State>::Nonce as $crate::Nonce>::deserialize(
+                                &mut expiry.as_slice(),
+                            )
+                        })
+                        .transpose()?
+                        .as_ref(),
+                ))
+            }
+
+            #[wasm_bindgen::prelude::wasm_bindgen(js_name = getApprovalMessage)]
+            pub fn approval_message(
+                &self,
+                player: &str,
+                subkey: &str,
+            ) -> Result<Vec<u8>, wasm_bindgen::JsValue> {
+                Ok(self.store.state().approval(
+                    &std::convert::TryInto::<_>::try_into($crate::utils::unhex(player)?.as_slice())
+                        .map_err(|error| format!("{}", error))?,
+                    &std::convert::TryInto::<_>::try_into($crate::utils::unhex(subkey)?.as_slice())
+                        .map_err(|error| format!("{}", error))?,
+                ))
+            }
         }
 
         #[wasm_bindgen::prelude::wasm_bindgen]
@@ -482,12 +622,14 @@ macro_rules! bind {
                 log: js_sys::Function,
                 random: js_sys::Function,
             ) -> Result<WasmState, wasm_bindgen::JsValue> {
-                let [secret1, secret2]: [Option<_>; 2] = $crate::utils::from_js(secrets)?;
+                let secrets: Vec<Option<(_, [u8; 16])>> = $crate::utils::from_js(secrets)?;
 
-                let secrets = [
-                    secret1.map(|(secret, seed)| (secret, rand::SeedableRng::from_seed(seed))),
-                    secret2.map(|(secret, seed)| (secret, rand::SeedableRng::from_seed(seed))),
-                ];
+                let secrets = secrets
+                    .into_iter()
+                    .map(|secret| {
+                        secret.map(|(secret, seed)| (secret, rand::SeedableRng::from_seed(seed)))
+                    })
+                    .collect();
 
                 Ok(Self {
                     state: $crate::store::StoreState::new(
@@ -574,7 +716,7 @@ macro_rules! bind {
                 Ok($crate::utils::to_js(&self.state.simulate(
                     player,
                     &$crate::utils::from_js(action)?,
-                    $crate::utils::from_js(using_secrets)?,
+                    &$crate::utils::from_js::<Vec<bool>>(using_secrets)?,
                 )?)?)
             }
 
@@ -619,6 +761,19 @@ macro_rules! bind {
             )
         }
 
+        #[wasm_bindgen::prelude::wasm_bindgen(js_name = getSupportedFormats)]
+        pub fn supported_formats() -> Vec<u8> {
+            $crate::Format::SUPPORTED
+                .iter()
+                .map(|format| u8::from(*format))
+                .collect()
+        }
+
+        #[wasm_bindgen::prelude::wasm_bindgen(js_name = getTypedData)]
+        pub fn typed_data(message: &[u8]) -> Result<String, wasm_bindgen::JsValue> {
+            Ok($crate::crypto::Eip712::typed_data(message)?)
+        }
+
         #[wasm_bindgen::prelude::wasm_bindgen(js_name = getRootProofVersion)]
         pub fn root_proof_version(root: &[u8]) -> Result<String, wasm_bindgen::JsValue> {
             Ok($crate::utils::hex(&$crate::RootProof::<
@@ -649,10 +804,10 @@ macro_rules! bind {
         ) -> Result<$crate::Player, wasm_bindgen::JsValue> {
             let player = $crate::utils::unhex(player)?;
 
-            if player.len() != std::mem::size_of::<$crate::crypto::Address>() {
-                return Err(
-                    "player.len() != std::mem::size_of::<$crate::crypto::Address>()".into(),
-                );
+            if player.len()
+                != <<$type as $crate::store::State>::Scheme as $crate::crypto::SignatureScheme>::ADDRESS_SIZE
+            {
+                return Err("player.len() != ADDRESS_SIZE".into());
             }
 
             let root = $crate::RootProof::<$crate::store::StoreState<$type>>::deserialize(
@@ -672,24 +827,104 @@ macro_rules! bind {
         #[wasm_bindgen::prelude::wasm_bindgen(js_name = getDiffProof)]
         pub fn diff_proof(diff: &[u8]) -> Result<String, wasm_bindgen::JsValue> {
             Ok($crate::utils::hex(
-                $crate::Diff::<$crate::store::StoreState<$type>>::deserialize(diff)?.proof(),
+                $crate::DiffRef::<$crate::store::StoreState<$type>>::new(diff)?.proof(),
             ))
         }
 
         #[wasm_bindgen::prelude::wasm_bindgen(js_name = getDiffDebugString)]
-        pub fn diff_debug_string(diff: &[u8]) -> Result<String, wasm_bindgen::JsValue> {
+        pub fn diff_debug_string(diff: &[u8], id: &[u8]) -> Result<String, wasm_bindgen::JsValue> {
+            let id = <<$type as $crate::store::State>::ID as $crate::ID>::deserialize(&mut &id[..])?;
+
             Ok(format!(
                 "{:?}",
-                $crate::Diff::<$crate::store::StoreState<$type>>::deserialize(diff)?
+                $crate::Diff::<$crate::store::StoreState<$type>>::deserialize(diff, &id)?
             ))
         }
     };
 }
 
+#[doc(hidden)]
+/// [super::StoreHooks] calling external JavaScript functions
+pub struct JsHooks {
+    pub ready: js_sys::Function,
+    pub sign: js_sys::Function,
+    pub send: js_sys::Function,
+    pub log: js_sys::Function,
+    pub random: JsRng,
+    pub format: std::rc::Rc<core::cell::Cell<crate::Format>>,
+}
+
+impl<S: super::State> super::StoreHooks<S> for JsHooks
+where
+    S: serde::Serialize,
+    S::Secret: serde::Serialize,
+    S::Event: serde::Serialize,
+{
+    fn on_ready(&mut self, state: &S, secrets: &[Option<&S::Secret>]) {
+        if let Ok(state) = crate::utils::to_js(state) {
+            let arguments = js_sys::Array::of1(&state);
+
+            for secret in secrets.iter().flatten() {
+                arguments.push(&crate::utils::to_js(secret).unwrap_or(wasm_bindgen::JsValue::NULL));
+            }
+
+            drop(
+                self.ready
+                    .apply(&wasm_bindgen::JsValue::UNDEFINED, &arguments),
+            );
+        }
+    }
+
+    fn sign(
+        &mut self,
+        message: &[u8],
+    ) -> Result<crate::crypto::StateSignature<super::StoreState<S>>, String> {
+        Ok(<S::Scheme as crate::crypto::SignatureScheme>::signature(
+            &crate::utils::from_js::<Vec<_>>(
+                self.sign
+                    .call1(
+                        &wasm_bindgen::JsValue::UNDEFINED,
+                        &crate::utils::to_js(message)?,
+                    )
+                    .map_err(|error| format!("{:?}", error))?,
+            )?,
+        )?)
+    }
+
+    fn send(&mut self, diff: &super::StoreDiff<S>) {
+        if let Ok(value) = &crate::utils::to_js(&diff.serialize_with_format(self.format.get())) {
+            drop(self.send.call1(&wasm_bindgen::JsValue::UNDEFINED, value));
+        }
+    }
+
+    fn on_event(&mut self, target: Option<crate::Player>, event: S::Event) {
+        if let (Ok(target), Ok(event)) = (crate::utils::to_js(&target), crate::utils::to_js(&event))
+        {
+            drop(
+                self.log
+                    .call2(&wasm_bindgen::JsValue::UNDEFINED, &target, &event),
+            );
+        }
+    }
+
+    fn random(&mut self) -> &mut dyn rand::RngCore {
+        &mut self.random
+    }
+}
+
 #[doc(hidden)]
 /// Random number generator using an external JavaScript function for entropy
 pub struct JsRng(pub js_sys::Function);
 
+#[doc(hidden)]
+pub struct JsClock;
+
+impl super::Clock for JsClock {
+    fn now(&self) -> core::time::Duration {
+        core::time::Duration::from_millis(js_sys::Date::now() as u64)
+    }
+}
+
 impl rand::RngCore for JsRng {
     fn next_u32(&mut self) -> u32 {
         rand_core::impls::next_u32_via_fill(self)
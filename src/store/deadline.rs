@@ -0,0 +1,60 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Pending phase deadlines
+
+use core::time::Duration;
+
+/// Source of wall-clock time for [Store](super::Store) deadlines
+///
+/// Any `Fn() -> Duration` is a clock.
+pub trait Clock {
+    /// Gets the current time, as the duration since an arbitrary fixed epoch.
+    fn now(&self) -> Duration;
+}
+
+impl<F: Fn() -> Duration> Clock for F {
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+/// Time allowed to the pending player in each phase before the owner dispatches a timeout
+///
+/// [None] means the phase has no deadline.
+///
+/// See [State::deadlines](super::State::deadlines) and [Store::poll_deadlines](super::Store::poll_deadlines).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Deadlines {
+    /// Time allowed to commit to a random seed
+    pub random_commit: Option<Duration>,
+    /// Time allowed to each player replying to a random seed commitment
+    pub random_reply: Option<Duration>,
+    /// Time allowed to reveal a committed random seed
+    pub random_reveal: Option<Duration>,
+    /// Time allowed to answer a [Context::reveal](super::Context::reveal) or
+    /// [Context::reveal_unique](super::Context::reveal_unique) request
+    pub reveal: Option<Duration>,
+}
+
+/// Deadline of the store's current pending phase
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) struct Deadline {
+    /// Action and reveal counts identifying the pending phase
+    pub(super) phase: (usize, usize),
+    /// Expiry of the phase, or [None] if it never expires or its timeout was already dispatched
+    pub(super) expiry: Option<Duration>,
+}
@@ -0,0 +1,119 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Equivocation detection
+
+use {
+    super::{State, StoreDiff},
+    crate::crypto::{Hash, SignatureScheme},
+    alloc::vec::Vec,
+    core::{convert::TryInto, mem::size_of},
+};
+
+/// Diff conflicting with one the store accepted on the same proof
+///
+/// The store only remembers the digest and author of each diff it accepts, so evidence also
+/// requires the accepted diff, e.g. from the transport that delivered it.
+///
+/// See [Store::set_equivocation_hook](super::Store::set_equivocation_hook).
+pub struct Equivocation<'a, S: State> {
+    pub(super) accepted: Hash,
+    pub(super) diff: &'a StoreDiff<S>,
+}
+
+impl<S: State> Equivocation<'_, S> {
+    /// Gets the address of the author of both diffs.
+    pub fn offender(&self) -> &<S::Scheme as SignatureScheme>::Address {
+        self.diff.author()
+    }
+
+    /// Gets the digest of the diff the store accepted.
+    ///
+    /// See [Diff::digest](crate::Diff::digest).
+    pub fn accepted(&self) -> &Hash {
+        &self.accepted
+    }
+
+    /// Gets the conflicting diff.
+    pub fn diff(&self) -> &StoreDiff<S> {
+        self.diff
+    }
+
+    /// Constructs evidence of the equivocation from the diff the store accepted.
+    ///
+    /// Returns [None] if `accepted` isn't the diff the store accepted.
+    pub fn evidence(&self, accepted: &StoreDiff<S>) -> Option<crate::EquivocationEvidence> {
+        if accepted.digest() != self.accepted {
+            return None;
+        }
+
+        crate::EquivocationEvidence::new(accepted, self.diff)
+    }
+}
+
+/// Digest and author of a diff accepted by the store
+pub(super) struct Accepted<S: State> {
+    pub(super) digest: Hash,
+    pub(super) author: <S::Scheme as SignatureScheme>::Address,
+}
+
+impl<S: State> Clone for Accepted<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S: State> Copy for Accepted<S> {}
+
+impl<S: State> Accepted<S> {
+    pub(super) fn new(diff: &StoreDiff<S>) -> Self {
+        Self {
+            digest: diff.digest(),
+            author: *diff.author(),
+        }
+    }
+
+    /// Checks if `diff` is by the same author as the accepted diff, but has different contents.
+    pub(super) fn conflicts(&self, diff: &StoreDiff<S>) -> bool {
+        *diff.author() == self.author && diff.digest() != self.digest
+    }
+
+    pub(super) const SIZE: usize = size_of::<Hash>() + size_of::<Hash>() + S::Scheme::ADDRESS_SIZE;
+
+    pub(super) fn write(data: &mut Vec<u8>, proof: &Hash, accepted: &Self) {
+        data.extend(proof);
+        data.extend(&accepted.digest);
+        data.extend(accepted.author.as_ref());
+    }
+
+    pub(super) fn read(data: &mut &[u8]) -> Result<(Hash, Self), crate::Error> {
+        crate::forbid!(data.len() < Self::SIZE);
+
+        let (proof, rest) = data.split_at(size_of::<Hash>());
+        let (digest, rest) = rest.split_at(size_of::<Hash>());
+        let (author, rest) = rest.split_at(S::Scheme::ADDRESS_SIZE);
+
+        *data = rest;
+
+        Ok((
+            proof.try_into().map_err(crate::Error::decode)?,
+            Self {
+                digest: digest.try_into().map_err(crate::Error::decode)?,
+                author: S::Scheme::parse_address(author)?,
+            },
+        ))
+    }
+}
@@ -0,0 +1,279 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Store hooks and builder
+
+use {
+    super::{
+        shared::{Lock, Shared},
+        MaybeSend, State, Store, StoreDiff, StoreState,
+    },
+    alloc::{boxed::Box, string::String, vec::Vec},
+    core::marker::PhantomData,
+};
+
+/// Callbacks through which a [Store] talks to its client
+///
+/// See [StoreBuilder].
+pub trait StoreHooks<S: State>: MaybeSend {
+    /// Called when the state is ready for the next action, with the secrets known to the store.
+    fn on_ready(&mut self, _state: &S, _secrets: &[Option<&S::Secret>]) {}
+
+    /// Signs a message as the store's player, or as the owner for the owner's store.
+    fn sign(
+        &mut self,
+        message: &[u8],
+    ) -> Result<crate::crypto::StateSignature<StoreState<S>>, String>;
+
+    /// Sends a diff to the other participants.
+    fn send(&mut self, diff: &StoreDiff<S>);
+
+    /// Called when the state logs an event.
+    fn on_event(&mut self, _player: Option<crate::Player>, _event: S::Event) {}
+
+    /// Gets the source of randomness for seeds the store commits to.
+    fn random(&mut self) -> &mut dyn rand::RngCore;
+}
+
+/// [Store] constructor with named options
+///
+/// ```no_run
+/// use arcadeum::{
+///     store::{State, Store, StoreBuilder, StoreHooks},
+///     DecodeLimits, Error,
+/// };
+///
+/// fn open<S: State + 'static>(
+///     hooks: impl StoreHooks<S> + 'static,
+///     root: &[u8],
+///     secrets: Vec<Option<(S::Secret, [u8; 16])>>,
+///     limits: DecodeLimits,
+/// ) -> Result<Store<S>, Error> {
+///     let mut store = StoreBuilder::new(hooks)
+///         .player(Some(0))
+///         .limits(limits)
+///         .build(root, secrets)?;
+///
+///     store.flush()?;
+///
+///     Ok(store)
+/// }
+/// ```
+pub struct StoreBuilder<S: State, H: StoreHooks<S>> {
+    hooks: H,
+    player: Option<crate::Player>,
+    p2p: bool,
+    no_version_check: bool,
+    limits: crate::DecodeLimits,
+    state: PhantomData<fn() -> S>,
+}
+
+impl<S: State + 'static, H: StoreHooks<S> + 'static> StoreBuilder<S, H> {
+    /// Starts building an owner store with the given hooks.
+    pub fn new(hooks: H) -> Self {
+        Self {
+            hooks,
+            player: None,
+            p2p: false,
+            no_version_check: false,
+            limits: Default::default(),
+            state: PhantomData,
+        }
+    }
+
+    /// Sets the player the store is for, or [None] for the owner.
+    ///
+    /// Ignored by [StoreBuilder::deserialize], which reads the player from the store.
+    pub fn player(mut self, player: Option<crate::Player>) -> Self {
+        self.player = player;
+        self
+    }
+
+    /// Sets whether players reveal their own secrets instead of the owner revealing them.
+    pub fn p2p(mut self, p2p: bool) -> Self {
+        self.p2p = p2p;
+        self
+    }
+
+    /// Sets whether to skip checking the state version.
+    pub fn no_version_check(mut self, no_version_check: bool) -> Self {
+        self.no_version_check = no_version_check;
+        self
+    }
+
+    /// Sets the limits bounding the decoded root or store, and any diffs the store decodes later.
+    pub fn limits(mut self, limits: crate::DecodeLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub(super) fn hooks(&self) -> &H {
+        &self.hooks
+    }
+
+    /// Constructs a new store.
+    ///
+    /// You should call [Store::flush] on the new store.
+    ///
+    /// `root` must have been constructed using [RootProof::serialize](crate::RootProof::serialize).
+    /// `secrets` must have one entry per player.
+    pub fn build(
+        self,
+        root: &[u8],
+        secrets: Vec<Option<(S::Secret, [u8; 16])>>,
+    ) -> Result<Store<S>, crate::Error> {
+        Store::with_callbacks(
+            self.player,
+            root,
+            secrets,
+            self.p2p,
+            Callbacks::new(self.hooks),
+            self.no_version_check,
+            self.limits,
+        )
+    }
+
+    /// Constructs a store from its binary representation.
+    ///
+    /// You should call [Store::flush] on the new store.
+    ///
+    /// `data` must have been constructed using [Store::serialize] or
+    /// [Store::serialize_with_format].
+    pub fn deserialize(self, data: &[u8]) -> Result<Store<S>, crate::Error> {
+        Store::deserialize_with_callbacks(
+            data,
+            self.p2p,
+            Callbacks::new(self.hooks),
+            self.no_version_check,
+            self.limits,
+        )
+    }
+}
+
+/// Hooks split into the callbacks a [Store] holds
+#[allow(clippy::type_complexity)]
+pub(super) struct Callbacks<S: State> {
+    pub(super) ready: Box<dyn_send!(FnMut(&S, &[Option<&S::Secret>]))>,
+    pub(super) sign: Box<
+        dyn_send!(FnMut(&[u8]) -> Result<crate::crypto::StateSignature<StoreState<S>>, String>),
+    >,
+    pub(super) send: Box<dyn_send!(FnMut(&StoreDiff<S>))>,
+    pub(super) log: Box<dyn_send!(FnMut(Option<crate::Player>, S::Event))>,
+    pub(super) random: Box<dyn_send!(rand::RngCore)>,
+}
+
+impl<S: State + 'static> Callbacks<S> {
+    pub(super) fn new<H: StoreHooks<S> + 'static>(hooks: H) -> Self {
+        let hooks = Shared::new(Lock::new(hooks));
+
+        Self {
+            ready: Box::new(ready(&hooks)),
+            sign: Box::new(sign(&hooks)),
+            send: Box::new(send(&hooks)),
+            log: Box::new(log(&hooks)),
+            random: Box::new(Random::new(&hooks)),
+        }
+    }
+}
+
+impl<S: State> StoreHooks<S> for Callbacks<S> {
+    fn on_ready(&mut self, state: &S, secrets: &[Option<&S::Secret>]) {
+        (self.ready)(state, secrets)
+    }
+
+    fn sign(
+        &mut self,
+        message: &[u8],
+    ) -> Result<crate::crypto::StateSignature<StoreState<S>>, String> {
+        (self.sign)(message)
+    }
+
+    fn send(&mut self, diff: &StoreDiff<S>) {
+        (self.send)(diff)
+    }
+
+    fn on_event(&mut self, player: Option<crate::Player>, event: S::Event) {
+        (self.log)(player, event)
+    }
+
+    fn random(&mut self) -> &mut dyn rand::RngCore {
+        &mut *self.random
+    }
+}
+
+struct Random<S: State, H: StoreHooks<S>> {
+    hooks: Shared<Lock<H>>,
+    state: PhantomData<fn() -> S>,
+}
+
+impl<S: State, H: StoreHooks<S>> Random<S, H> {
+    fn new(hooks: &Shared<Lock<H>>) -> Self {
+        Self {
+            hooks: hooks.clone(),
+            state: PhantomData,
+        }
+    }
+}
+
+impl<S: State, H: StoreHooks<S>> rand::RngCore for Random<S, H> {
+    fn next_u32(&mut self) -> u32 {
+        self.hooks.borrow_mut().random().next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.hooks.borrow_mut().random().next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.hooks.borrow_mut().random().fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.hooks.borrow_mut().random().try_fill_bytes(dest)
+    }
+}
+
+fn ready<S: State, H: StoreHooks<S> + 'static>(
+    hooks: &Shared<Lock<H>>,
+) -> impl FnMut(&S, &[Option<&S::Secret>]) + 'static {
+    let hooks = hooks.clone();
+
+    move |state, secrets| hooks.borrow_mut().on_ready(state, secrets)
+}
+
+fn sign<S: State, H: StoreHooks<S> + 'static>(
+    hooks: &Shared<Lock<H>>,
+) -> impl FnMut(&[u8]) -> Result<crate::crypto::StateSignature<StoreState<S>>, String> + 'static {
+    let hooks = hooks.clone();
+
+    move |message| hooks.borrow_mut().sign(message)
+}
+
+fn send<S: State, H: StoreHooks<S> + 'static>(
+    hooks: &Shared<Lock<H>>,
+) -> impl FnMut(&StoreDiff<S>) + 'static {
+    let hooks = hooks.clone();
+
+    move |diff| hooks.borrow_mut().send(diff)
+}
+
+fn log<S: State, H: StoreHooks<S> + 'static>(
+    hooks: &Shared<Lock<H>>,
+) -> impl FnMut(Option<crate::Player>, S::Event) + 'static {
+    let hooks = hooks.clone();
+
+    move |player, event| hooks.borrow_mut().on_event(player, event)
+}
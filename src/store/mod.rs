@@ -19,15 +19,14 @@
 use {
     alloc::{
         boxed::Box,
+        collections::BTreeMap,
         fmt::Debug,
         format,
-        rc::Rc,
         string::{String, ToString},
         vec,
         vec::Vec,
     },
     core::{
-        cell::{Ref, RefCell},
         column,
         convert::TryInto,
         file,
@@ -38,148 +37,291 @@ use {
         pin::Pin,
         ptr, task,
         task::{Poll, RawWaker, RawWakerVTable, Waker},
+        time::Duration,
     },
 };
 
+#[macro_use]
+mod shared;
+pub use shared::MaybeSend;
+use shared::{Guard, Lock, Shared};
+
+mod asynchronous;
+pub use asynchronous::{AsyncHooks, AsyncStore, StoreEvent, StoreEvents};
+
+mod deadline;
+pub use deadline::{Clock, Deadlines};
+
+mod equivocation;
+pub use equivocation::Equivocation;
+
+mod hooks;
+use hooks::Callbacks;
+pub use hooks::{StoreBuilder, StoreHooks};
+
 mod tester;
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "sync")))]
 pub mod bindings;
 
 #[derive(PartialEq)]
 pub enum SecretKnowledge {
-    Both,
+    All,
     None,
     Some(crate::Player),
 }
 
+impl From<u8> for SecretKnowledge {
+    /// Decodes secret knowledge as in the first byte of a serialized [Store].
+    ///
+    /// 0 is none and 3 is all, as in the two-player layout.
+    /// 1 and 2 are players 0 and 1, and any larger byte is 2 more than its player.
+    fn from(byte: u8) -> Self {
+        match byte {
+            0 => Self::None,
+            3 => Self::All,
+            byte @ 1..=2 => Self::Some(byte - 1),
+            byte => Self::Some(byte - 2),
+        }
+    }
+}
+
 /// Client [State] store
+#[allow(clippy::type_complexity)]
 pub struct Store<S: State> {
     player: Option<crate::Player>,
     proof: crate::Proof<StoreState<S>>,
     p2p: bool,
-    ready: Box<dyn FnMut(&S, [Option<&S::Secret>; 2])>,
-    sign: Box<dyn FnMut(&[u8]) -> Result<crate::crypto::Signature, String>>,
-    send: Box<dyn FnMut(&StoreDiff<S>)>,
-    random: Box<dyn rand::RngCore>,
+    ready: Box<dyn_send!(FnMut(&S, &[Option<&S::Secret>]))>,
+    sign: Box<
+        dyn_send!(FnMut(&[u8]) -> Result<crate::crypto::StateSignature<StoreState<S>>, String>),
+    >,
+    send: Box<dyn_send!(FnMut(&StoreDiff<S>))>,
+    random: Box<dyn_send!(rand::RngCore)>,
     seed: Option<Vec<u8>>,
+    applied: BTreeMap<crate::crypto::Hash, equivocation::Accepted<S>>,
+    equivocation: Option<Box<dyn_send!(FnMut(&Equivocation<'_, S>))>>,
+    discard: Option<Box<dyn_send!(FnMut(&StoreDiff<S>, &crate::Error))>>,
+    reveal_default: Option<Box<dyn_send!(FnMut(crate::Player) -> S::Secret)>>,
+    pending: BTreeMap<crate::crypto::Hash, StoreDiff<S>>,
+    pending_capacity: usize,
+    checkpoint_threshold: usize,
+    decode_limits: crate::DecodeLimits,
+    clock: Option<Box<dyn_send!(Clock)>>,
+    deadlines: Deadlines,
+    deadline: Option<deadline::Deadline>,
 }
 
 impl<S: State> Store<S> {
-    /// Constructs a new store for a given player.
+    /// Default maximum number of diffs buffered while waiting for their predecessors
     ///
-    /// You should call [Store::flush] on the new store.
+    /// See [Store::set_pending_capacity].
+    pub const DEFAULT_PENDING_CAPACITY: usize = 64;
+
+    /// Default size in bytes of a serialized proof past which the owner checkpoints it
     ///
-    /// `root` must have been constructed using [RootProof::serialize](crate::RootProof::serialize).
+    /// See [Store::set_checkpoint_threshold].
+    pub const DEFAULT_CHECKPOINT_THRESHOLD: usize = 16 * 1024;
+
+    /// Constructs a new store for a given player.
+    ///
+    /// See [StoreBuilder::build].
+    #[deprecated(note = "use StoreBuilder::build")]
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         player: Option<crate::Player>,
         root: &[u8],
-        [secret1, secret2]: [Option<(S::Secret, [u8; 16])>; 2],
+        secrets: Vec<Option<(S::Secret, [u8; 16])>>,
         p2p: bool,
-        ready: impl FnMut(&S, [Option<&S::Secret>; 2]) + 'static,
-        sign: impl FnMut(&[u8]) -> Result<crate::crypto::Signature, String> + 'static,
-        send: impl FnMut(&StoreDiff<S>) + 'static,
-        log: impl FnMut(Option<crate::Player>, S::Event) + 'static,
-        random: impl rand::RngCore + 'static,
+        ready: impl FnMut(&S, &[Option<&S::Secret>]) + MaybeSend + 'static,
+        sign: impl FnMut(&[u8]) -> Result<crate::crypto::StateSignature<StoreState<S>>, String>
+            + MaybeSend
+            + 'static,
+        send: impl FnMut(&StoreDiff<S>) + MaybeSend + 'static,
+        log: impl FnMut(Option<crate::Player>, S::Event) + MaybeSend + 'static,
+        random: impl rand::RngCore + MaybeSend + 'static,
         no_version_check: bool,
-    ) -> Result<Self, String> {
-        Ok(Self {
+        limits: crate::DecodeLimits,
+    ) -> Result<Self, crate::Error> {
+        Self::with_callbacks(
             player,
-            proof: crate::Proof::new(crate::RootProof::<StoreState<S>>::deserialize_and_init(
-                root,
-                |state| {
-                    if let Some(_StoreState::Ready { secrets, .. }) = &mut state.0 {
-                        *secrets = [
-                            secret1
-                                .map(|(secret, seed)| (secret, rand::SeedableRng::from_seed(seed))),
-                            secret2
-                                .map(|(secret, seed)| (secret, rand::SeedableRng::from_seed(seed))),
-                        ];
-                    } else {
-                        unreachable!("{}:{}:{}", file!(), line!(), column!());
-                    }
+            root,
+            secrets,
+            p2p,
+            Callbacks {
+                ready: Box::new(ready),
+                sign: Box::new(sign),
+                send: Box::new(send),
+                log: Box::new(log),
+                random: Box::new(random),
+            },
+            no_version_check,
+            limits,
+        )
+    }
 
-                    state.set_logger(Rc::new(RefCell::new(Logger::new(log))));
-                },
-                no_version_check,
-            )?),
+    fn with_callbacks(
+        player: Option<crate::Player>,
+        root: &[u8],
+        secrets: Vec<Option<(S::Secret, [u8; 16])>>,
+        p2p: bool,
+        callbacks: Callbacks<S>,
+        no_version_check: bool,
+        limits: crate::DecodeLimits,
+    ) -> Result<Self, crate::Error> {
+        let Callbacks {
+            ready,
+            sign,
+            send,
+            log,
+            random,
+        } = callbacks;
+
+        let length = secrets.len();
+
+        let root = crate::RootProof::<StoreState<S>>::deserialize_and_init(
+            root,
+            |state| {
+                if let Some(_StoreState::Ready {
+                    secrets: state_secrets,
+                    ..
+                }) = &mut state.0
+                {
+                    *state_secrets = secrets
+                        .into_iter()
+                        .map(|secret| {
+                            secret
+                                .map(|(secret, seed)| (secret, rand::SeedableRng::from_seed(seed)))
+                        })
+                        .collect();
+                } else {
+                    unreachable!("{}:{}:{}", file!(), line!(), column!());
+                }
+
+                state.set_logger(Shared::new(Lock::new(Logger::boxed(log))));
+            },
+            no_version_check,
+            &limits,
+        )
+        .map_err(crate::Error::decoding)?;
+
+        crate::forbid!(length != root.state.players.len());
+
+        if let Some(player) = player {
+            crate::forbid!(usize::from(player) >= root.state.players.len());
+        }
+
+        Ok(Self {
+            player,
+            proof: crate::Proof::new(root),
             p2p,
-            ready: Box::new(ready),
-            sign: Box::new(sign),
-            send: Box::new(send),
-            random: Box::new(random),
+            ready,
+            sign,
+            send,
+            random,
             seed: None,
+            applied: BTreeMap::new(),
+            equivocation: None,
+            discard: None,
+            reveal_default: None,
+            pending: BTreeMap::new(),
+            pending_capacity: Self::DEFAULT_PENDING_CAPACITY,
+            checkpoint_threshold: Self::DEFAULT_CHECKPOINT_THRESHOLD,
+            decode_limits: limits,
+            clock: None,
+            deadlines: S::deadlines(),
+            deadline: None,
         })
     }
 
     /// Constructs a store from its binary representation.
     ///
-    /// You should call [Store::flush] on the new store.
-    ///
-    /// `data` must have been constructed using [Store::serialize].
+    /// See [StoreBuilder::deserialize].
+    #[deprecated(note = "use StoreBuilder::deserialize")]
     #[allow(clippy::too_many_arguments)]
     pub fn deserialize(
-        mut data: &[u8],
+        data: &[u8],
         p2p: bool,
-        ready: impl FnMut(&S, [Option<&S::Secret>; 2]) + 'static,
-        sign: impl FnMut(&[u8]) -> Result<crate::crypto::Signature, String> + 'static,
-        send: impl FnMut(&StoreDiff<S>) + 'static,
-        log: impl FnMut(Option<crate::Player>, S::Event) + 'static,
-        random: impl rand::RngCore + 'static,
+        ready: impl FnMut(&S, &[Option<&S::Secret>]) + MaybeSend + 'static,
+        sign: impl FnMut(&[u8]) -> Result<crate::crypto::StateSignature<StoreState<S>>, String>
+            + MaybeSend
+            + 'static,
+        send: impl FnMut(&StoreDiff<S>) + MaybeSend + 'static,
+        log: impl FnMut(Option<crate::Player>, S::Event) + MaybeSend + 'static,
+        random: impl rand::RngCore + MaybeSend + 'static,
         no_version_check: bool,
-    ) -> Result<Self, String> {
-        crate::forbid!(data.len() < 1 + size_of::<u32>() + size_of::<u32>() + 1);
-
-        let player = match crate::utils::read_u8(&mut data)? {
-            0 => None,
-            3 => None,
-            byte => Some(byte - 1),
-        };
-
-        let mut log = Logger::new(log);
-        log.enabled = false;
-        let log = Rc::new(RefCell::new(log));
+        limits: crate::DecodeLimits,
+    ) -> Result<Self, crate::Error> {
+        Self::deserialize_with_callbacks(
+            data,
+            p2p,
+            Callbacks {
+                ready: Box::new(ready),
+                sign: Box::new(sign),
+                send: Box::new(send),
+                log: Box::new(log),
+                random: Box::new(random),
+            },
+            no_version_check,
+            limits,
+        )
+    }
 
-        let secrets = {
-            let secret1 = if crate::utils::read_u8_bool(&mut data)? {
-                let size = crate::utils::read_u32_usize(&mut data)?;
+    fn deserialize_with_callbacks(
+        data: &[u8],
+        p2p: bool,
+        callbacks: Callbacks<S>,
+        no_version_check: bool,
+        limits: crate::DecodeLimits,
+    ) -> Result<Self, crate::Error> {
+        let data = crate::format::canonical(
+            data,
+            crate::format::Blob::Store,
+            <S::Scheme as crate::crypto::SignatureScheme>::SIGNATURE_SIZE,
+        );
 
-                crate::forbid!(data.len() < size);
-                let secret = S::Secret::deserialize(&data[..size])?;
-                data = &data[size..];
+        Self::deserialize_unchecked(&data, p2p, callbacks, no_version_check, limits)
+            .map_err(crate::Error::decoding)
+    }
 
-                let size = crate::utils::read_u32_usize(&mut data)?;
+    fn deserialize_unchecked(
+        mut data: &[u8],
+        p2p: bool,
+        callbacks: Callbacks<S>,
+        no_version_check: bool,
+        limits: crate::DecodeLimits,
+    ) -> Result<Self, crate::Error> {
+        let Callbacks {
+            ready,
+            sign,
+            send,
+            log,
+            random,
+        } = callbacks;
 
-                crate::forbid!(data.len() < size);
-                let random = rand_xorshift::XorShiftRng::deserialize(&data[..size])?;
-                data = &data[size..];
+        crate::forbid!(data.len() < 1 + size_of::<u32>() + size_of::<u32>() + 1);
 
-                Some((secret, random))
-            } else {
-                None
-            };
+        let tagged = data.first() == Some(&STORE_TAG);
 
-            let secret2 = if crate::utils::read_u8_bool(&mut data)? {
-                let size = crate::utils::read_u32_usize(&mut data)?;
+        if tagged {
+            data = &data[1..];
+        }
 
-                crate::forbid!(data.len() < size);
-                let secret = S::Secret::deserialize(&data[..size])?;
-                data = &data[size..];
+        let player = read_knowledge(&mut data)?;
 
-                let size = crate::utils::read_u32_usize(&mut data)?;
+        let length = if tagged {
+            crate::utils::read_u32_usize(&mut data)?
+        } else {
+            2
+        };
 
-                crate::forbid!(data.len() < size);
-                let random = rand_xorshift::XorShiftRng::deserialize(&data[..size])?;
-                data = &data[size..];
+        crate::forbid!(length >= usize::from(crate::Player::MAX));
 
-                Some((secret, random))
-            } else {
-                None
-            };
+        let mut log = Logger::boxed(log);
+        log.enabled = false;
+        let log = Shared::new(Lock::new(log));
 
-            [secret1, secret2]
-        };
+        let secrets = read_secrets::<S>(&mut data, length, &limits)?;
 
         let size = crate::utils::read_u32_usize(&mut data)?;
 
@@ -201,6 +343,7 @@ impl<S: State> Store<S> {
                 state.set_logger(log.clone());
             },
             no_version_check,
+            &limits,
         )?;
 
         data = &data[size..];
@@ -209,45 +352,9 @@ impl<S: State> Store<S> {
             crate::forbid!(usize::from(player) >= root.state.players.len());
         }
 
-        let secrets = {
-            let secret1 = if crate::utils::read_u8_bool(&mut data)? {
-                let size = crate::utils::read_u32_usize(&mut data)?;
-
-                crate::forbid!(data.len() < size);
-                let secret = S::Secret::deserialize(&data[..size])?;
-                data = &data[size..];
+        crate::forbid!(root.state.players.len() != length);
 
-                let size = crate::utils::read_u32_usize(&mut data)?;
-
-                crate::forbid!(data.len() < size);
-                let random = rand_xorshift::XorShiftRng::deserialize(&data[..size])?;
-                data = &data[size..];
-
-                Some((secret, random))
-            } else {
-                None
-            };
-
-            let secret2 = if crate::utils::read_u8_bool(&mut data)? {
-                let size = crate::utils::read_u32_usize(&mut data)?;
-
-                crate::forbid!(data.len() < size);
-                let secret = S::Secret::deserialize(&data[..size])?;
-                data = &data[size..];
-
-                let size = crate::utils::read_u32_usize(&mut data)?;
-
-                crate::forbid!(data.len() < size);
-                let random = rand_xorshift::XorShiftRng::deserialize(&data[..size])?;
-                data = &data[size..];
-
-                Some((secret, random))
-            } else {
-                None
-            };
-
-            [secret1, secret2]
-        };
+        let secrets = read_secrets::<S>(&mut data, length, &limits)?;
 
         let size = crate::utils::read_u32_usize(&mut data)?;
 
@@ -270,10 +377,23 @@ impl<S: State> Store<S> {
                 state.set_logger(log);
             },
             no_version_check,
+            &limits,
         )?;
 
         data = &data[size..];
 
+        let mut applied = BTreeMap::new();
+
+        if data.first() == Some(&APPLIED_TAG) {
+            data = &data[1..];
+
+            for _ in 0..crate::utils::read_u32_usize(&mut data)? {
+                let (proof, accepted) = equivocation::Accepted::read(&mut data)?;
+
+                applied.insert(proof, accepted);
+            }
+        }
+
         let seed = if crate::utils::read_u8_bool(&mut data)? {
             Some(data.to_vec())
         } else {
@@ -286,121 +406,116 @@ impl<S: State> Store<S> {
             player,
             proof,
             p2p,
-            ready: Box::new(ready),
-            sign: Box::new(sign),
-            send: Box::new(send),
-            random: Box::new(random),
+            ready,
+            sign,
+            send,
+            random,
             seed,
+            applied,
+            equivocation: None,
+            discard: None,
+            reveal_default: None,
+            pending: BTreeMap::new(),
+            pending_capacity: Self::DEFAULT_PENDING_CAPACITY,
+            checkpoint_threshold: Self::DEFAULT_CHECKPOINT_THRESHOLD,
+            decode_limits: limits,
+            clock: None,
+            deadlines: S::deadlines(),
+            deadline: None,
         })
     }
 
+    /// Generates a binary representation in the given format.
+    ///
+    /// See [Store::serialize].
+    pub fn serialize_with_format(
+        &self,
+        with_knowledge: SecretKnowledge,
+        format: crate::Format,
+    ) -> Vec<u8> {
+        crate::format::encode(
+            self.serialize(with_knowledge),
+            format,
+            crate::format::Blob::Store,
+            <S::Scheme as crate::crypto::SignatureScheme>::SIGNATURE_SIZE,
+        )
+    }
+
     /// Generates a binary representation that can be used to reconstruct the store for a given
     /// player.
     ///
-    /// See [Store::deserialize].
+    /// See [StoreBuilder::deserialize].
     pub fn serialize(&self, with_knowledge: SecretKnowledge) -> Vec<u8> {
         let root = self.proof.root.serialize();
         let proof = self.proof.serialize();
 
+        let length = self.proof.root.state.players.len();
+
         let mut data = Vec::with_capacity(
             1 + 1
+                + size_of::<u32>()
                 + size_of::<u32>()
                 + root.len()
-                + 1
+                + size_of::<u32>()
                 + size_of::<u32>()
                 + proof.len()
                 + 1
+                + size_of::<u32>()
+                + self.applied.len() * equivocation::Accepted::<S>::SIZE
+                + 1
                 + self.seed.as_ref().map_or(0, Vec::len),
         );
 
-        crate::utils::write_u8(
-            &mut data,
-            match self.player {
-                None => match with_knowledge {
-                    SecretKnowledge::None => 0,
-                    SecretKnowledge::Both => 3,
-                    SecretKnowledge::Some(player) => 1 + player,
-                },
-                Some(player) => 1 + player,
-            },
-        );
+        // two-player stores keep the untagged layout that predates other player counts
 
-        if let Some(_StoreState::Ready { secrets, .. }) = &self.proof.root.state.state.0 {
-            for (i, secret) in secrets.iter().enumerate() {
-                if with_knowledge == SecretKnowledge::Both
-                    || with_knowledge == SecretKnowledge::Some(i.try_into().unwrap())
-                {
-                    match secret {
-                        Some((secret, random)) => {
-                            crate::utils::write_u8_bool(&mut data, true);
+        if length != 2 {
+            crate::utils::write_u8(&mut data, STORE_TAG);
+        }
 
-                            let secret = secret.serialize();
-                            crate::utils::write_u32_usize(&mut data, secret.len()).unwrap();
-                            data.extend(secret);
+        write_knowledge(&mut data, self.player, &with_knowledge);
 
-                            let random = random.serialize();
-                            crate::utils::write_u32_usize(&mut data, random.len()).unwrap();
-                            data.extend(random);
-                        }
-                        None => crate::utils::write_u8_bool(&mut data, false),
-                    }
-                } else {
-                    crate::utils::write_u8_bool(&mut data, false);
-                }
-            }
-        } else {
-            unreachable!("{}:{}:{}", file!(), line!(), column!());
+        if length != 2 {
+            crate::utils::write_u32_usize(&mut data, length).unwrap();
         }
 
+        write_secrets::<S>(
+            &mut data,
+            self.proof.root.state.state.secrets(),
+            &with_knowledge,
+        );
+
         crate::utils::write_u32_usize(&mut data, root.len()).unwrap();
         data.extend(root);
 
-        if let Some(_StoreState::Ready { secrets, .. }) = &self
-            .proof
-            .proofs
-            .iter()
-            .find(|proof| match proof {
-                Some(proof) => proof.range.start == 0,
-                None => false,
-            })
-            .as_ref()
-            .unwrap()
-            .as_ref()
-            .unwrap()
-            .state
-            .state
-            .0
-        {
-            for (i, secret) in secrets.iter().enumerate() {
-                if with_knowledge == SecretKnowledge::Both
-                    || with_knowledge == SecretKnowledge::Some(i.try_into().unwrap())
-                {
-                    match secret {
-                        Some((secret, random)) => {
-                            crate::utils::write_u8_bool(&mut data, true);
+        write_secrets::<S>(
+            &mut data,
+            self.proof
+                .proofs
+                .iter()
+                .flatten()
+                .find(|proof| proof.range.start == 0)
+                .unwrap()
+                .state
+                .state
+                .secrets(),
+            &with_knowledge,
+        );
+
+        crate::utils::write_u32_usize(&mut data, proof.len()).unwrap();
+        data.extend(proof);
 
-                            let secret = secret.serialize();
-                            crate::utils::write_u32_usize(&mut data, secret.len()).unwrap();
-                            data.extend(secret);
+        // stores without accepted diffs keep the layout that predates equivocation detection
 
-                            let random = random.serialize();
-                            crate::utils::write_u32_usize(&mut data, random.len()).unwrap();
-                            data.extend(random);
-                        }
-                        None => crate::utils::write_u8_bool(&mut data, false),
-                    }
-                } else {
-                    crate::utils::write_u8_bool(&mut data, false);
-                }
+        if !self.applied.is_empty() {
+            crate::utils::write_u8(&mut data, APPLIED_TAG);
+            crate::utils::write_u32_usize(&mut data, self.applied.len()).unwrap();
+
+            for (proof, accepted) in &self.applied {
+                equivocation::Accepted::write(&mut data, proof, accepted);
             }
-        } else {
-            unreachable!("{}:{}:{}", file!(), line!(), column!());
         }
 
-        crate::utils::write_u32_usize(&mut data, proof.len()).unwrap();
-        data.extend(proof);
-
-        if with_knowledge == SecretKnowledge::Both || with_knowledge == SecretKnowledge::Some(0) {
+        if with_knowledge == SecretKnowledge::All || with_knowledge == SecretKnowledge::Some(0) {
             if let Some(seed) = &self.seed {
                 crate::utils::write_u8_bool(&mut data, true);
                 data.extend(seed);
@@ -420,7 +535,7 @@ impl<S: State> Store<S> {
     }
 
     /// Gets the author of the store's root proof.
-    pub fn owner(&self) -> &crate::crypto::Address {
+    pub fn owner(&self) -> &<S::Scheme as crate::crypto::SignatureScheme>::Address {
         self.proof.root.author()
     }
 
@@ -434,19 +549,78 @@ impl<S: State> Store<S> {
         &self.proof.state
     }
 
+    /// Gets the status of the match.
+    pub fn status(&self) -> crate::MatchStatus {
+        self.proof.state.status()
+    }
+
+    /// Gets the outcome of the match.
+    pub fn outcome(&self) -> crate::Outcome {
+        self.proof.outcome()
+    }
+
+    /// Checks if the match has a final outcome.
+    pub fn is_final(&self) -> bool {
+        self.proof.is_final()
+    }
+
+    /// Generates a compact signed result for a final match.
+    ///
+    /// See [crate::MatchResult::deserialize].
+    pub fn result(&mut self) -> Result<crate::MatchResult<StoreState<S>>, crate::Error> {
+        self.proof.result(&mut self.sign)
+    }
+
+    /// Resigns the match on behalf of the store's player.
+    pub fn resign(&mut self) -> Result<(), crate::Error> {
+        self.dispatch_action(crate::PlayerAction::Resign)
+    }
+
+    /// Offers a draw on behalf of the store's player.
+    pub fn offer_draw(&mut self) -> Result<(), crate::Error> {
+        self.dispatch_action(crate::PlayerAction::OfferDraw)
+    }
+
+    /// Accepts another player's draw offer on behalf of the store's player.
+    pub fn accept_draw(&mut self) -> Result<(), crate::Error> {
+        self.dispatch_action(crate::PlayerAction::AcceptDraw)
+    }
+
+    /// Requests to abort the match on behalf of the store's player.
+    pub fn abort(&mut self) -> Result<(), crate::Error> {
+        self.dispatch_action(crate::PlayerAction::Abort)
+    }
+
+    fn dispatch_action(
+        &mut self,
+        action: crate::PlayerAction<StoreState<S>>,
+    ) -> Result<(), crate::Error> {
+        let diff = self.diff(vec![crate::ProofAction {
+            player: self.player,
+            action,
+        }])?;
+
+        (self.send)(&diff);
+
+        self.apply(&diff)
+    }
+
     /// Gets the player who must act if in a pending state.
-    pub fn pending_player(&self) -> Result<Option<crate::Player>, String> {
+    pub fn pending_player(&self) -> Result<Option<crate::Player>, crate::Error> {
         if let _StoreState::Pending { phase, .. } = self
             .proof
             .state
             .state
             .0
             .as_ref()
-            .ok_or("self.proof.state.state.0.is_none()")?
+            .ok_or_else(|| crate::forbidden!("self.proof.state.state.0.is_none()"))?
         {
-            match *phase.try_borrow().map_err(|error| error.to_string())? {
+            match *phase
+                .try_borrow()
+                .map_err(|error| crate::forbidden!(error.to_string()))?
+            {
                 Phase::RandomCommit => Ok(Some(0)),
-                Phase::RandomReply { .. } => Ok(Some(1)),
+                Phase::RandomReply { player, .. } => Ok(Some(player)),
                 Phase::RandomReveal {
                     owner_hash: false, ..
                 } => Ok(Some(0)),
@@ -457,18 +631,23 @@ impl<S: State> Store<S> {
                     request: RevealRequest { player, .. },
                     ..
                 } => Ok(Some(player)),
+                Phase::Forfeit { .. } => Err(crate::forbidden!("phase == Phase::Forfeit { .. }")),
                 _ => unreachable!("{}:{}:{}", file!(), line!(), column!()),
             }
         } else {
-            Err("self.proof.state.state.0 != _StoreState::Pending { .. }".to_string())
+            Err(crate::forbidden!(
+                "self.proof.state.state.0 != _StoreState::Pending { .. }"
+            ))
         }
     }
 
-    /// Dispatches an action that will continue a stalled commit-reveal sequence.
+    /// Dispatches an action that will continue a stalled commit-reveal sequence, or enforce the
+    /// [State::reveal_timeout] policy of a stalled reveal.
     ///
     /// Only call this if the pending player isn't live.
-    /// Only the owner can call this.
-    pub fn dispatch_timeout(&mut self) -> Result<(), String> {
+    /// Only the owner can call this, and [Store::set_reveal_default] must be set if the policy is
+    /// [RevealTimeout::Default].
+    pub fn dispatch_timeout(&mut self) -> Result<(), crate::Error> {
         crate::forbid!(self.player.is_some());
 
         let action = match self
@@ -477,7 +656,7 @@ impl<S: State> Store<S> {
             .state
             .0
             .as_ref()
-            .ok_or("self.proof.state.state.0.is_none()")?
+            .ok_or_else(|| crate::forbidden!("self.proof.state.state.0.is_none()"))?
         {
             _StoreState::Pending { phase, .. } => match &*phase.try_borrow().unwrap() {
                 Phase::RandomCommit => {
@@ -487,7 +666,7 @@ impl<S: State> Store<S> {
 
                         self.random
                             .try_fill_bytes(&mut seed)
-                            .map_err(|error| error.to_string())?;
+                            .map_err(|error| crate::forbidden!(error.to_string()))?;
 
                         seed
                     };
@@ -508,7 +687,7 @@ impl<S: State> Store<S> {
 
                         self.random
                             .try_fill_bytes(&mut seed)
-                            .map_err(|error| error.to_string())?;
+                            .map_err(|error| crate::forbidden!(error.to_string()))?;
 
                         seed
                     };
@@ -529,7 +708,7 @@ impl<S: State> Store<S> {
 
                         self.random
                             .try_fill_bytes(&mut seed)
-                            .map_err(|error| error.to_string())?;
+                            .map_err(|error| crate::forbidden!(error.to_string()))?;
 
                         seed
                     };
@@ -541,6 +720,38 @@ impl<S: State> Store<S> {
                         ))),
                     })
                 }
+                Phase::Reveal {
+                    request:
+                        RevealRequest {
+                            player,
+                            reveal,
+                            verify,
+                        },
+                    ..
+                } => match S::reveal_timeout(*player) {
+                    RevealTimeout::Wait => None,
+                    RevealTimeout::Forfeit => Some(crate::ProofAction {
+                        player: None,
+                        action: crate::PlayerAction::Play(StoreAction(_StoreAction::Forfeit)),
+                    }),
+                    RevealTimeout::Default => {
+                        let reveal_default = self
+                            .reveal_default
+                            .as_mut()
+                            .ok_or_else(|| crate::forbidden!("self.reveal_default.is_none()"))?;
+
+                        let secret = reveal(&reveal_default(*player));
+
+                        crate::forbid!(!verify(&secret));
+
+                        Some(crate::ProofAction {
+                            player: None,
+                            action: crate::PlayerAction::Play(StoreAction(
+                                _StoreAction::RevealDefault(secret),
+                            )),
+                        })
+                    }
+                },
                 _ => None,
             },
             _StoreState::Ready { state, secrets, .. } => {
@@ -548,10 +759,10 @@ impl<S: State> Store<S> {
 
                 (self.ready)(
                     state,
-                    [
-                        secrets[0].as_ref().map(|(secret, _)| secret),
-                        secrets[1].as_ref().map(|(secret, _)| secret),
-                    ],
+                    &secrets
+                        .iter()
+                        .map(|secret| secret.as_ref().map(|(secret, _)| secret))
+                        .collect::<Vec<_>>(),
                 );
 
                 None
@@ -570,7 +781,10 @@ impl<S: State> Store<S> {
     }
 
     /// Dispatches any actions the client is required to send.
-    pub fn flush(&mut self) -> Result<(), String> {
+    ///
+    /// If the client is the owner, this also checkpoints the proof once its serialization exceeds
+    /// the checkpoint threshold.
+    pub fn flush(&mut self) -> Result<(), crate::Error> {
         let actions = self.flush_actions(true)?;
 
         if !actions.is_empty() {
@@ -587,22 +801,24 @@ impl<S: State> Store<S> {
             (self.send)(&diff);
 
             self.apply(&diff)?;
+        } else if self.is_checkpoint_due() {
+            self.checkpoint()?;
         } else if let _StoreState::Ready { state, secrets, .. } = self
             .proof
             .state
             .state
             .0
             .as_ref()
-            .ok_or("self.proof.state.state.0.is_none()")?
+            .ok_or_else(|| crate::forbidden!("self.proof.state.state.0.is_none()"))?
         {
             self.seed = None;
 
             (self.ready)(
                 state,
-                [
-                    secrets[0].as_ref().map(|(secret, _)| secret),
-                    secrets[1].as_ref().map(|(secret, _)| secret),
-                ],
+                &secrets
+                    .iter()
+                    .map(|secret| secret.as_ref().map(|(secret, _)| secret))
+                    .collect::<Vec<_>>(),
             );
         }
 
@@ -614,20 +830,31 @@ impl<S: State> Store<S> {
     ///
     /// If `check_sender` is `false`, also includes actions that the client is capable of sending
     /// due to knowing secret information, but shouldn't due to not being the expected sender.
-    pub fn flush_actions(&mut self, check_sender: bool) -> Result<Vec<StoreAction<S>>, String> {
+    pub fn flush_actions(
+        &mut self,
+        check_sender: bool,
+    ) -> Result<Vec<StoreAction<S>>, crate::Error> {
+        if self.proof.is_final() {
+            return Ok(Vec::new());
+        }
+
         self.proof
             .state
             .state
             .logger()
             .try_borrow_mut()
-            .map_err(|error| error.to_string())?
+            .map_err(|error| crate::forbidden!(error.to_string()))?
             .enabled = false;
 
         let mut state = self.proof.compute_state().state;
         let mut actions = Vec::new();
 
         loop {
-            let action = match state.0.as_ref().ok_or("state.0.is_none()")? {
+            let action = match state
+                .0
+                .as_ref()
+                .ok_or_else(|| crate::forbidden!("state.0.is_none()"))?
+            {
                 _StoreState::Pending { phase, secrets, .. } => {
                     match (&*phase.try_borrow().unwrap(), self.player) {
                         (Phase::RandomCommit, Some(0)) => {
@@ -636,7 +863,7 @@ impl<S: State> Store<S> {
 
                                 self.random
                                     .try_fill_bytes(&mut seed)
-                                    .map_err(|error| error.to_string())?;
+                                    .map_err(|error| crate::forbidden!(error.to_string()))?;
 
                                 seed
                             };
@@ -645,13 +872,18 @@ impl<S: State> Store<S> {
 
                             Some(_StoreAction::RandomCommit(crate::crypto::keccak256(&seed)))
                         }
-                        (Phase::RandomReply { .. }, Some(1)) => {
+                        (
+                            Phase::RandomReply {
+                                player: replier, ..
+                            },
+                            Some(player),
+                        ) if player == *replier => {
                             let seed = {
                                 let mut seed = <rand_xorshift::XorShiftRng as rand::SeedableRng>::Seed::default();
 
                                 self.random
                                     .try_fill_bytes(&mut seed)
-                                    .map_err(|error| error.to_string())?;
+                                    .map_err(|error| crate::forbidden!(error.to_string()))?;
 
                                 seed
                             };
@@ -679,7 +911,7 @@ impl<S: State> Store<S> {
 
                                 Some(_StoreAction::RandomReveal(seed.to_vec()))
                             } else {
-                                return Err("self.seed.is_none()".to_string());
+                                return Err(crate::forbidden!("self.seed.is_none()"));
                             }
                         }
                         (
@@ -700,7 +932,10 @@ impl<S: State> Store<S> {
                             {
                                 if let Some(secret) = &secrets[usize::from(*player)] {
                                     let secret = reveal(
-                                        &secret.try_borrow().map_err(|error| error.to_string())?.0,
+                                        &secret
+                                            .try_borrow()
+                                            .map_err(|error| crate::forbidden!(error.to_string()))?
+                                            .0,
                                     );
 
                                     crate::forbid!(!verify(&secret));
@@ -716,79 +951,431 @@ impl<S: State> Store<S> {
                         _ => None,
                     }
                 }
-                _StoreState::Ready { .. } => None,
-            };
+                _StoreState::Ready { .. } => None,
+            };
+
+            match action {
+                Some(action) => {
+                    let action = StoreAction(action);
+
+                    crate::State::apply(&mut state, self.player, &action)
+                        .map_err(|error| crate::Error::rejected(self.player, error))?;
+
+                    actions.push(action);
+                }
+                None => break,
+            }
+        }
+
+        Ok(actions)
+    }
+
+    /// Verifies and applies a cryptographically constructed diff to the store, then calls .flush().
+    ///
+    /// `diff` must have been constructed using [Store::diff] on a store with the same state.
+    pub fn apply(&mut self, diff: &StoreDiff<S>) -> Result<(), crate::Error> {
+        self.raw_apply(diff)?;
+
+        self.flush()
+    }
+    /// Verifies and applies a cryptographically constructed diff to the store.
+    ///
+    /// `diff` must have been constructed using [Store::diff] on a store with the same state.
+    ///
+    /// A diff constructed on a proof the store hasn't reached yet is buffered until its predecessor
+    /// is applied, unless [Store::pending] is full, or its author already fills their share of it.
+    /// Only diffs by the owner, a player or one of their current subkeys are buffered.
+    /// A diff constructed on a proof the store has already applied a diff to is stale, and is
+    /// rejected.
+    ///
+    /// Buffered diffs are applied automatically once their predecessors are.
+    /// Buffered diffs that fail to apply are discarded without failing this call.
+    /// See [Store::set_discard_hook].
+    pub fn raw_apply(&mut self, diff: &StoreDiff<S>) -> Result<(), crate::Error> {
+        self.proof
+            .state
+            .state
+            .logger()
+            .try_borrow_mut()
+            .map_err(|error| crate::forbidden!(error.to_string()))?
+            .enabled = true;
+
+        self.check_equivocation(diff)?;
+
+        if diff.proof() != self.hash() && !self.applied.contains_key(diff.proof()) {
+            return self.buffer(diff);
+        }
+
+        self.proof.apply(diff)?;
+        self.accept(diff);
+
+        while let Some(diff) = self.pending.remove(&self.proof.hash) {
+            match self.proof.apply(&diff) {
+                Ok(()) => self.accept(&diff),
+                Err(error) => {
+                    if let Some(discard) = &mut self.discard {
+                        discard(&diff, &error);
+                    }
+                }
+            }
+        }
+
+        self.track_deadline();
+
+        Ok(())
+    }
+
+    fn buffer(&mut self, diff: &StoreDiff<S>) -> Result<(), crate::Error> {
+        let wrong_proof_hash = || crate::Error::WrongProofHash {
+            expected: self.proof.hash,
+            actual: *diff.proof(),
+        };
+
+        if let Some(pending) = self.pending.get(diff.proof()) {
+            // check_equivocation has ruled out conflicting diffs by the same author
+
+            if pending.author() == diff.author() {
+                return Ok(());
+            }
+
+            return Err(wrong_proof_hash());
+        }
+
+        // unknown authors could otherwise fill the buffer with fresh keys
+
+        if diff.author() != self.owner()
+            && self
+                .proof
+                .state
+                .player(diff.author(), self.owner())
+                .is_none()
+        {
+            return Err(wrong_proof_hash());
+        }
+
+        let share = (self.pending_capacity / (1 + self.proof.state.players.len())).max(1);
+
+        if self.pending.len() >= self.pending_capacity
+            || self
+                .pending
+                .values()
+                .filter(|pending| pending.author() == diff.author())
+                .count()
+                >= share
+        {
+            return Err(wrong_proof_hash());
+        }
+
+        self.pending.insert(*diff.proof(), diff.clone());
+
+        Ok(())
+    }
+
+    fn accept(&mut self, diff: &StoreDiff<S>) {
+        self.applied
+            .insert(*diff.proof(), equivocation::Accepted::new(diff));
+
+        if diff.is_checkpoint() {
+            if let Some(root) = self.proof.checkpoint_root() {
+                self.proof = crate::Proof::new(root);
+
+                // diffs before the checkpoint can no longer be applied
+
+                self.applied.retain(|proof, _| proof == diff.proof());
+            }
+        }
+    }
+
+    /// Checks if [Store::flush] would checkpoint the store's proof.
+    ///
+    /// This is only the case for the owner, once the serialized proof exceeds the checkpoint
+    /// threshold.
+    pub fn is_checkpoint_due(&self) -> bool {
+        self.player.is_none()
+            && self.proof.can_checkpoint()
+            && self.proof.serialized_size() > self.checkpoint_threshold
+    }
+
+    /// Generates a checkpoint diff whose signatures are supplied separately.
+    ///
+    /// The store is unchanged until the signed diff is applied.
+    /// See [crate::Proof::unsigned_checkpoint].
+    pub fn unsigned_checkpoint(&self) -> Result<crate::UnsignedDiff<StoreState<S>>, crate::Error> {
+        self.proof.unsigned_checkpoint()
+    }
+
+    /// Checkpoints the store's proof on behalf of the owner.
+    ///
+    /// See [crate::Proof::checkpoint].
+    pub fn checkpoint(&mut self) -> Result<(), crate::Error> {
+        let diff = self.proof.checkpoint(&mut self.sign)?;
+
+        (self.send)(&diff);
+
+        self.apply(&diff)
+    }
+
+    /// Sets the size in bytes of a serialized proof past which the owner checkpoints it.
+    ///
+    /// The default is [Store::DEFAULT_CHECKPOINT_THRESHOLD].
+    pub fn set_checkpoint_threshold(&mut self, threshold: usize) {
+        self.checkpoint_threshold = threshold;
+    }
+
+    /// Gets the limits for binary representations decoded on behalf of the store.
+    pub fn decode_limits(&self) -> &crate::DecodeLimits {
+        &self.decode_limits
+    }
+
+    /// Sets the limits for binary representations decoded on behalf of the store.
+    ///
+    /// See [Store::decode_limits].
+    pub fn set_decode_limits(&mut self, limits: crate::DecodeLimits) {
+        self.decode_limits = limits;
+    }
+
+    /// Sets the clock used to time pending phases.
+    ///
+    /// Without a clock, the store tracks no deadlines.
+    /// The current pending phase, if any, is timed from now.
+    pub fn set_clock(&mut self, clock: impl Clock + MaybeSend + 'static) {
+        self.clock = Some(Box::new(clock));
+        self.deadline = None;
+
+        self.track_deadline();
+    }
+
+    /// Gets the time allowed to the pending player in each phase.
+    ///
+    /// The default is [State::deadlines].
+    pub fn deadlines(&self) -> &Deadlines {
+        &self.deadlines
+    }
+
+    /// Sets the time allowed to the pending player in each phase.
+    ///
+    /// The current pending phase, if any, is timed from now.
+    pub fn set_deadlines(&mut self, deadlines: Deadlines) {
+        self.deadlines = deadlines;
+        self.deadline = None;
+
+        self.track_deadline();
+    }
+
+    /// Gets the time by which the pending player must act, if any.
+    ///
+    /// See [Store::pending_player] and [Store::poll_deadlines].
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline.and_then(|deadline| deadline.expiry)
+    }
+
+    /// Dispatches a timeout if the pending player has missed their deadline.
+    ///
+    /// Only the owner dispatches timeouts: for other players, this does nothing.
+    /// Each pending phase times out at most once.
+    /// Returns `true` if a timeout was dispatched.
+    ///
+    /// See [Store::dispatch_timeout].
+    pub fn poll_deadlines(&mut self, now: Duration) -> Result<bool, crate::Error> {
+        if self.player.is_none() {
+            if let Some(deadline) = self.deadline {
+                if matches!(deadline.expiry, Some(expiry) if now >= expiry) {
+                    self.dispatch_timeout()?;
+
+                    // the timeout may not have advanced the phase, so don't dispatch it twice
+
+                    if let Some(current) = &mut self.deadline {
+                        if current.phase == deadline.phase {
+                            current.expiry = None;
+                        }
+                    }
+
+                    return Ok(true);
+                }
+            }
+        }
 
-            match action {
-                Some(action) => {
-                    let action = StoreAction(action);
+        Ok(false)
+    }
 
-                    crate::State::apply(&mut state, self.player, &action)?;
+    fn track_deadline(&mut self) {
+        let phase = if self.proof.is_final() {
+            None
+        } else if let Some(_StoreState::Pending { phase, .. }) = &self.proof.state.state.0 {
+            match &*phase.try_borrow().unwrap() {
+                Phase::RandomCommit => self.deadlines.random_commit,
+                Phase::RandomReply { .. } => self.deadlines.random_reply,
+                Phase::RandomReveal { .. } => self.deadlines.random_reveal,
+                Phase::Reveal { .. } => self.deadlines.reveal,
+                Phase::Idle { .. } | Phase::Forfeit { .. } => None,
+            }
+            .map(|timeout| {
+                (
+                    (
+                        self.proof.state.state.action_count(),
+                        self.proof.state.state.reveal_count(),
+                    ),
+                    timeout,
+                )
+            })
+        } else {
+            None
+        };
 
-                    actions.push(action);
-                }
-                None => break,
+        self.deadline = match (phase, &self.clock) {
+            (Some((phase, _)), _)
+                if self
+                    .deadline
+                    .is_some_and(|deadline| deadline.phase == phase) =>
+            {
+                self.deadline
             }
-        }
+            (Some((phase, timeout)), Some(clock)) => Some(deadline::Deadline {
+                phase,
+                expiry: clock.now().checked_add(timeout),
+            }),
+            _ => None,
+        };
+    }
 
-        Ok(actions)
+    /// Gets the diffs buffered while waiting for their predecessors.
+    pub fn pending(&self) -> impl Iterator<Item = &StoreDiff<S>> {
+        self.pending.values()
     }
 
-    /// Verifies and applies a cryptographically constructed diff to the store, then calls .flush().
+    /// Discards buffered diffs for which `expired` returns `true`.
     ///
-    /// `diff` must have been constructed using [Store::diff] on a store with the same state.
-    pub fn apply(&mut self, diff: &StoreDiff<S>) -> Result<(), String> {
-        self.raw_apply(diff)?;
-
-        self.flush()
+    /// See [Store::pending].
+    pub fn expire_pending(&mut self, mut expired: impl FnMut(&StoreDiff<S>) -> bool) {
+        self.pending.retain(|_, diff| !expired(diff));
     }
-    /// Verifies and applies a cryptographically constructed diff to the store.
+
+    /// Sets the maximum number of diffs buffered while waiting for their predecessors.
     ///
-    /// `diff` must have been constructed using [Store::diff] on a store with the same state.
-    pub fn raw_apply(&mut self, diff: &StoreDiff<S>) -> Result<(), String> {
-        self.proof
-            .state
-            .state
-            .logger()
-            .try_borrow_mut()
-            .map_err(|error| error.to_string())?
-            .enabled = true;
+    /// Each author may fill at most an equal share of `capacity` among the owner and players.
+    ///
+    /// The default is [Store::DEFAULT_PENDING_CAPACITY].
+    /// Already buffered diffs are kept even if there are more than `capacity`.
+    pub fn set_pending_capacity(&mut self, capacity: usize) {
+        self.pending_capacity = capacity;
+    }
+
+    fn check_equivocation(&mut self, diff: &StoreDiff<S>) -> Result<(), crate::Error> {
+        let accepted = self.applied.get(diff.proof()).copied().or_else(|| {
+            self.pending
+                .get(diff.proof())
+                .map(equivocation::Accepted::new)
+        });
+
+        if let Some(accepted) = accepted.filter(|accepted| accepted.conflicts(diff)) {
+            if let Some(equivocation) = &mut self.equivocation {
+                equivocation(&Equivocation {
+                    accepted: accepted.digest,
+                    diff,
+                });
+            }
+
+            return Err(crate::forbidden!("diff equivocates")
+                .slashable(diff.author(), crate::Offence::Equivocation));
+        }
 
-        self.proof.apply(diff)?;
         Ok(())
     }
 
+    /// Sets a hook that is called whenever a diff conflicts with one previously applied to or
+    /// buffered by the store.
+    ///
+    /// The store remembers the diffs applied since the last checkpoint, including across
+    /// [Store::serialize].
+    pub fn set_equivocation_hook(
+        &mut self,
+        equivocation: impl FnMut(&Equivocation<'_, S>) + MaybeSend + 'static,
+    ) {
+        self.equivocation = Some(Box::new(equivocation));
+    }
+
+    /// Sets a hook that is called whenever a buffered diff fails to apply once its predecessor is
+    /// applied.
+    ///
+    /// The diff is discarded.
+    /// See [Store::raw_apply].
+    pub fn set_discard_hook(
+        &mut self,
+        discard: impl FnMut(&StoreDiff<S>, &crate::Error) + MaybeSend + 'static,
+    ) {
+        self.discard = Some(Box::new(discard));
+    }
+
+    /// Sets the function the owner uses to supply the secret revealed on behalf of a player
+    /// whose [State::reveal_timeout] policy is [RevealTimeout::Default].
+    ///
+    /// See [Store::dispatch_timeout].
+    pub fn set_reveal_default(
+        &mut self,
+        reveal_default: impl FnMut(crate::Player) -> S::Secret + MaybeSend + 'static,
+    ) {
+        self.reveal_default = Some(Box::new(reveal_default));
+    }
+
     /// Generates a diff that can be applied to a store with the same state.
     ///
     /// See [Store::apply].
     pub fn diff(
         &mut self,
         actions: Vec<crate::ProofAction<StoreState<S>>>,
-    ) -> Result<StoreDiff<S>, String> {
+    ) -> Result<StoreDiff<S>, crate::Error> {
+        self.unsigned_diff(actions)?.sign_with(&mut self.sign)
+    }
+
+    /// Generates a diff whose signatures are supplied separately.
+    ///
+    /// The store is unchanged until the signed diff is applied.
+    /// See [Store::diff].
+    pub fn unsigned_diff(
+        &mut self,
+        actions: Vec<crate::ProofAction<StoreState<S>>>,
+    ) -> Result<crate::UnsignedDiff<StoreState<S>>, crate::Error> {
         self.proof
             .state
             .state
             .logger()
             .try_borrow_mut()
-            .map_err(|error| error.to_string())?
+            .map_err(|error| crate::forbidden!(error.to_string()))?
             .enabled = false;
 
-        self.proof.diff(actions, &mut self.sign)
+        self.proof.unsigned_diff(actions)
     }
 }
 
 type StoreDiff<S> = crate::Diff<StoreState<S>>;
 
+/// Tag preceding the digests of accepted diffs in a serialized store
+///
+/// Distinct from the seed presence flag that follows the proof in stores without any.
+const APPLIED_TAG: u8 = 2;
+
+/// Tag preceding serialized stores for other than two players
+///
+/// Untagged stores begin with their secret knowledge, which is never this large.
+pub(crate) const STORE_TAG: u8 = 0xff;
+
+/// Suffix of serialized store states for other than two players
+///
+/// Untagged store states end with their event count, which never reaches this.
+const STORE_STATE_TAG: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+
 /// Client store state
 #[derive(Clone)]
 pub struct StoreState<S: State>(Option<_StoreState<S>>);
 
 impl<S: State> StoreState<S> {
     /// Constructs a new store state.
+    ///
+    /// `secrets` must have one entry per player.
     pub fn new(
         state: S,
-        secrets: [Option<(S::Secret, rand_xorshift::XorShiftRng)>; 2],
-        log: impl FnMut(Option<crate::Player>, S::Event) + 'static,
+        secrets: Vec<Option<(S::Secret, rand_xorshift::XorShiftRng)>>,
+        log: impl FnMut(Option<crate::Player>, S::Event) + MaybeSend + 'static,
     ) -> Self {
         Self(Some(_StoreState::Ready {
             state,
@@ -796,7 +1383,7 @@ impl<S: State> StoreState<S> {
             action_count: Default::default(),
             reveal_count: Default::default(),
             event_count: Default::default(),
-            logger: Rc::new(RefCell::new(Logger::new(log))),
+            logger: Shared::new(Lock::new(Logger::new(log))),
         }))
     }
 
@@ -805,11 +1392,11 @@ impl<S: State> StoreState<S> {
     /// `data` must have been constructed using [crate::State::serialize].
     pub fn deserialize(
         data: &[u8],
-        log: impl FnMut(Option<crate::Player>, S::Event) + 'static,
-    ) -> Result<Self, String> {
-        let mut state: Self = crate::State::deserialize(data)?;
+        log: impl FnMut(Option<crate::Player>, S::Event) + MaybeSend + 'static,
+    ) -> Result<Self, crate::Error> {
+        let mut state: Self = crate::State::deserialize(data).map_err(crate::Error::decode)?;
 
-        state.set_logger(Rc::new(RefCell::new(Logger::new(log))));
+        state.set_logger(Shared::new(Lock::new(Logger::new(log))));
 
         Ok(state)
     }
@@ -829,21 +1416,22 @@ impl<S: State> StoreState<S> {
         player: crate::Player,
     ) -> Option<Box<dyn Deref<Target = S::Secret> + 'a>> {
         match self.0.as_ref()? {
-            _StoreState::Ready { secrets, .. } => secrets[usize::from(player)]
+            _StoreState::Ready { secrets, .. } => secrets
+                .get(usize::from(player))?
                 .as_ref()
                 .map(|(secret, _)| Box::new(secret) as Box<dyn Deref<Target = S::Secret>>),
 
-            _StoreState::Pending { secrets, .. } => {
-                secrets[usize::from(player)].as_ref().and_then(|secret| {
+            _StoreState::Pending { secrets, .. } => secrets
+                .get(usize::from(player))?
+                .as_ref()
+                .and_then(|secret| {
                     secret
                         .try_borrow()
                         .map(|secret| {
-                            Box::new(Ref::map(secret, |(secret, _)| secret))
-                                as Box<dyn Deref<Target = S::Secret>>
+                            Box::new(SecretGuard(secret)) as Box<dyn Deref<Target = S::Secret>>
                         })
                         .ok()
-                })
-            }
+                }),
         }
     }
 
@@ -870,8 +1458,8 @@ impl<S: State> StoreState<S> {
         &self,
         player: Option<crate::Player>,
         action: &S::Action,
-        using_secrets: [bool; 2],
-    ) -> Result<Log<S>, String>
+        using_secrets: &[bool],
+    ) -> Result<Log<S>, crate::Error>
     where
         S::Event: serde::Serialize + 'static,
     {
@@ -881,9 +1469,12 @@ impl<S: State> StoreState<S> {
             action_count,
             reveal_count,
             ..
-        } = self.0.as_ref().ok_or("self.0.is_none()")?
+        } = self
+            .0
+            .as_ref()
+            .ok_or_else(|| crate::forbidden!("self.0.is_none()"))?
         {
-            let events = Rc::new(RefCell::new(Vec::new()));
+            let events = Shared::new(Lock::new(Vec::new()));
 
             Ok({
                 let mut state = Self(Some(_StoreState::Ready {
@@ -892,7 +1483,7 @@ impl<S: State> StoreState<S> {
                     action_count: *action_count,
                     reveal_count: *reveal_count,
                     event_count: Default::default(),
-                    logger: Rc::new(RefCell::new(Logger::new({
+                    logger: Shared::new(Lock::new(Logger::new({
                         let events = events.clone();
 
                         move |target, event| {
@@ -903,12 +1494,15 @@ impl<S: State> StoreState<S> {
                     }))),
                 }));
 
-                crate::State::apply(&mut state, player, &StoreAction::new(action.clone()))?;
+                crate::State::apply(&mut state, player, &StoreAction::new(action.clone()))
+                    .map_err(|error| crate::Error::rejected(player, error))?;
 
                 let mut complete = true;
 
-                while let _StoreState::Pending { secrets, phase, .. } =
-                    state.0.as_ref().ok_or("state.0.is_none()")?
+                while let _StoreState::Pending { secrets, phase, .. } = state
+                    .0
+                    .as_ref()
+                    .ok_or_else(|| crate::forbidden!("state.0.is_none()"))?
                 {
                     let (player, secret) = if let Phase::Reveal {
                         request: RevealRequest { player, reveal, .. },
@@ -935,7 +1529,8 @@ impl<S: State> StoreState<S> {
                         &mut state,
                         Some(player),
                         &StoreAction(_StoreAction::Reveal(secret)),
-                    )?;
+                    )
+                    .map_err(|error| crate::Error::rejected(Some(player), error))?;
                 }
 
                 if complete {
@@ -944,10 +1539,10 @@ impl<S: State> StoreState<S> {
                     Log::Incomplete
                 }
             }(
-                Rc::try_unwrap(events).ok().unwrap().into_inner()
+                Shared::try_unwrap(events).ok().unwrap().into_inner()
             ))
         } else {
-            Err("self.0 != _StoreState::Ready { .. }".to_string())
+            Err(crate::forbidden!("self.0 != _StoreState::Ready { .. }"))
         }
     }
 
@@ -959,24 +1554,29 @@ impl<S: State> StoreState<S> {
         player: Option<crate::Player>,
         action: S::Action,
         random: &mut impl rand::RngCore,
-    ) -> Result<(), String> {
-        crate::State::apply(self, player, &StoreAction::new(action))?;
+    ) -> Result<(), crate::Error> {
+        crate::State::apply(self, player, &StoreAction::new(action))
+            .map_err(|error| crate::Error::rejected(player, error))?;
 
-        while let _StoreState::Pending { secrets, phase, .. } =
-            self.0.as_ref().ok_or("self.0.is_none()")?
+        while let _StoreState::Pending { secrets, phase, .. } = self
+            .0
+            .as_ref()
+            .ok_or_else(|| crate::forbidden!("self.0.is_none()"))?
         {
-            let borrowed_phase = phase.try_borrow().map_err(|error| error.to_string())?;
+            let borrowed_phase = phase
+                .try_borrow()
+                .map_err(|error| crate::forbidden!(error.to_string()))?;
 
             match &*borrowed_phase {
                 Phase::RandomCommit => {
                     drop(borrowed_phase);
 
                     phase.replace(Phase::Idle {
-                        random: Some(Rc::new(RefCell::new({
+                        random: Some(Shared::new(Lock::new({
                             rand::SeedableRng::from_seed({
                                 let mut seed = <rand_xorshift::XorShiftRng as rand::SeedableRng>::Seed::default();
 
-                                random.try_fill_bytes(&mut seed).map_err(|error| error.to_string())?;
+                                random.try_fill_bytes(&mut seed).map_err(|error| crate::forbidden!(error.to_string()))?;
 
                                 seed
                             })
@@ -988,13 +1588,16 @@ impl<S: State> StoreState<S> {
                         drop(self.0.replace(
                             if let _StoreState::Pending {
                                 mut state,
-                                secrets: [secret1, secret2],
+                                secrets,
                                 action_count,
                                 reveal_count,
                                 phase,
                                 logger,
                             } = state
                             {
+                                // a commit, a reply from every other player, and a reveal
+                                let reveal_count = reveal_count + 1 + secrets.len();
+
                                 if let Poll::Ready((state, context)) = state
                                     .as_mut()
                                     .poll(&mut task::Context::from_waker(&phantom_waker()))
@@ -1003,25 +1606,28 @@ impl<S: State> StoreState<S> {
 
                                     _StoreState::Ready {
                                         state,
-                                        secrets: [
-                                            secret1.map(|secret| {
-                                                Rc::try_unwrap(secret).ok().unwrap().into_inner()
-                                            }),
-                                            secret2.map(|secret| {
-                                                Rc::try_unwrap(secret).ok().unwrap().into_inner()
-                                            }),
-                                        ],
+                                        secrets: secrets
+                                            .into_iter()
+                                            .map(|secret| {
+                                                secret.map(|secret| {
+                                                    Shared::try_unwrap(secret)
+                                                        .ok()
+                                                        .unwrap()
+                                                        .into_inner()
+                                                })
+                                            })
+                                            .collect(),
                                         action_count,
-                                        reveal_count: reveal_count + 3,
+                                        reveal_count,
                                         event_count: context.event_count,
                                         logger,
                                     }
                                 } else {
                                     _StoreState::Pending {
                                         state,
-                                        secrets: [secret1, secret2],
+                                        secrets,
                                         action_count,
-                                        reveal_count: reveal_count + 3,
+                                        reveal_count,
                                         phase,
                                         logger,
                                     }
@@ -1046,8 +1652,12 @@ impl<S: State> StoreState<S> {
                     let player = *player;
 
                     if let Some(secret) = &secrets[usize::from(player)] {
-                        let secret =
-                            reveal(&secret.try_borrow().map_err(|error| error.to_string())?.0);
+                        let secret = reveal(
+                            &secret
+                                .try_borrow()
+                                .map_err(|error| crate::forbidden!(error.to_string()))?
+                                .0,
+                        );
 
                         crate::forbid!(!verify(&secret));
 
@@ -1057,7 +1667,8 @@ impl<S: State> StoreState<S> {
                             self,
                             Some(player),
                             &StoreAction(_StoreAction::Reveal(secret)),
-                        )?;
+                        )
+                        .map_err(|error| crate::Error::rejected(Some(player), error))?;
                     }
                 }
                 _ => (),
@@ -1067,13 +1678,21 @@ impl<S: State> StoreState<S> {
         Ok(())
     }
 
-    fn logger(&self) -> &Rc<RefCell<Logger<S::Event>>> {
+    fn secrets(&self) -> &[Option<(S::Secret, rand_xorshift::XorShiftRng)>] {
+        if let Some(_StoreState::Ready { secrets, .. }) = &self.0 {
+            secrets
+        } else {
+            unreachable!("{}:{}:{}", file!(), line!(), column!());
+        }
+    }
+
+    fn logger(&self) -> &Shared<Lock<Logger<S::Event>>> {
         match self.0.as_ref().unwrap() {
             _StoreState::Ready { logger, .. } | _StoreState::Pending { logger, .. } => logger,
         }
     }
 
-    fn set_logger(&mut self, logger: Rc<RefCell<Logger<S::Event>>>) {
+    fn set_logger(&mut self, logger: Shared<Lock<Logger<S::Event>>>) {
         match self.0.as_mut().unwrap() {
             _StoreState::Ready {
                 logger: state_logger,
@@ -1091,33 +1710,58 @@ impl<S: State> crate::State for StoreState<S> {
     type ID = S::ID;
     type Nonce = S::Nonce;
     type Action = StoreAction<S>;
+    type Scheme = S::Scheme;
 
     fn version() -> &'static [u8] {
         S::version()
     }
 
-    fn challenge(address: &crate::crypto::Address) -> String {
+    fn challenge(address: &crate::crypto::StateAddress<Self>) -> String {
         S::challenge(address)
     }
 
-    fn approval(player: &crate::crypto::Address, subkey: &crate::crypto::Address) -> String {
+    fn approval(
+        player: &crate::crypto::StateAddress<Self>,
+        subkey: &crate::crypto::StateAddress<Self>,
+    ) -> String {
         S::approval(player, subkey)
     }
 
     fn deserialize(mut data: &[u8]) -> Result<Self, String> {
-        crate::forbid!(data.len() < 3 * size_of::<u32>());
+        let tagged = data.len() >= 4 * size_of::<u32>() + STORE_STATE_TAG.len()
+            && data.ends_with(&STORE_STATE_TAG);
+
+        let trailer = if tagged {
+            data = &data[..data.len() - STORE_STATE_TAG.len()];
+
+            4 * size_of::<u32>()
+        } else {
+            3 * size_of::<u32>()
+        };
+
+        crate::forbid!(data.len() < trailer);
 
         Ok(Self(Some(_StoreState::Ready {
             state: {
-                let state = S::deserialize(&data[..data.len() - 3 * size_of::<u32>()])?;
-                data = &data[data.len() - 3 * size_of::<u32>()..];
+                let state = S::deserialize(&data[..data.len() - trailer])?;
+                data = &data[data.len() - trailer..];
                 state
             },
-            secrets: Default::default(),
+            secrets: {
+                let length = if tagged {
+                    crate::utils::read_u32_usize(&mut data)?
+                } else {
+                    2
+                };
+
+                crate::forbid!(length >= usize::from(crate::Player::MAX));
+
+                vec![None; length]
+            },
             action_count: crate::utils::read_u32_usize(&mut data)?,
             reveal_count: crate::utils::read_u32_usize(&mut data)?,
             event_count: crate::utils::read_u32_usize(&mut data)?,
-            logger: Rc::new(RefCell::new(Logger::new(|_, _| ()))),
+            logger: Shared::new(Lock::new(Logger::new(|_, _| ()))),
         })))
     }
 
@@ -1143,6 +1787,7 @@ impl<S: State> crate::State for StoreState<S> {
         match self.0.as_ref()? {
             _StoreState::Ready {
                 state,
+                secrets,
                 action_count,
                 reveal_count,
                 event_count,
@@ -1150,10 +1795,20 @@ impl<S: State> crate::State for StoreState<S> {
             } => {
                 let mut data = State::serialize(state)?;
 
+                // two-player states keep the untagged layout that predates other player counts
+
+                if secrets.len() != 2 {
+                    crate::utils::write_u32_usize(&mut data, secrets.len()).ok()?;
+                }
+
                 crate::utils::write_u32_usize(&mut data, *action_count).ok()?;
                 crate::utils::write_u32_usize(&mut data, *reveal_count).ok()?;
                 crate::utils::write_u32_usize(&mut data, *event_count).ok()?;
 
+                if secrets.len() != 2 {
+                    data.extend(&STORE_STATE_TAG);
+                }
+
                 Some(data)
             }
             _ => None,
@@ -1176,19 +1831,21 @@ impl<S: State> crate::State for StoreState<S> {
                         drop(self.0.replace(
                             if let _StoreState::Ready {
                                 state,
-                                secrets: [secret1, secret2],
+                                secrets,
                                 action_count,
                                 reveal_count,
                                 event_count,
                                 logger,
                             } = state
                             {
-                                let secrets = [
-                                    secret1.map(|secret| Rc::new(RefCell::new(secret))),
-                                    secret2.map(|secret| Rc::new(RefCell::new(secret))),
-                                ];
-
-                                let phase = Rc::new(RefCell::new(Phase::Idle {
+                                let secrets: Vec<_> = secrets
+                                    .into_iter()
+                                    .map(|secret| {
+                                        secret.map(|secret| Shared::new(Lock::new(secret)))
+                                    })
+                                    .collect();
+
+                                let phase = Shared::new(Lock::new(Phase::Idle {
                                     random: None,
                                     secret: None,
                                 }));
@@ -1223,6 +1880,7 @@ impl<S: State> crate::State for StoreState<S> {
             }
             _StoreAction::RandomCommit(hash) => {
                 if let _StoreState::Pending {
+                    secrets,
                     phase,
                     reveal_count,
                     ..
@@ -1233,14 +1891,29 @@ impl<S: State> crate::State for StoreState<S> {
                     if let Phase::RandomCommit = *borrowed_phase {
                         drop(borrowed_phase);
 
-                        crate::forbid!(player != None && player != Some(0));
+                        crate::forbid!(player.is_some() && player != Some(0));
 
                         *reveal_count += 1;
 
-                        phase.replace(Phase::RandomReply {
-                            hash: *hash,
-                            owner_hash: player.is_none(),
-                        });
+                        if secrets.len() < 2 {
+                            phase.replace(Phase::RandomReveal {
+                                hash: *hash,
+                                owner_hash: player.is_none(),
+                                reply: vec![
+                                    0;
+                                    size_of::<
+                                        <rand_xorshift::XorShiftRng as rand::SeedableRng>::Seed,
+                                    >()
+                                ],
+                            });
+                        } else {
+                            phase.replace(Phase::RandomReply {
+                                hash: *hash,
+                                owner_hash: player.is_none(),
+                                reply: Vec::new(),
+                                player: 1,
+                            });
+                        }
                     } else {
                         return Err("borrowed_phase != Phase::RandomCommit".to_string());
                     }
@@ -1250,6 +1923,7 @@ impl<S: State> crate::State for StoreState<S> {
             }
             _StoreAction::RandomReply(seed) => {
                 if let _StoreState::Pending {
+                    secrets,
                     phase,
                     reveal_count,
                     ..
@@ -1257,18 +1931,42 @@ impl<S: State> crate::State for StoreState<S> {
                 {
                     let borrowed_phase = phase.try_borrow().map_err(|error| error.to_string())?;
 
-                    if let Phase::RandomReply { hash, owner_hash } = *borrowed_phase {
-                        drop(borrowed_phase);
+                    if let Phase::RandomReply {
+                        hash,
+                        owner_hash,
+                        reply,
+                        player: replier,
+                    } = &*borrowed_phase
+                    {
+                        crate::forbid!(player.is_some() && player != Some(*replier));
+                        crate::forbid!(!reply.is_empty() && reply.len() != seed.len());
 
-                        crate::forbid!(player != None && player != Some(1));
+                        let (hash, owner_hash, replier) = (*hash, *owner_hash, *replier);
+
+                        let reply = if reply.is_empty() {
+                            seed.to_vec()
+                        } else {
+                            reply.iter().zip(seed).map(|(x, y)| x ^ y).collect()
+                        };
+
+                        drop(borrowed_phase);
 
                         *reveal_count += 1;
 
-                        phase.replace(Phase::RandomReveal {
-                            hash,
-                            owner_hash,
-                            reply: seed.to_vec(),
-                        });
+                        if usize::from(replier) + 1 < secrets.len() {
+                            phase.replace(Phase::RandomReply {
+                                hash,
+                                owner_hash,
+                                reply,
+                                player: replier + 1,
+                            });
+                        } else {
+                            phase.replace(Phase::RandomReveal {
+                                hash,
+                                owner_hash,
+                                reply,
+                            });
+                        }
                     } else {
                         return Err("borrowed_phase != Phase::RandomReply { .. }".to_string());
                     }
@@ -1292,9 +1990,9 @@ impl<S: State> crate::State for StoreState<S> {
                     } = &*borrowed_phase
                     {
                         if *owner_hash {
-                            crate::forbid!(player != None);
+                            crate::forbid!(player.is_some());
                         } else {
-                            crate::forbid!(player != None && player != Some(0));
+                            crate::forbid!(player.is_some() && player != Some(0));
                         }
 
                         if player.is_some() || *owner_hash {
@@ -1315,7 +2013,9 @@ impl<S: State> crate::State for StoreState<S> {
                         *reveal_count += 1;
 
                         phase.replace(Phase::Idle {
-                            random: Some(Rc::new(RefCell::new(rand::SeedableRng::from_seed(seed)))),
+                            random: Some(Shared::new(Lock::new(rand::SeedableRng::from_seed(
+                                seed,
+                            )))),
                             secret: None,
                         });
                     } else {
@@ -1344,7 +2044,52 @@ impl<S: State> crate::State for StoreState<S> {
                             },
                     } = &*borrowed_phase
                     {
-                        crate::forbid!(player != None && player != Some(*revealer));
+                        crate::forbid!(player.is_some() && player != Some(*revealer));
+                        crate::forbid!(!verify(secret));
+
+                        let random = random.clone();
+
+                        drop(borrowed_phase);
+
+                        *reveal_count += 1;
+
+                        phase.replace(Phase::Idle {
+                            random,
+                            secret: Some(secret.clone()),
+                        });
+                    } else {
+                        return Err("borrowed_phase != Phase::Reveal { .. }".to_string());
+                    }
+                } else {
+                    return Err("self.0 != _StoreState::Pending { .. }".to_string());
+                }
+            }
+            _StoreAction::RevealDefault(secret) => {
+                if let _StoreState::Pending {
+                    phase,
+                    reveal_count,
+                    ..
+                } = self.0.as_mut().ok_or("self.0.is_none()")?
+                {
+                    let borrowed_phase = phase.try_borrow().map_err(|error| error.to_string())?;
+
+                    if let Phase::Reveal {
+                        random,
+                        request:
+                            RevealRequest {
+                                player: revealer,
+                                verify,
+                                ..
+                            },
+                    } = &*borrowed_phase
+                    {
+                        crate::forbid!(player.is_some());
+
+                        crate::forbid!(!matches!(
+                            S::reveal_timeout(*revealer),
+                            RevealTimeout::Default
+                        ));
+
                         crate::forbid!(!verify(secret));
 
                         let random = random.clone();
@@ -1364,13 +2109,46 @@ impl<S: State> crate::State for StoreState<S> {
                     return Err("self.0 != _StoreState::Pending { .. }".to_string());
                 }
             }
+            _StoreAction::Forfeit => {
+                if let _StoreState::Pending { phase, .. } =
+                    self.0.as_mut().ok_or("self.0.is_none()")?
+                {
+                    let borrowed_phase = phase.try_borrow().map_err(|error| error.to_string())?;
+
+                    if let Phase::Reveal {
+                        request:
+                            RevealRequest {
+                                player: revealer, ..
+                            },
+                        ..
+                    } = &*borrowed_phase
+                    {
+                        crate::forbid!(player.is_some());
+
+                        crate::forbid!(!matches!(
+                            S::reveal_timeout(*revealer),
+                            RevealTimeout::Forfeit
+                        ));
+
+                        let revealer = *revealer;
+
+                        drop(borrowed_phase);
+
+                        phase.replace(Phase::Forfeit { player: revealer });
+                    } else {
+                        return Err("borrowed_phase != Phase::Reveal { .. }".to_string());
+                    }
+                } else {
+                    return Err("self.0 != _StoreState::Pending { .. }".to_string());
+                }
+            }
         }
 
         if let Some(state) = self.0.take() {
             drop(self.0.replace(
                 if let _StoreState::Pending {
                     mut state,
-                    secrets: [secret1, secret2],
+                    secrets,
                     action_count,
                     reveal_count,
                     phase,
@@ -1385,14 +2163,14 @@ impl<S: State> crate::State for StoreState<S> {
 
                         _StoreState::Ready {
                             state,
-                            secrets: [
-                                secret1.map(|secret| {
-                                    Rc::try_unwrap(secret).ok().unwrap().into_inner()
-                                }),
-                                secret2.map(|secret| {
-                                    Rc::try_unwrap(secret).ok().unwrap().into_inner()
-                                }),
-                            ],
+                            secrets: secrets
+                                .into_iter()
+                                .map(|secret| {
+                                    secret.map(|secret| {
+                                        Shared::try_unwrap(secret).ok().unwrap().into_inner()
+                                    })
+                                })
+                                .collect(),
                             action_count,
                             reveal_count,
                             event_count: context.event_count,
@@ -1401,7 +2179,7 @@ impl<S: State> crate::State for StoreState<S> {
                     } else {
                         _StoreState::Pending {
                             state,
-                            secrets: [secret1, secret2],
+                            secrets,
                             action_count,
                             reveal_count,
                             phase,
@@ -1418,24 +2196,36 @@ impl<S: State> crate::State for StoreState<S> {
 
         Ok(())
     }
+
+    fn outcome(&self) -> crate::Outcome {
+        match &self.0 {
+            Some(_StoreState::Ready { state, .. }) => state.outcome(),
+            Some(_StoreState::Pending { phase, .. }) => match *phase.try_borrow().unwrap() {
+                Phase::Forfeit { player } => crate::Outcome::Resigned(player),
+                _ => crate::Outcome::InProgress,
+            },
+            None => crate::Outcome::InProgress,
+        }
+    }
 }
 
+#[allow(clippy::type_complexity)]
 enum _StoreState<S: State> {
     Ready {
         state: S,
-        secrets: [Option<(S::Secret, rand_xorshift::XorShiftRng)>; 2],
+        secrets: Vec<Option<(S::Secret, rand_xorshift::XorShiftRng)>>,
         action_count: usize,
         reveal_count: usize,
         event_count: usize,
-        logger: Rc<RefCell<Logger<S::Event>>>,
+        logger: Shared<Lock<Logger<S::Event>>>,
     },
     Pending {
-        state: Pin<Box<dyn Future<Output = (S, Context<S::Secret, S::Event>)>>>,
-        secrets: [Option<Rc<RefCell<(S::Secret, rand_xorshift::XorShiftRng)>>>; 2],
+        state: StateFuture<S>,
+        secrets: Vec<Option<Shared<Lock<(S::Secret, rand_xorshift::XorShiftRng)>>>>,
         action_count: usize,
         reveal_count: usize,
-        phase: Rc<RefCell<Phase<S::Secret>>>,
-        logger: Rc<RefCell<Logger<S::Event>>>,
+        phase: Shared<Lock<Phase<S::Secret>>>,
+        logger: Shared<Lock<Logger<S::Event>>>,
     },
 }
 
@@ -1508,6 +2298,8 @@ enum _StoreAction<S: State> {
     RandomReply(#[derivative(Debug(format_with = "crate::utils::fmt_hex"))] Vec<u8>),
     RandomReveal(#[derivative(Debug(format_with = "crate::utils::fmt_hex"))] Vec<u8>),
     Reveal(#[derivative(Debug(format_with = "crate::utils::fmt_hex"))] Vec<u8>),
+    Forfeit,
+    RevealDefault(#[derivative(Debug(format_with = "crate::utils::fmt_hex"))] Vec<u8>),
 }
 
 impl<S: State> crate::Action for _StoreAction<S> {
@@ -1520,6 +2312,12 @@ impl<S: State> crate::Action for _StoreAction<S> {
             2 => Ok(Self::RandomReply(data.to_vec())),
             3 => Ok(Self::RandomReveal(data.to_vec())),
             4 => Ok(Self::Reveal(data.to_vec())),
+            5 => {
+                crate::forbid!(!data.is_empty());
+
+                Ok(Self::Forfeit)
+            }
+            6 => Ok(Self::RevealDefault(data.to_vec())),
             byte => Err(format!("byte == {}", byte)),
         }
     }
@@ -1548,12 +2346,25 @@ impl<S: State> crate::Action for _StoreAction<S> {
                 crate::utils::write_u8(&mut data, 4);
                 data.extend(secret);
             }
+            Self::Forfeit => {
+                crate::utils::write_u8(&mut data, 5);
+            }
+            Self::RevealDefault(secret) => {
+                crate::utils::write_u8(&mut data, 6);
+                data.extend(secret);
+            }
         }
 
         data
     }
 }
 
+/// Future returned by [State::apply]
+///
+/// With the `sync` feature, this is [Send].
+pub type StateFuture<S> =
+    Pin<Box<dyn_send!(Future<Output = (S, Context<<S as State>::Secret, <S as State>::Event>)>)>>;
+
 /// Domain-specific store state trait
 pub trait State: Clone {
     /// Identifier type
@@ -1566,30 +2377,38 @@ pub trait State: Clone {
     type Action: crate::Action;
 
     /// Event type
-    type Event;
+    type Event: MaybeSend;
 
     /// Secret type
     type Secret: Secret;
 
+    /// Signature scheme type
+    ///
+    /// Use [crate::crypto::Secp256k1] for Ethereum compatibility.
+    type Scheme: crate::crypto::SignatureScheme;
+
     /// Gets the ABI version of this implementation.
     ///
     /// See [super::tag] and [super::version::version] for potentially helpful utilities.
     fn version() -> &'static [u8];
 
     /// Gets the challenge that must be signed in order to certify the subkey with the given address.
-    fn challenge(address: &crate::crypto::Address) -> String {
+    fn challenge(address: &<Self::Scheme as crate::crypto::SignatureScheme>::Address) -> String {
         format!(
             "Sign to play! This won't cost anything.\n\n{}\n",
-            crate::crypto::Addressable::eip55(address)
+            crate::crypto::format_address(address.as_ref())
         )
     }
 
     /// Gets the approval that must be signed by the owner in order to approve a subkey for a player.
-    fn approval(player: &crate::crypto::Address, subkey: &crate::crypto::Address) -> String {
+    fn approval(
+        player: &<Self::Scheme as crate::crypto::SignatureScheme>::Address,
+        subkey: &<Self::Scheme as crate::crypto::SignatureScheme>::Address,
+    ) -> String {
         format!(
             "Approve {} for {}.",
-            crate::crypto::Addressable::eip55(subkey),
-            crate::crypto::Addressable::eip55(player),
+            crate::crypto::format_address(subkey.as_ref()),
+            crate::crypto::format_address(player.as_ref()),
         )
     }
 
@@ -1615,12 +2434,56 @@ pub trait State: Clone {
     fn verify(&self, player: Option<crate::Player>, action: &Self::Action) -> Result<(), String>;
 
     /// Applies an action by a given player to the state.
+    ///
+    /// The returned future is usually an `async` block boxed with [Box::pin].
     fn apply(
         self,
         player: Option<crate::Player>,
         action: &Self::Action,
         context: Context<Self::Secret, Self::Event>,
-    ) -> Pin<Box<dyn Future<Output = (Self, Context<Self::Secret, Self::Event>)>>>;
+    ) -> StateFuture<Self>;
+
+    /// Gets the outcome of the game.
+    ///
+    /// Once the outcome is final, no further [crate::PlayerAction::Play] actions are accepted.
+    fn outcome(&self) -> crate::Outcome {
+        crate::Outcome::InProgress
+    }
+
+    /// Gets the policy for a player who stalls a [Context::reveal] or [Context::reveal_unique]
+    /// request.
+    ///
+    /// The owner enforces the policy with [Store::dispatch_timeout].
+    fn reveal_timeout(_player: crate::Player) -> RevealTimeout {
+        RevealTimeout::Wait
+    }
+
+    /// Gets the time allowed to the pending player in each phase.
+    ///
+    /// The default is no deadlines.
+    /// See [Store::set_deadlines] to override this for a single store.
+    fn deadlines() -> Deadlines {
+        Deadlines::default()
+    }
+}
+
+/// Policy for a player who stalls a [Context::reveal] or [Context::reveal_unique] request
+///
+/// See [State::reveal_timeout].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevealTimeout {
+    /// The match waits for the player indefinitely.
+    Wait,
+
+    /// The player forfeits the match, which ends with [crate::Outcome::Resigned].
+    Forfeit,
+
+    /// The owner reveals on the player's behalf, as if the player's secret were a default
+    /// supplied by the owner.
+    ///
+    /// The request's `verify` function must accept the revealed value.
+    /// See [Store::set_reveal_default].
+    Default,
 }
 
 /// Domain-specific store state secret trait
@@ -1647,11 +2510,12 @@ impl<T: serde::Serialize + serde::de::DeserializeOwned + Clone> Secret for T {
 }
 
 /// [State::apply] utilities
+#[allow(clippy::type_complexity)]
 pub struct Context<S: Secret, E> {
-    phase: Rc<RefCell<Phase<S>>>,
-    secrets: [Option<Rc<RefCell<(S, rand_xorshift::XorShiftRng)>>>; 2],
+    phase: Shared<Lock<Phase<S>>>,
+    secrets: Vec<Option<Shared<Lock<(S, rand_xorshift::XorShiftRng)>>>>,
     event_count: usize,
-    logger: (bool, Rc<RefCell<Logger<E>>>),
+    logger: (bool, Shared<Lock<Logger<E>>>),
 }
 
 impl<S: Secret, E> Context<S, E> {
@@ -1727,8 +2591,8 @@ impl<S: Secret, E> Context<S, E> {
     pub async fn reveal<T: Secret>(
         &mut self,
         player: crate::Player,
-        reveal: impl Fn(&S) -> T + 'static,
-        verify: impl Fn(&T) -> bool + 'static,
+        reveal: impl Fn(&S) -> T + MaybeSend + 'static,
+        verify: impl Fn(&T) -> bool + MaybeSend + 'static,
     ) -> T {
         self.phase.replace(Phase::Reveal {
             random: None,
@@ -1759,8 +2623,8 @@ impl<S: Secret, E> Context<S, E> {
     pub async fn reveal_unique<T: Secret>(
         &mut self,
         player: crate::Player,
-        reveal: impl Fn(&S) -> T + 'static,
-        verify: impl Fn(&T) -> bool + 'static,
+        reveal: impl Fn(&S) -> T + MaybeSend + 'static,
+        verify: impl Fn(&T) -> bool + MaybeSend + 'static,
     ) -> T {
         let random = if let Phase::Idle { random, .. } = &*self.phase.try_borrow().unwrap() {
             random.clone()
@@ -1861,13 +2725,15 @@ pub use tester::Tester;
 #[derive(Debug)]
 enum Phase<S: Secret> {
     Idle {
-        random: Option<Rc<RefCell<rand_xorshift::XorShiftRng>>>,
+        random: Option<Shared<Lock<rand_xorshift::XorShiftRng>>>,
         secret: Option<Vec<u8>>,
     },
     RandomCommit,
     RandomReply {
         hash: crate::crypto::Hash,
         owner_hash: bool,
+        reply: Vec<u8>,
+        player: crate::Player,
     },
     RandomReveal {
         hash: crate::crypto::Hash,
@@ -1875,31 +2741,39 @@ enum Phase<S: Secret> {
         reply: Vec<u8>,
     },
     Reveal {
-        random: Option<Rc<RefCell<rand_xorshift::XorShiftRng>>>,
+        random: Option<Shared<Lock<rand_xorshift::XorShiftRng>>>,
         request: RevealRequest<S>,
     },
+    Forfeit {
+        player: crate::Player,
+    },
 }
 
 #[derive(derivative::Derivative)]
 #[derivative(Debug)]
+#[allow(clippy::type_complexity)]
 struct RevealRequest<S: Secret> {
     player: crate::Player,
     #[derivative(Debug = "ignore")]
-    reveal: Box<dyn Fn(&S) -> Vec<u8>>,
+    reveal: Box<dyn_send!(Fn(&S) -> Vec<u8>)>,
     #[derivative(Debug = "ignore")]
-    verify: Box<dyn Fn(&[u8]) -> bool>,
+    verify: Box<dyn_send!(Fn(&[u8]) -> bool)>,
 }
 
 struct Logger<E> {
-    log: Box<dyn FnMut(Option<crate::Player>, E)>,
+    log: Box<dyn_send!(FnMut(Option<crate::Player>, E))>,
     event_count: usize,
     enabled: bool,
 }
 
 impl<E> Logger<E> {
-    fn new(log: impl FnMut(Option<crate::Player>, E) + 'static) -> Self {
+    fn new(log: impl FnMut(Option<crate::Player>, E) + MaybeSend + 'static) -> Self {
+        Self::boxed(Box::new(log))
+    }
+
+    fn boxed(log: Box<dyn_send!(FnMut(Option<crate::Player>, E))>) -> Self {
         Self {
-            log: Box::new(log),
+            log,
             event_count: Default::default(),
             enabled: true,
         }
@@ -1914,7 +2788,7 @@ impl<E> Logger<E> {
     }
 }
 
-struct SharedXorShiftRngFuture<S: Secret>(Rc<RefCell<Phase<S>>>);
+struct SharedXorShiftRngFuture<S: Secret>(Shared<Lock<Phase<S>>>);
 
 impl<S: Secret> Future for SharedXorShiftRngFuture<S> {
     type Output = SharedXorShiftRng;
@@ -1936,7 +2810,17 @@ impl<S: Secret> Future for SharedXorShiftRngFuture<S> {
     }
 }
 
-struct SharedXorShiftRng(Rc<RefCell<rand_xorshift::XorShiftRng>>);
+struct SecretGuard<'a, S>(Guard<'a, (S, rand_xorshift::XorShiftRng)>);
+
+impl<S> Deref for SecretGuard<'_, S> {
+    type Target = S;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0 .0
+    }
+}
+
+struct SharedXorShiftRng(Shared<Lock<rand_xorshift::XorShiftRng>>);
 
 impl rand::RngCore for SharedXorShiftRng {
     fn next_u32(&mut self) -> u32 {
@@ -1956,7 +2840,7 @@ impl rand::RngCore for SharedXorShiftRng {
     }
 }
 
-struct RevealFuture<S: Secret>(Rc<RefCell<Phase<S>>>);
+struct RevealFuture<S: Secret>(Shared<Lock<Phase<S>>>);
 
 impl<S: Secret> Future for RevealFuture<S> {
     type Output = Vec<u8>;
@@ -1978,6 +2862,99 @@ impl<S: Secret> Future for RevealFuture<S> {
     }
 }
 
+#[allow(clippy::type_complexity)]
+fn read_secrets<S: State>(
+    data: &mut &[u8],
+    length: usize,
+    limits: &crate::DecodeLimits,
+) -> Result<Vec<Option<(S::Secret, rand_xorshift::XorShiftRng)>>, crate::Error> {
+    let mut secrets = Vec::with_capacity(length);
+
+    for _ in 0..length {
+        secrets.push(if crate::utils::read_u8_bool(data)? {
+            let size = crate::utils::read_u32_usize(data)?;
+
+            crate::forbid!(size > limits.max_secret_size);
+            crate::forbid!(data.len() < size);
+            let secret = S::Secret::deserialize(&data[..size]).map_err(crate::Error::decode)?;
+            *data = &data[size..];
+
+            let size = crate::utils::read_u32_usize(data)?;
+
+            crate::forbid!(data.len() < size);
+            let random = rand_xorshift::XorShiftRng::deserialize(&data[..size])
+                .map_err(crate::Error::decode)?;
+            *data = &data[size..];
+
+            Some((secret, random))
+        } else {
+            None
+        });
+    }
+
+    Ok(secrets)
+}
+
+fn write_secrets<S: State>(
+    data: &mut Vec<u8>,
+    secrets: &[Option<(S::Secret, rand_xorshift::XorShiftRng)>],
+    with_knowledge: &SecretKnowledge,
+) {
+    for (i, secret) in secrets.iter().enumerate() {
+        if *with_knowledge == SecretKnowledge::All
+            || *with_knowledge == SecretKnowledge::Some(i.try_into().unwrap())
+        {
+            match secret {
+                Some((secret, random)) => {
+                    crate::utils::write_u8_bool(data, true);
+
+                    let secret = secret.serialize();
+                    crate::utils::write_u32_usize(data, secret.len()).unwrap();
+                    data.extend(secret);
+
+                    let random = random.serialize();
+                    crate::utils::write_u32_usize(data, random.len()).unwrap();
+                    data.extend(random);
+                }
+                None => crate::utils::write_u8_bool(data, false),
+            }
+        } else {
+            crate::utils::write_u8_bool(data, false);
+        }
+    }
+}
+
+/// Reads the player of a serialized store.
+///
+/// See [SecretKnowledge::from].
+fn read_knowledge(data: &mut &[u8]) -> Result<Option<crate::Player>, crate::Error> {
+    match crate::utils::read_u8(data)?.into() {
+        SecretKnowledge::Some(player) => Ok(Some(player)),
+        _ => Ok(None),
+    }
+}
+
+fn write_knowledge(
+    data: &mut Vec<u8>,
+    player: Option<crate::Player>,
+    with_knowledge: &SecretKnowledge,
+) {
+    crate::utils::write_u8(
+        data,
+        match (player, with_knowledge) {
+            (None, SecretKnowledge::None) => 0,
+            (None, SecretKnowledge::All) => 3,
+            (Some(player), _) | (None, &SecretKnowledge::Some(player)) => {
+                if player < 2 {
+                    1 + player
+                } else {
+                    2 + player
+                }
+            }
+        },
+    );
+}
+
 fn phantom_waker() -> Waker {
     unsafe {
         Waker::from_raw(RawWaker::new(
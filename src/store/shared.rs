@@ -0,0 +1,105 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Shared mutable state
+//!
+//! With the `sync` feature, shared state is reference counted atomically and locked with a
+//! mutex, and boxed callbacks and futures are [Send], so stores can move between threads.
+
+/// Trait object type that is also [Send] with the `sync` feature
+#[cfg(not(feature = "sync"))]
+macro_rules! dyn_send {
+    ($($bound:tt)*) => {
+        dyn $($bound)*
+    };
+}
+
+/// Trait object type that is also [Send] with the `sync` feature
+#[cfg(feature = "sync")]
+macro_rules! dyn_send {
+    ($($bound:tt)*) => {
+        dyn $($bound)* + Send
+    };
+}
+
+/// Bound that is [Send] with the `sync` feature, and satisfied by every type otherwise
+#[cfg(not(feature = "sync"))]
+pub trait MaybeSend {}
+
+#[cfg(not(feature = "sync"))]
+impl<T: ?Sized> MaybeSend for T {}
+
+/// Bound that is [Send] with the `sync` feature, and satisfied by every type otherwise
+#[cfg(feature = "sync")]
+pub trait MaybeSend: Send {}
+
+#[cfg(feature = "sync")]
+impl<T: ?Sized + Send> MaybeSend for T {}
+
+#[cfg(not(feature = "sync"))]
+pub(crate) use {
+    alloc::rc::Rc as Shared,
+    core::cell::{Ref as Guard, RefCell as Lock},
+};
+
+#[cfg(feature = "sync")]
+pub(crate) use {alloc::sync::Arc as Shared, std::sync::MutexGuard as Guard};
+
+/// Mutex with the subset of the [core::cell::RefCell] interface the store uses
+///
+/// [Lock::borrow_mut] and [Lock::replace] wait for other threads to release the lock, while
+/// [Lock::try_borrow] and [Lock::try_borrow_mut] fail instead, like borrowing a mutably borrowed
+/// [core::cell::RefCell].
+/// A lock poisoned by a panicking thread is still usable.
+#[cfg(feature = "sync")]
+#[derive(Debug, Default)]
+pub(crate) struct Lock<T>(std::sync::Mutex<T>);
+
+#[cfg(feature = "sync")]
+impl<T> Lock<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self(std::sync::Mutex::new(value))
+    }
+
+    pub(crate) fn borrow_mut(&self) -> Guard<'_, T> {
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub(crate) fn try_borrow(&self) -> Result<Guard<'_, T>, std::sync::TryLockError<Guard<'_, T>>> {
+        self.try_borrow_mut()
+    }
+
+    pub(crate) fn try_borrow_mut(
+        &self,
+    ) -> Result<Guard<'_, T>, std::sync::TryLockError<Guard<'_, T>>> {
+        match self.0.try_lock() {
+            Err(std::sync::TryLockError::Poisoned(error)) => Ok(error.into_inner()),
+            result => result,
+        }
+    }
+
+    pub(crate) fn replace(&self, value: T) -> T {
+        core::mem::replace(&mut *self.borrow_mut(), value)
+    }
+
+    pub(crate) fn into_inner(self) -> T {
+        self.0
+            .into_inner()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
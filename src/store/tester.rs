@@ -16,19 +16,17 @@
 
 //! Store tester
 
-use crate::store::SecretKnowledge;
+use crate::{
+    crypto::SignatureScheme,
+    store::{
+        shared::{Lock, Shared},
+        MaybeSend, SecretKnowledge,
+    },
+};
 
 use {
-    alloc::{
-        boxed::Box,
-        collections::VecDeque,
-        format,
-        rc::Rc,
-        string::{String, ToString},
-        vec,
-        vec::Vec,
-    },
-    core::{cell::RefCell, column, file, line, mem::size_of, ops::Deref},
+    alloc::{boxed::Box, collections::VecDeque, string::ToString, vec, vec::Vec},
+    core::{column, convert::TryInto, file, line, mem::size_of, ops::Deref, time::Duration},
 };
 
 /// Store tester
@@ -37,9 +35,11 @@ where
     S::ID: Default,
 {
     proof: crate::Proof<crate::store::StoreState<S>>,
-    stores: [crate::store::Store<S>; 3],
-    queues: [Rc<RefCell<VecDeque<Vec<u8>>>>; 3],
+    stores: Vec<crate::store::Store<S>>,
+    queues: Vec<Shared<Lock<VecDeque<Vec<u8>>>>>,
     no_version_check: bool,
+    limits: crate::DecodeLimits,
+    clock: Shared<Lock<Duration>>,
 }
 
 impl<S: crate::store::State> Tester<S>
@@ -47,247 +47,179 @@ where
     S::ID: Default,
 {
     /// Constructs a new store tester.
+    ///
+    /// There is one player per secret in `secrets`.
     pub fn new(
         state: S,
-        [secret1, secret2]: [S::Secret; 2],
+        secrets: Vec<S::Secret>,
         actions: Vec<crate::ProofAction<crate::store::StoreState<S>>>,
-        ready: impl FnMut(Option<crate::Player>, &S, [Option<&S::Secret>; 2]) + 'static,
-        log: impl FnMut(Option<crate::Player>, Option<crate::Player>, S::Event) + 'static,
+        ready: impl FnMut(Option<crate::Player>, &S, &[Option<&S::Secret>]) + MaybeSend + 'static,
+        log: impl FnMut(Option<crate::Player>, Option<crate::Player>, S::Event) + MaybeSend + 'static,
         no_version_check: bool,
-    ) -> Result<Self, String> {
-        let mut randoms = {
-            const SIZE: usize = size_of::<
-                <libsecp256k1_rand::rngs::StdRng as libsecp256k1_rand::SeedableRng>::Seed,
-            >();
+    ) -> Result<Self, crate::Error> {
+        crate::forbid!(secrets.len() >= usize::from(crate::Player::MAX));
+
+        let mut randoms: Vec<_> = (0..=secrets.len())
+            .map(|i| {
+                const SIZE: usize = size_of::<
+                    <libsecp256k1_rand::rngs::StdRng as libsecp256k1_rand::SeedableRng>::Seed,
+                >();
 
-            [
-                <libsecp256k1_rand::rngs::StdRng as libsecp256k1_rand::SeedableRng>::from_seed(
-                    [1; SIZE],
-                ),
-                <libsecp256k1_rand::rngs::StdRng as libsecp256k1_rand::SeedableRng>::from_seed(
-                    [2; SIZE],
-                ),
                 <libsecp256k1_rand::rngs::StdRng as libsecp256k1_rand::SeedableRng>::from_seed(
-                    [3; SIZE],
-                ),
-            ]
-        };
+                    [seed(i); SIZE],
+                )
+            })
+            .collect();
 
-        let (keys, subkeys) = generate_keys_and_subkeys(&mut randoms)?;
+        let (keys, subkeys) = generate_keys_and_subkeys::<S::Scheme, _>(&mut randoms)?;
 
-        let randoms = {
+        let randoms = (0..=secrets.len()).map(|i| {
             const SIZE: usize =
                 size_of::<<rand_xorshift::XorShiftRng as rand::SeedableRng>::Seed>();
 
-            [
-                <rand_xorshift::XorShiftRng as rand::SeedableRng>::from_seed([1; SIZE]),
-                <rand_xorshift::XorShiftRng as rand::SeedableRng>::from_seed([2; SIZE]),
-                <rand_xorshift::XorShiftRng as rand::SeedableRng>::from_seed([3; SIZE]),
-            ]
-        };
-
-        let certificates = if cfg!(feature = "test-approvals") {
-            [
-                {
-                    let player = crate::crypto::Addressable::address(&keys[1]);
-                    let subkey = crate::crypto::Addressable::address(&subkeys[0]);
+            <rand_xorshift::XorShiftRng as rand::SeedableRng>::from_seed([seed(i); SIZE])
+        });
 
-                    crate::ProofAction {
-                        player: None,
-                        action: crate::PlayerAction::Approve {
-                            player,
-                            subkey,
-                            signature: crate::crypto::sign(
-                                <crate::store::StoreState<S> as crate::State>::approval(
-                                    &player, &subkey,
-                                )
-                                .as_bytes(),
-                                &keys[0],
-                            ),
-                        },
-                    }
-                },
-                {
-                    let player = crate::crypto::Addressable::address(&keys[2]);
-                    let subkey = crate::crypto::Addressable::address(&subkeys[1]);
+        let state = crate::ProofState::new(
+            Default::default(),
+            keys[1..].iter().map(S::Scheme::address).collect(),
+            crate::store::StoreState::new(state, vec![None; secrets.len()], |_, _| ()),
+        )?;
 
-                    crate::ProofAction {
+        let certificates = if cfg!(feature = "test-approvals") {
+            subkeys
+                .iter()
+                .enumerate()
+                .map(|(i, subkey)| {
+                    let player = S::Scheme::address(&keys[1 + i]);
+                    let subkey = S::Scheme::address(subkey);
+
+                    Ok(crate::ProofAction {
                         player: None,
                         action: crate::PlayerAction::Approve {
                             player,
                             subkey,
-                            signature: crate::crypto::sign(
-                                <crate::store::StoreState<S> as crate::State>::approval(
-                                    &player, &subkey,
-                                )
-                                .as_bytes(),
+                            signature: S::Scheme::sign(
+                                &state.approval(&player, &subkey),
                                 &keys[0],
-                            ),
+                            )?,
                         },
-                    }
-                },
-            ]
+                    })
+                })
+                .collect::<Result<Vec<_>, crate::Error>>()?
         } else {
-            [
-                {
-                    let address = crate::crypto::Addressable::address(&subkeys[0]);
-
-                    crate::ProofAction {
-                        player: Some(0),
+            subkeys
+                .iter()
+                .enumerate()
+                .map(|(i, subkey)| {
+                    let address = S::Scheme::address(subkey);
+
+                    Ok(crate::ProofAction {
+                        player: Some(i.try_into().unwrap()),
                         action: crate::PlayerAction::Certify {
                             address,
-                            signature: crate::crypto::sign(
-                                <crate::store::StoreState<S> as crate::State>::challenge(&address)
-                                    .as_bytes(),
-                                &keys[1],
-                            ),
+                            signature: S::Scheme::sign(
+                                &state.challenge(&address, None),
+                                &keys[1 + i],
+                            )?,
+                            expiry: None,
                         },
-                    }
-                },
-                {
-                    let address = crate::crypto::Addressable::address(&subkeys[1]);
-
-                    crate::ProofAction {
-                        player: Some(1),
-                        action: crate::PlayerAction::Certify {
-                            address,
-                            signature: crate::crypto::sign(
-                                <crate::store::StoreState<S> as crate::State>::challenge(&address)
-                                    .as_bytes(),
-                                &keys[2],
-                            ),
-                        },
-                    }
-                },
-            ]
+                    })
+                })
+                .collect::<Result<Vec<_>, crate::Error>>()?
         };
 
         let proof = crate::Proof::new(crate::RootProof::new(
-            crate::ProofState::new(
-                Default::default(),
-                [
-                    crate::crypto::Addressable::address(&keys[1]),
-                    crate::crypto::Addressable::address(&keys[2]),
-                ],
-                crate::store::StoreState::new(state, Default::default(), |_, _| ()),
-            )?,
+            state,
             [&certificates[..], &actions].concat(),
-            &mut |message| Ok(crate::crypto::sign(message, &keys[0])),
+            &mut |message| S::Scheme::sign(message, &keys[0]).map_err(|error| error.to_string()),
         )?);
 
-        let queues = [
-            Rc::new(RefCell::new(VecDeque::new())),
-            Rc::new(RefCell::new(VecDeque::new())),
-            Rc::new(RefCell::new(VecDeque::new())),
-        ];
+        let queues: Vec<_> = (0..=secrets.len())
+            .map(|_| Shared::new(Lock::new(VecDeque::new())))
+            .collect();
 
         let root = proof.root.serialize();
 
-        let stores = {
-            let [random0, random1, random2] = randoms;
-            let [subkey1, subkey2] = subkeys;
-            let ready = Rc::new(RefCell::new(ready));
-            let log = Rc::new(RefCell::new(log));
-
-            [
-                {
-                    let mut store = crate::store::Store::new(
-                        None,
-                        &root,
-                        [
-                            Some((secret1.clone(), [1; 16])),
-                            Some((secret2.clone(), [2; 16])),
-                        ],
-                        false,
-                        {
-                            let ready = ready.clone();
+        let clock = Shared::new(Lock::new(Duration::default()));
 
-                            move |state, secrets| {
-                                (ready.try_borrow_mut().unwrap())(None, state, secrets)
-                            }
-                        },
-                        move |message| Ok(crate::crypto::sign(message, &keys[0])),
-                        {
-                            let queue = queues[0].clone();
-
-                            move |diff| queue.try_borrow_mut().unwrap().push_back(diff.serialize())
-                        },
-                        {
-                            let log = log.clone();
-
-                            move |target, event| {
-                                (log.try_borrow_mut().unwrap())(None, target, event)
-                            }
-                        },
-                        random0,
-                        no_version_check,
-                    )?;
-
-                    store.flush()?;
-
-                    store
-                },
-                {
-                    let mut store = crate::store::Store::new(
-                        Some(0),
+        let stores = {
+            let ready = Shared::new(Lock::new(ready));
+            let log = Shared::new(Lock::new(log));
+
+            let signers = core::iter::once(keys[0].clone()).chain(subkeys);
+
+            randoms
+                .zip(signers)
+                .enumerate()
+                .map(|(i, (random, signer))| {
+                    let player = match i {
+                        0 => None,
+                        i => Some((i - 1).try_into().unwrap()),
+                    };
+
+                    let mut store = crate::store::Store::with_callbacks(
+                        player,
                         &root,
-                        [Some((secret1, [1; 16])), None],
+                        secrets
+                            .iter()
+                            .enumerate()
+                            .map(|(j, secret)| {
+                                if player.is_none() || player.map(usize::from) == Some(j) {
+                                    Some((secret.clone(), [seed(j); 16]))
+                                } else {
+                                    None
+                                }
+                            })
+                            .collect(),
                         false,
-                        {
-                            let ready = ready.clone();
-
-                            move |state, secrets| {
-                                (ready.try_borrow_mut().unwrap())(Some(0), state, secrets)
-                            }
+                        crate::store::Callbacks {
+                            ready: Box::new({
+                                let ready = ready.clone();
+
+                                move |state, secrets| {
+                                    (ready.try_borrow_mut().unwrap())(player, state, secrets)
+                                }
+                            }),
+                            sign: Box::new(move |message| {
+                                S::Scheme::sign(message, &signer).map_err(|error| error.to_string())
+                            }),
+                            send: Box::new({
+                                let queue = queues[i].clone();
+                                let format =
+                                    crate::Format::SUPPORTED[i % crate::Format::SUPPORTED.len()];
+
+                                move |diff| {
+                                    queue
+                                        .try_borrow_mut()
+                                        .unwrap()
+                                        .push_back(diff.serialize_with_format(format))
+                                }
+                            }),
+                            log: Box::new({
+                                let log = log.clone();
+
+                                move |target, event| {
+                                    (log.try_borrow_mut().unwrap())(player, target, event)
+                                }
+                            }),
+                            random: Box::new(random),
                         },
-                        move |message| Ok(crate::crypto::sign(message, &subkey1)),
-                        {
-                            let queue = queues[1].clone();
-
-                            move |diff| queue.try_borrow_mut().unwrap().push_back(diff.serialize())
-                        },
-                        {
-                            let log = log.clone();
-
-                            move |target, event| {
-                                (log.try_borrow_mut().unwrap())(Some(0), target, event)
-                            }
-                        },
-                        random1,
                         no_version_check,
+                        Default::default(),
                     )?;
 
-                    store.flush()?;
+                    store.set_clock({
+                        let clock = clock.clone();
 
-                    store
-                },
-                {
-                    let mut store = crate::store::Store::new(
-                        Some(1),
-                        &root,
-                        [None, Some((secret2, [2; 16]))],
-                        false,
-                        move |state, secrets| {
-                            (ready.try_borrow_mut().unwrap())(Some(1), state, secrets)
-                        },
-                        move |message| Ok(crate::crypto::sign(message, &subkey2)),
-                        {
-                            let queue = queues[2].clone();
-
-                            move |diff| queue.try_borrow_mut().unwrap().push_back(diff.serialize())
-                        },
-                        move |target, event| {
-                            (log.try_borrow_mut().unwrap())(Some(1), target, event)
-                        },
-                        random2,
-                        no_version_check,
-                    )?;
+                        move || *clock.try_borrow().unwrap()
+                    });
 
                     store.flush()?;
 
-                    store
-                },
-            ]
+                    Ok(store)
+                })
+                .collect::<Result<_, crate::Error>>()?
         };
 
         let mut tester = Self {
@@ -295,6 +227,8 @@ where
             stores,
             queues,
             no_version_check,
+            limits: Default::default(),
+            clock,
         };
 
         tester.flush()?;
@@ -318,6 +252,58 @@ where
             .unwrap()
     }
 
+    /// Sets the limits for binary representations decoded by the tester and its stores.
+    pub fn set_decode_limits(&mut self, limits: crate::DecodeLimits) {
+        self.limits = limits;
+
+        for store in &mut self.stores {
+            store.set_decode_limits(limits);
+        }
+    }
+
+    /// Gets the time of the tester's fake clock.
+    ///
+    /// The clock starts at zero, and only moves with [Tester::advance].
+    pub fn now(&self) -> Duration {
+        *self.clock.try_borrow().unwrap()
+    }
+
+    /// Sets the time allowed to the pending player in each phase for the tester's stores.
+    ///
+    /// See [crate::store::Store::set_deadlines].
+    pub fn set_deadlines(&mut self, deadlines: crate::store::Deadlines) {
+        for store in &mut self.stores {
+            store.set_deadlines(deadlines);
+        }
+    }
+
+    /// Advances the tester's fake clock, dispatching any timeouts that fall due.
+    ///
+    /// Returns a [Vec] of actions that were automatically dispatched as a result.
+    pub fn advance(
+        &mut self,
+        duration: Duration,
+    ) -> Result<Vec<crate::ProofAction<crate::store::StoreState<S>>>, crate::Error> {
+        let now = self.now() + duration;
+
+        *self.clock.try_borrow_mut().unwrap() = now;
+
+        for store in &mut self.stores {
+            store.poll_deadlines(now)?;
+        }
+
+        let actions = self.flush()?;
+
+        self.check().map(|_| actions)
+    }
+
+    /// Gets the time by which the pending player must act, if any.
+    ///
+    /// See [crate::store::Store::deadline].
+    pub fn deadline(&self) -> Option<Duration> {
+        self.stores[0].deadline()
+    }
+
     /// Applies an action by a given player (or the owner) to the tester.
     ///
     /// Returns a [Vec] of actions that were automatically dispatched as a result.
@@ -325,7 +311,7 @@ where
         &mut self,
         player: Option<crate::Player>,
         action: &S::Action,
-    ) -> Result<Vec<crate::ProofAction<crate::store::StoreState<S>>>, String> {
+    ) -> Result<Vec<crate::ProofAction<crate::store::StoreState<S>>>, crate::Error> {
         let diff = self.stores[if let Some(player) = player {
             1 + usize::from(player)
         } else {
@@ -347,153 +333,119 @@ where
         self.check().map(|_| reveals)
     }
 
-    fn check(&self) -> Result<(), String> {
-        crate::forbid!({
-            let data = self.proof.root.serialize();
-
-            deserialize_root_proof::<S>(&data, self.no_version_check)?.serialize() != data
-        });
-
-        crate::forbid!({
-            let data = self.proof.serialize();
-
-            deserialize_proof(&data, self.proof.root.clone(), self.no_version_check)?.serialize()
-                != data
-        });
+    fn check(&self) -> Result<(), crate::Error> {
+        for &format in &crate::Format::SUPPORTED {
+            crate::forbid!({
+                let data = self.proof.root.serialize_with_format(format);
 
-        for store in &self.stores {
-            crate::forbid!(store.proof.serialize() != self.proof.serialize());
+                deserialize_root_proof::<S>(&data, self.no_version_check, &self.limits)?
+                    .serialize_with_format(format)
+                    != data
+            });
 
             crate::forbid!({
-                let data = store.serialize(SecretKnowledge::Both);
-
-                deserialize_store::<S>(&data, self.no_version_check)?
-                    .serialize(SecretKnowledge::Both)
+                let data = self.proof.serialize_with_format(format);
+
+                deserialize_proof(
+                    &data,
+                    self.proof.root.clone(),
+                    self.no_version_check,
+                    &self.limits,
+                )?
+                .serialize_with_format(format)
                     != data
             });
         }
 
-        crate::forbid!(
-            self.stores[1].serialize(SecretKnowledge::Both)
-                != self.stores[1].serialize(SecretKnowledge::Some(0))
-        );
-        crate::forbid!(
-            self.stores[2].serialize(SecretKnowledge::Both)
-                != self.stores[2].serialize(SecretKnowledge::Some(1))
-        );
-        crate::forbid!(
-            self.stores[0].serialize(SecretKnowledge::Some(0))
-                != self.stores[1].serialize(SecretKnowledge::Some(0))
-        );
-        crate::forbid!(
-            self.stores[0].serialize(SecretKnowledge::Some(1))
-                != self.stores[2].serialize(SecretKnowledge::Some(1))
-        );
-
-        crate::forbid!(self.stores[0].state().state().secret(0).is_none());
-        crate::forbid!(self.stores[0].state().state().secret(1).is_none());
-        crate::forbid!(self.stores[1].state().state().secret(0).is_none());
-        crate::forbid!(self.stores[1].state().state().secret(1).is_some());
-        crate::forbid!(self.stores[2].state().state().secret(0).is_some());
-        crate::forbid!(self.stores[2].state().state().secret(1).is_none());
-
-        let store = deserialize_store::<S>(
-            &self.stores[0].serialize(SecretKnowledge::Some(0)),
-            self.no_version_check,
-        )?;
-        crate::forbid!(store.state().state().secret(0).is_none());
-        crate::forbid!(store.state().state().secret(1).is_some());
-
-        let store = deserialize_store::<S>(
-            &self.stores[0].serialize(SecretKnowledge::Some(1)),
-            self.no_version_check,
-        )?;
-        crate::forbid!(store.state().state().secret(0).is_some());
-        crate::forbid!(store.state().state().secret(1).is_none());
+        for store in &self.stores {
+            crate::forbid!(store.proof.serialize() != self.proof.serialize());
 
-        let store = deserialize_store::<S>(
-            &self.stores[1].serialize(SecretKnowledge::Some(0)),
-            self.no_version_check,
-        )?;
-        crate::forbid!(store.state().state().secret(0).is_none());
-        crate::forbid!(store.state().state().secret(1).is_some());
+            for &format in &crate::Format::SUPPORTED {
+                crate::forbid!({
+                    let data = store.serialize_with_format(SecretKnowledge::All, format);
 
-        let store = deserialize_store::<S>(
-            &self.stores[1].serialize(SecretKnowledge::Some(1)),
-            self.no_version_check,
-        )?;
-        crate::forbid!(store.state().state().secret(0).is_some());
-        crate::forbid!(store.state().state().secret(1).is_some());
+                    deserialize_store::<S>(&data, self.no_version_check, self.limits)?
+                        .serialize_with_format(SecretKnowledge::All, format)
+                        != data
+                });
+            }
+        }
 
-        let store = deserialize_store::<S>(
-            &self.stores[2].serialize(SecretKnowledge::Some(0)),
-            self.no_version_check,
-        )?;
-        crate::forbid!(store.state().state().secret(0).is_some());
-        crate::forbid!(store.state().state().secret(1).is_some());
+        for (i, store) in self.stores[1..].iter().enumerate() {
+            let player = i.try_into().unwrap();
+
+            crate::forbid!(
+                store.serialize(SecretKnowledge::All)
+                    != store.serialize(SecretKnowledge::Some(player))
+            );
+            crate::forbid!(
+                self.stores[0].serialize(SecretKnowledge::Some(player))
+                    != store.serialize(SecretKnowledge::Some(player))
+            );
+        }
 
-        let store = deserialize_store::<S>(
-            &self.stores[2].serialize(SecretKnowledge::Some(1)),
-            self.no_version_check,
-        )?;
-        crate::forbid!(store.state().state().secret(0).is_some());
-        crate::forbid!(store.state().state().secret(1).is_none());
+        for (i, store) in self.stores.iter().enumerate() {
+            let knows = |player: usize| i == 0 || i == 1 + player;
+
+            for j in 0..self.stores.len() - 1 {
+                let player = j.try_into().unwrap();
+
+                crate::forbid!(store.state().state().secret(player).is_some() != knows(j));
+
+                let store = deserialize_store::<S>(
+                    &store.serialize(SecretKnowledge::Some(player)),
+                    self.no_version_check,
+                    self.limits,
+                )?;
+
+                for k in 0..self.stores.len() - 1 {
+                    crate::forbid!(
+                        store
+                            .state()
+                            .state()
+                            .secret(k.try_into().unwrap())
+                            .is_some()
+                            != (k == j && knows(j))
+                    );
+                }
+            }
+        }
 
         Ok(())
     }
 
-    fn flush(&mut self) -> Result<Vec<crate::ProofAction<crate::store::StoreState<S>>>, String> {
+    fn flush(
+        &mut self,
+    ) -> Result<Vec<crate::ProofAction<crate::store::StoreState<S>>>, crate::Error> {
         let mut reveals = Vec::new();
 
         loop {
             let mut repeat = false;
 
-            if let Some(diff) = self.queues[0]
-                .try_borrow_mut()
-                .map_err(|error| error.to_string())?
-                .pop_front()
-            {
-                let diff = crate::Diff::deserialize(&diff)?;
-
-                reveals.extend(diff.actions.clone());
-
-                self.proof.apply(&diff)?;
-                self.stores[1].apply(&diff)?;
-                self.stores[2].apply(&diff)?;
-
-                repeat = true;
-            }
-
-            if let Some(diff) = self.queues[1]
-                .try_borrow_mut()
-                .map_err(|error| error.to_string())?
-                .pop_front()
-            {
-                let diff = crate::Diff::deserialize(&diff)?;
-
-                reveals.extend(diff.actions.clone());
-
-                self.proof.apply(&diff)?;
-                self.stores[0].apply(&diff)?;
-                self.stores[2].apply(&diff)?;
-
-                repeat = true;
-            }
+            for i in 0..self.queues.len() {
+                if let Some(diff) = self.queues[i]
+                    .try_borrow_mut()
+                    .map_err(|error| crate::forbidden!(error.to_string()))?
+                    .pop_front()
+                {
+                    let diff = crate::Diff::deserialize_with_limits(
+                        &diff,
+                        self.proof.state().id(),
+                        &self.limits,
+                    )?;
 
-            if let Some(diff) = self.queues[2]
-                .try_borrow_mut()
-                .map_err(|error| error.to_string())?
-                .pop_front()
-            {
-                let diff = crate::Diff::deserialize(&diff)?;
+                    reveals.extend(diff.actions.clone());
 
-                reveals.extend(diff.actions.clone());
+                    self.proof.apply(&diff)?;
 
-                self.proof.apply(&diff)?;
-                self.stores[0].apply(&diff)?;
-                self.stores[1].apply(&diff)?;
+                    for (j, store) in self.stores.iter_mut().enumerate() {
+                        if j != i {
+                            store.apply(&diff)?;
+                        }
+                    }
 
-                repeat = true;
+                    repeat = true;
+                }
             }
 
             if !repeat {
@@ -505,94 +457,56 @@ where
     }
 }
 
-#[cfg(not(feature = "no-crypto"))]
-#[allow(clippy::unnecessary_wraps)]
-fn generate_keys_and_subkeys<R: libsecp256k1_rand::RngCore>(
-    randoms: &mut [R; 3],
-) -> Result<([crate::crypto::SecretKey; 3], [crate::crypto::SecretKey; 2]), String> {
-    Ok((
-        [
-            crate::crypto::SecretKey::random(&mut randoms[0]),
-            crate::crypto::SecretKey::random(&mut randoms[1]),
-            crate::crypto::SecretKey::random(&mut randoms[2]),
-        ],
-        [
-            crate::crypto::SecretKey::random(&mut randoms[1]),
-            crate::crypto::SecretKey::random(&mut randoms[2]),
-        ],
-    ))
+fn seed(i: usize) -> u8 {
+    (1 + i).try_into().unwrap()
 }
 
-#[cfg(feature = "no-crypto")]
-fn generate_keys_and_subkeys<R: libsecp256k1_rand::RngCore>(
-    randoms: &mut [R; 3],
-) -> Result<([crate::crypto::SecretKey; 3], [crate::crypto::SecretKey; 2]), String> {
-    Ok((
-        [
-            {
-                let mut key = crate::crypto::SecretKey::default();
-
-                randoms[0]
-                    .try_fill_bytes(&mut key)
-                    .map_err(|error| error.to_string())?;
-
-                key
-            },
-            {
-                let mut key = crate::crypto::SecretKey::default();
-
-                randoms[1]
-                    .try_fill_bytes(&mut key)
-                    .map_err(|error| error.to_string())?;
-
-                key
-            },
-            {
-                let mut key = crate::crypto::SecretKey::default();
-
-                randoms[2]
-                    .try_fill_bytes(&mut key)
-                    .map_err(|error| error.to_string())?;
-
-                key
-            },
-        ],
-        [
-            {
-                let mut subkey = crate::crypto::SecretKey::default();
-
-                randoms[1]
-                    .try_fill_bytes(&mut subkey)
-                    .map_err(|error| error.to_string())?;
-
-                subkey
-            },
-            {
-                let mut subkey = crate::crypto::SecretKey::default();
-
-                randoms[2]
-                    .try_fill_bytes(&mut subkey)
-                    .map_err(|error| error.to_string())?;
-
-                subkey
-            },
-        ],
-    ))
+#[allow(clippy::type_complexity)]
+fn generate_keys_and_subkeys<C: SignatureScheme, R: libsecp256k1_rand::RngCore>(
+    randoms: &mut [R],
+) -> Result<(Vec<C::SecretKey>, Vec<C::SecretKey>), crate::Error> {
+    let random = |random: &mut R| loop {
+        let mut seed = [0; 32];
+
+        random
+            .try_fill_bytes(&mut seed)
+            .map_err(|error| crate::forbidden!(error.to_string()))?;
+
+        if let Ok(key) = C::secret_key(&seed) {
+            return Ok(key);
+        }
+    };
+
+    let keys = randoms
+        .iter_mut()
+        .map(random)
+        .collect::<Result<_, crate::Error>>()?;
+
+    let subkeys = randoms[1..]
+        .iter_mut()
+        .map(random)
+        .collect::<Result<_, crate::Error>>()?;
+
+    Ok((keys, subkeys))
 }
 
 fn deserialize_store<S: crate::store::State>(
     data: &[u8],
     no_version_check: bool,
-) -> Result<crate::store::Store<S>, String> {
-    let mut store = crate::store::Store::deserialize(
+    limits: crate::DecodeLimits,
+) -> Result<crate::store::Store<S>, crate::Error> {
+    let mut store = crate::store::Store::deserialize_with_callbacks(
         data,
         false,
-        |_, _| (),
-        |_| unreachable!("{}:{}:{}", file!(), line!(), column!()),
-        |_| (),
-        |_, _| (),
-        UnreachableRng,
+        crate::store::Callbacks {
+            ready: Box::new(|_, _| ()),
+            sign: Box::new(|_| unreachable!("{}:{}:{}", file!(), line!(), column!())),
+            send: Box::new(|_| ()),
+            log: Box::new(|_, _| ()),
+            random: Box::new(UnreachableRng),
+        },
         no_version_check,
+        limits,
     )?;
 
     store.flush()?;
@@ -604,10 +518,11 @@ fn deserialize_proof<S: crate::store::State>(
     data: &[u8],
     root: crate::RootProof<crate::store::StoreState<S>>,
     no_version_check: bool,
-) -> Result<crate::Proof<crate::store::StoreState<S>>, String> {
+    limits: &crate::DecodeLimits,
+) -> Result<crate::Proof<crate::store::StoreState<S>>, crate::Error> {
     let mut proof = crate::Proof::new(root);
 
-    proof.deserialize(data, no_version_check)?;
+    proof.deserialize_with_limits(data, no_version_check, limits)?;
 
     Ok(proof)
 }
@@ -615,8 +530,9 @@ fn deserialize_proof<S: crate::store::State>(
 fn deserialize_root_proof<S: crate::store::State>(
     data: &[u8],
     no_version_check: bool,
-) -> Result<crate::RootProof<crate::store::StoreState<S>>, String> {
-    crate::RootProof::deserialize(data, no_version_check)
+    limits: &crate::DecodeLimits,
+) -> Result<crate::RootProof<crate::store::StoreState<S>>, crate::Error> {
+    crate::RootProof::deserialize_with_limits(data, no_version_check, limits)
 }
 
 struct UnreachableRng;
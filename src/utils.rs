@@ -20,7 +20,7 @@ use {
     alloc::{format, string::String, vec::Vec},
     core::{
         convert::TryInto,
-        fmt::{Error, Formatter},
+        fmt::{self, Formatter},
         mem::size_of,
     },
 };
@@ -66,8 +66,10 @@ pub fn hex(data: &[u8]) -> String {
 ///     Ok(b"quod erat demonstrandum".to_vec()),
 /// );
 /// ```
-pub fn unhex(mut hex: &str) -> Result<Vec<u8>, String> {
-    crate::forbid!(hex.len() % 2 != 0);
+pub fn unhex(mut hex: &str) -> Result<Vec<u8>, crate::Error> {
+    if hex.len() % 2 != 0 {
+        return Err(crate::Error::decode("hex.len() % 2 != 0"));
+    }
 
     if hex.starts_with("0x") || hex.starts_with("0X") {
         hex = &hex["0x".len()..];
@@ -90,7 +92,7 @@ pub fn unhex(mut hex: &str) -> Result<Vec<u8>, String> {
         b'D' | b'd' => Ok(13),
         b'E' | b'e' => Ok(14),
         b'F' | b'f' => Ok(15),
-        byte => Err(format!("byte = {}", byte)),
+        byte => Err(crate::Error::decode(format!("byte = {}", byte))),
     };
 
     let mut data = Vec::with_capacity(hex.len() / 2);
@@ -116,35 +118,76 @@ pub fn to_js<T: serde::Serialize + ?Sized>(value: &T) -> Result<wasm_bindgen::Js
     serde_wasm_bindgen::to_value(&value).map_err(|error| error.to_string())
 }
 
-pub(crate) fn fmt_hex(data: &impl AsRef<[u8]>, f: &mut Formatter<'_>) -> Result<(), Error> {
+pub(crate) fn fmt_hex(data: &impl AsRef<[u8]>, f: &mut Formatter<'_>) -> fmt::Result {
     write!(f, "{}", hex(data.as_ref()))
 }
 
-pub(crate) fn read_u32_usize(data: &mut &[u8]) -> Result<usize, String> {
+pub(crate) fn fmt_hexes(data: &[impl AsRef<[u8]>], f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_list()
+        .entries(data.iter().map(|data| hex(data.as_ref())))
+        .finish()
+}
+
+pub(crate) fn read_u32_usize(data: &mut &[u8]) -> Result<usize, crate::Error> {
     crate::forbid!(data.len() < size_of::<u32>());
 
     let value = u32::from_le_bytes(
         data[..size_of::<u32>()]
             .try_into()
-            .map_err(|error| format!("{}", error))?,
+            .map_err(crate::Error::decode)?,
     )
     .try_into()
-    .map_err(|error| format!("{}", error))?;
+    .map_err(crate::Error::decode)?;
 
     *data = &data[size_of::<u32>()..];
 
     Ok(value)
 }
 
-pub(crate) fn write_u32_usize(data: &mut Vec<u8>, value: usize) -> Result<(), String> {
-    let value: u32 = value.try_into().map_err(|error| format!("{}", error))?;
+pub(crate) fn write_u32_usize(data: &mut Vec<u8>, value: usize) -> Result<(), crate::Error> {
+    let value: u32 = value
+        .try_into()
+        .map_err(|_| crate::Error::encode(format!("{} > u32::MAX", value)))?;
 
     data.extend(&value.to_le_bytes());
 
     Ok(())
 }
 
-pub(crate) fn read_u8(data: &mut &[u8]) -> Result<u8, String> {
+pub(crate) fn read_varint_usize(data: &mut &[u8]) -> Result<usize, crate::Error> {
+    let mut value: u32 = 0;
+
+    for i in 0..5 {
+        let byte = read_u8(data)?;
+
+        crate::forbid!(i == 4 && byte > 0x0f);
+
+        value |= u32::from(byte & 0x7f) << (7 * i);
+
+        if byte & 0x80 == 0 {
+            return value.try_into().map_err(crate::Error::decode);
+        }
+    }
+
+    Err(crate::forbidden!("varint longer than 5 bytes"))
+}
+
+pub(crate) fn write_varint_usize(data: &mut Vec<u8>, value: usize) -> Result<(), crate::Error> {
+    let mut value: u32 = value
+        .try_into()
+        .map_err(|_| crate::Error::encode(format!("{} > u32::MAX", value)))?;
+
+    while value >= 0x80 {
+        write_u8(data, (value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+
+    write_u8(data, value as u8);
+
+    Ok(())
+}
+
+pub(crate) fn read_u8(data: &mut &[u8]) -> Result<u8, crate::Error> {
     crate::forbid!(data.is_empty());
 
     let byte = data[0];
@@ -157,7 +200,7 @@ pub(crate) fn write_u8(data: &mut Vec<u8>, value: u8) {
     data.push(value);
 }
 
-pub(crate) fn read_u8_bool(data: &mut &[u8]) -> Result<bool, String> {
+pub(crate) fn read_u8_bool(data: &mut &[u8]) -> Result<bool, crate::Error> {
     crate::forbid!(data.is_empty());
 
     let byte = data[0];
@@ -171,14 +214,11 @@ pub(crate) fn write_u8_bool(data: &mut Vec<u8>, value: bool) {
     write_u8(data, value.into());
 }
 
-/// Set Option::None in an array at positions where a boolean array is false.
-pub(crate) fn keep_by_array<T, const N: usize>(
-    mut opts_arr: [Option<T>; N],
-    keep_arr: [bool; N],
-) -> [Option<T>; N] {
-    for i in 0..opts_arr.len() {
-        if !keep_arr[i] {
-            opts_arr[i] = None
+/// Set Option::None in a vector at positions where a boolean slice is false or absent.
+pub(crate) fn keep_by_array<T>(mut opts_arr: Vec<Option<T>>, keep_arr: &[bool]) -> Vec<Option<T>> {
+    for (i, opt) in opts_arr.iter_mut().enumerate() {
+        if !keep_arr.get(i).copied().unwrap_or(false) {
+            *opt = None
         }
     }
     opts_arr
@@ -187,24 +227,28 @@ pub(crate) fn keep_by_array<T, const N: usize>(
 #[rustfmt::skip]
 #[test]
 fn test_keep_by_array() {
-    assert_eq!(keep_by_array::<u8, 2>([None, None],       [true, true]),   [None, None]);
-    assert_eq!(keep_by_array::<u8, 2>([None, None],       [true, false]),  [None, None]);
-    assert_eq!(keep_by_array::<u8, 2>([None, None],       [false, true]),  [None, None]);
-    assert_eq!(keep_by_array::<u8, 2>([None, None],       [false, false]), [None, None]);
-
-    assert_eq!(keep_by_array::<u8, 2>([None, Some(1)],    [true, true]),   [None, Some(1)]);
-    assert_eq!(keep_by_array::<u8, 2>([None, Some(1)],    [true, false]),  [None, None]);
-    assert_eq!(keep_by_array::<u8, 2>([None, Some(1)],    [false, true]),  [None, Some(1)]);
-    assert_eq!(keep_by_array::<u8, 2>([None, Some(1)],    [false, false]), [None, None]);
-
-
-    assert_eq!(keep_by_array::<u8, 2>([Some(1), None],    [true, true]),   [Some(1), None]);
-    assert_eq!(keep_by_array::<u8, 2>([Some(1), None],    [true, false]),  [Some(1), None]);
-    assert_eq!(keep_by_array::<u8, 2>([Some(1), None],    [false, true]),  [None, None]);
-    assert_eq!(keep_by_array::<u8, 2>([Some(1), None],    [false, false]), [None, None]);
-
-    assert_eq!(keep_by_array::<u8, 2>([Some(1), Some(2)], [true, true]),   [Some(1), Some(2)]);
-    assert_eq!(keep_by_array::<u8, 2>([Some(1), Some(2)], [true, false]),  [Some(1), None]);
-    assert_eq!(keep_by_array::<u8, 2>([Some(1), Some(2)], [false, true]),  [None, Some(2)]);
-    assert_eq!(keep_by_array::<u8, 2>([Some(1), Some(2)], [false, false]), [None, None]);
+    use alloc::vec;
+
+    assert_eq!(keep_by_array::<u8>(vec![None, None],       &[true, true]),   [None, None]);
+    assert_eq!(keep_by_array::<u8>(vec![None, None],       &[true, false]),  [None, None]);
+    assert_eq!(keep_by_array::<u8>(vec![None, None],       &[false, true]),  [None, None]);
+    assert_eq!(keep_by_array::<u8>(vec![None, None],       &[false, false]), [None, None]);
+
+    assert_eq!(keep_by_array::<u8>(vec![None, Some(1)],    &[true, true]),   [None, Some(1)]);
+    assert_eq!(keep_by_array::<u8>(vec![None, Some(1)],    &[true, false]),  [None, None]);
+    assert_eq!(keep_by_array::<u8>(vec![None, Some(1)],    &[false, true]),  [None, Some(1)]);
+    assert_eq!(keep_by_array::<u8>(vec![None, Some(1)],    &[false, false]), [None, None]);
+
+
+    assert_eq!(keep_by_array::<u8>(vec![Some(1), None],    &[true, true]),   [Some(1), None]);
+    assert_eq!(keep_by_array::<u8>(vec![Some(1), None],    &[true, false]),  [Some(1), None]);
+    assert_eq!(keep_by_array::<u8>(vec![Some(1), None],    &[false, true]),  [None, None]);
+    assert_eq!(keep_by_array::<u8>(vec![Some(1), None],    &[false, false]), [None, None]);
+
+    assert_eq!(keep_by_array::<u8>(vec![Some(1), Some(2)], &[true, true]),   [Some(1), Some(2)]);
+    assert_eq!(keep_by_array::<u8>(vec![Some(1), Some(2)], &[true, false]),  [Some(1), None]);
+    assert_eq!(keep_by_array::<u8>(vec![Some(1), Some(2)], &[false, true]),  [None, Some(2)]);
+    assert_eq!(keep_by_array::<u8>(vec![Some(1), Some(2)], &[false, false]), [None, None]);
+
+    assert_eq!(keep_by_array::<u8>(vec![Some(1), Some(2), Some(3)], &[true]), [Some(1), None, None]);
 }
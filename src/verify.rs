@@ -0,0 +1,148 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Standalone proof verification
+
+use {
+    crate::{
+        crypto, crypto::SignatureScheme, DecodeLimits, Error, Player, Proof, ProofState, RootProof,
+        State,
+    },
+    alloc::vec::Vec,
+    core::{convert::TryInto, ops::Range},
+};
+
+/// Validates a proof against its root, without constructing a [Store](crate::store::Store).
+///
+/// `root` must have been constructed using [RootProof::serialize], and `proof` using
+/// [Proof::serialize] on a proof with the same root.
+/// Checks all signatures and ranges, and replays all actions.
+///
+/// For [store](crate::store) games, use [StoreState](crate::store::StoreState) as `S`.
+pub fn verify<S: State>(
+    root: &[u8],
+    proof: &[u8],
+    no_version_check: bool,
+) -> Result<Verification<S>, Error> {
+    verify_with_limits(root, proof, no_version_check, &Default::default())
+}
+
+/// Validates a proof against its root, within the given limits.
+///
+/// See [verify].
+pub fn verify_with_limits<S: State>(
+    root: &[u8],
+    proof: &[u8],
+    no_version_check: bool,
+    limits: &DecodeLimits,
+) -> Result<Verification<S>, Error> {
+    let root = RootProof::<S>::deserialize_with_limits(root, no_version_check, limits)?;
+
+    let mut latest = Proof::new(root);
+
+    latest.deserialize_with_limits(proof, no_version_check, limits)?;
+
+    let attestations = latest
+        .proofs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, proof)| proof.as_ref().map(|proof| (i, proof)))
+        .map(|(i, proof)| {
+            Ok(Attestation {
+                player: match i {
+                    0 => None,
+                    i => Some((i - 1).try_into().map_err(Error::decode)?),
+                },
+                signer: S::Scheme::recover(
+                    &proof.message(&latest.actions),
+                    proof.signature.as_ref(),
+                )?,
+                actions: proof.range.clone(),
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(Verification {
+        hash: latest.hash,
+        action_count: latest.actions.len(),
+        state: latest.state,
+        attestations,
+    })
+}
+
+/// Result of [verify]
+pub struct Verification<S: State> {
+    hash: crypto::Hash,
+    action_count: usize,
+    state: ProofState<S>,
+    attestations: Vec<Attestation<S>>,
+}
+
+impl<S: State> Verification<S> {
+    /// Gets the digest of the verified proof.
+    pub fn hash(&self) -> &crypto::Hash {
+        &self.hash
+    }
+
+    /// Gets the number of actions in the verified proof.
+    pub fn action_count(&self) -> usize {
+        self.action_count
+    }
+
+    /// Gets the final state of the verified proof.
+    pub fn state(&self) -> &ProofState<S> {
+        &self.state
+    }
+
+    /// Gets the attestations in the verified proof, owner first.
+    pub fn attestations(&self) -> &[Attestation<S>] {
+        &self.attestations
+    }
+}
+
+/// Signed attestation to a state and a range of the actions following it
+#[derive(derivative::Derivative)]
+#[derivative(
+    Clone(bound = ""),
+    PartialEq(bound = ""),
+    Eq(bound = ""),
+    Debug(bound = "")
+)]
+pub struct Attestation<S: State> {
+    player: Option<Player>,
+    #[derivative(Debug(format_with = "crate::crypto::fmt_address"))]
+    signer: crypto::StateAddress<S>,
+    actions: Range<usize>,
+}
+
+impl<S: State> Attestation<S> {
+    /// Gets the attesting player, or [None] for the owner.
+    pub fn player(&self) -> Option<Player> {
+        self.player
+    }
+
+    /// Gets the address of the key that signed the attestation.
+    ///
+    /// This may be a subkey of the player's address.
+    pub fn signer(&self) -> &crypto::StateAddress<S> {
+        &self.signer
+    }
+
+    /// Gets the range of actions in the verified proof covered by the attestation.
+    pub fn actions(&self) -> &Range<usize> {
+        &self.actions
+    }
+}
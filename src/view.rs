@@ -0,0 +1,253 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Borrowed views over binary representations
+
+use {
+    crate::{
+        crypto,
+        format::{self, Reader, Writer},
+        DecodeLimits, Diff, Error, Format, Player, ProofAction, State,
+    },
+    alloc::{borrow::Cow, vec::Vec},
+    core::{convert::TryInto, marker::PhantomData, mem::size_of},
+    crypto::SignatureScheme,
+};
+
+/// Borrowed view of a [Diff]
+///
+/// Only the framing is checked on construction.
+/// Actions are parsed lazily, and the author is only recovered on demand.
+/// Views over [Format::V2] diffs borrow the compact encoding as is.
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct DiffRef<'a, S: State> {
+    proof: &'a crypto::Hash,
+    #[derivative(Debug(format_with = "crate::utils::fmt_hex"))]
+    actions: &'a [u8],
+    length: usize,
+    format: Format,
+    #[derivative(Debug(format_with = "crate::utils::fmt_hex"))]
+    proof_signature: &'a [u8],
+    #[derivative(Debug(format_with = "crate::utils::fmt_hex"))]
+    signature: &'a [u8],
+    #[derivative(Debug = "ignore")]
+    phantom: PhantomData<fn() -> S>,
+}
+
+impl<S: State> Clone for DiffRef<'_, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S: State> Copy for DiffRef<'_, S> {}
+
+impl<'a, S: State> DiffRef<'a, S> {
+    /// Constructs a view over a diff's binary representation.
+    ///
+    /// `data` must have been constructed using [Diff::serialize] or [Diff::serialize_with_format].
+    pub fn new(data: &'a [u8]) -> Result<Self, Error> {
+        Self::new_with_limits(data, &Default::default())
+    }
+
+    /// Constructs a view over a diff's binary representation, within the given limits.
+    ///
+    /// See [DiffRef::new].
+    pub fn new_with_limits(data: &'a [u8], limits: &DecodeLimits) -> Result<Self, Error> {
+        Self::new_unchecked(data, limits).map_err(Error::decoding)
+    }
+
+    fn new_unchecked(data: &'a [u8], limits: &DecodeLimits) -> Result<Self, Error> {
+        // a canonical diff can begin with the tag by chance
+
+        if let Some(body) = format::strip_tag(data) {
+            if let Ok(diff) = Self::parse(body, Format::V2, limits) {
+                return Ok(diff);
+            }
+        }
+
+        Self::parse(data, Format::V1, limits)
+    }
+
+    fn parse(data: &'a [u8], format: Format, limits: &DecodeLimits) -> Result<Self, Error> {
+        crate::forbid!(
+            data.len()
+                < size_of::<crypto::Hash>()
+                    + 1
+                    + S::Scheme::SIGNATURE_SIZE
+                    + S::Scheme::SIGNATURE_SIZE
+        );
+
+        let (proof, data) = data.split_at(size_of::<crypto::Hash>());
+        let (actions, signatures) =
+            data.split_at(data.len() - S::Scheme::SIGNATURE_SIZE - S::Scheme::SIGNATURE_SIZE);
+
+        let mut reader = Reader::new(actions, format);
+
+        let length = reader.length()?;
+
+        crate::forbid!(length > limits.max_actions);
+
+        for _ in 0..length {
+            reader.action()?;
+        }
+
+        crate::forbid!(!reader.data.is_empty());
+
+        let (proof_signature, signature) = signatures.split_at(S::Scheme::SIGNATURE_SIZE);
+
+        Ok(Self {
+            proof: proof.try_into().map_err(Error::decode)?,
+            actions,
+            length,
+            format,
+            proof_signature,
+            signature,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Gets the hash of the proof the diff was constructed on.
+    pub fn proof(&self) -> &'a crypto::Hash {
+        self.proof
+    }
+
+    /// Gets the number of actions in the diff.
+    pub fn action_count(&self) -> usize {
+        self.length
+    }
+
+    /// Gets views of the actions in the diff.
+    pub fn actions(&self) -> impl Iterator<Item = ProofActionRef<'a, S>> + 'a
+    where
+        S: 'a,
+    {
+        let mut reader = Reader::new(self.actions, self.format);
+
+        reader.length().unwrap();
+
+        (0..self.length).map(move |_| {
+            let (player, kind, payload) = reader.action().unwrap();
+
+            ProofActionRef {
+                player,
+                kind,
+                payload,
+                phantom: PhantomData,
+            }
+        })
+    }
+
+    /// Gets the binary representation of the diff's proof signature.
+    pub fn proof_signature(&self) -> &'a [u8] {
+        self.proof_signature
+    }
+
+    /// Gets the binary representation of the diff's signature.
+    pub fn signature(&self) -> &'a [u8] {
+        self.signature
+    }
+
+    /// Recovers the address of the key that signed the diff for the match with the given identifier.
+    pub fn author(&self, id: &S::ID) -> Result<crypto::StateAddress<S>, Error> {
+        S::Scheme::recover(
+            &S::Scheme::encode(&crypto::Message::Diff {
+                domain: crate::domain::<S>(id),
+                proof: self.proof,
+                actions: &self.canonical_actions()?,
+                proof_signature: self.proof_signature,
+            }),
+            self.signature,
+        )
+    }
+
+    fn canonical_actions(&self) -> Result<Cow<'a, [u8]>, Error> {
+        match self.format {
+            Format::V1 => Ok(Cow::Borrowed(self.actions)),
+            Format::V2 => {
+                let mut writer = Writer::new(Format::V1, self.actions.len());
+
+                writer.length(self.length)?;
+
+                for action in self.actions() {
+                    writer.action(action.player, action.kind, action.payload)?;
+                }
+
+                Ok(Cow::Owned(writer.data))
+            }
+        }
+    }
+
+    /// Decodes the viewed diff.
+    ///
+    /// This is equivalent to [Diff::deserialize].
+    pub fn decode(&self, id: &S::ID) -> Result<Diff<S>, Error> {
+        Ok(Diff {
+            id: id.clone(),
+            proof: *self.proof,
+            actions: self
+                .actions()
+                .map(|action| action.decode())
+                .collect::<Result<Vec<_>, _>>()?,
+            proof_signature: S::Scheme::signature(self.proof_signature)?,
+            signature: S::Scheme::signature(self.signature)?,
+            author: self.author(id)?,
+        })
+    }
+}
+
+/// Borrowed view of a [ProofAction]
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct ProofActionRef<'a, S: State> {
+    player: u8,
+    kind: u8,
+    #[derivative(Debug(format_with = "crate::utils::fmt_hex"))]
+    payload: &'a [u8],
+    #[derivative(Debug = "ignore")]
+    phantom: PhantomData<fn() -> S>,
+}
+
+impl<S: State> Clone for ProofActionRef<'_, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S: State> Copy for ProofActionRef<'_, S> {}
+
+impl<'a, S: State> ProofActionRef<'a, S> {
+    /// Gets the player performing the action, or [None] if performed by the root author.
+    pub fn player(&self) -> Option<Player> {
+        self.player.checked_sub(1)
+    }
+
+    /// Checks if the action is a [PlayerAction::Play](crate::PlayerAction::Play).
+    pub fn is_play(&self) -> bool {
+        self.kind == 0
+    }
+
+    /// Gets the canonical binary representation of the action.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [&[self.player, self.kind][..], self.payload].concat()
+    }
+
+    /// Decodes the viewed action.
+    pub fn decode(&self) -> Result<ProofAction<S>, Error> {
+        ProofAction::deserialize(&self.to_bytes())
+    }
+}
@@ -28,10 +28,10 @@ use {
     },
     arcadeum::{
         crypto,
-        store::{Context, State, Tester},
+        store::{Context, State, StateFuture, Tester},
         Player,
     },
-    core::{convert::TryInto, future::Future, mem::size_of, pin::Pin},
+    core::{convert::TryInto, mem::size_of},
     rand::{RngCore, SeedableRng},
     serde::{Deserialize, Serialize},
 };
@@ -48,7 +48,7 @@ macro_rules! println {
     };
 }
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "sync")))]
 arcadeum::bind!(Battleship);
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
@@ -64,6 +64,7 @@ impl State for Battleship {
     type Action = u8;
     type Event = bool;
     type Secret = crypto::MerkleTree<bool>;
+    type Scheme = crypto::Secp256k1;
 
     fn version() -> &'static [u8] {
         "Battleship".as_bytes()
@@ -112,7 +113,7 @@ impl State for Battleship {
         player: Option<crate::Player>,
         action: &Self::Action,
         mut context: Context<Self::Secret, Self::Event>,
-    ) -> Pin<Box<dyn Future<Output = (Self, Context<Self::Secret, Self::Event>)>>> {
+    ) -> StateFuture<Self> {
         Box::pin({
             let action = *action;
 
@@ -185,7 +186,7 @@ fn test_battleship() {
 
     let mut tester = Tester::new(
         state,
-        secrets,
+        secrets.to_vec(),
         Vec::new(),
         |player, _, _| println!("[{:?}: ready]", player),
         |player, target, event| println!("[{:?} (target {:?}): log] {:?}", player, target, event),
@@ -17,22 +17,54 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 extern crate alloc;
+#[cfg(feature = "sync")]
+extern crate std;
 
 use {
     alloc::{
         boxed::Box,
         string::{String, ToString},
+        sync::Arc,
         vec,
         vec::Vec,
     },
     arcadeum::{
-        store::{Context, State, Tester},
-        Player,
+        crypto::{MerkleTree, Secp256k1, SignatureScheme},
+        store::{
+            Context, SecretKnowledge, State, StateFuture, Store, StoreAction, StoreBuilder,
+            StoreHooks, StoreState, Tester,
+        },
+        DecodeLimits, Diff, EquivocationEvidence, Error, MatchStatus, Offence, Outcome, Player,
+        PlayerAction, ProofAction, ProofState, RootProof,
     },
-    core::{future::Future, pin::Pin},
+    core::{
+        convert::TryInto,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+    rand::SeedableRng,
     serde::{Deserialize, Serialize},
 };
 
+#[cfg(not(feature = "sync"))]
+use {alloc::rc::Rc as Shared, core::cell::RefCell as Lock};
+
+#[cfg(feature = "sync")]
+use alloc::sync::Arc as Shared;
+
+/// Mutex with the subset of the [core::cell::RefCell] interface the tests use
+///
+/// With the `sync` feature, hooks must be [Send].
+#[cfg(feature = "sync")]
+#[derive(Default)]
+struct Lock<T>(std::sync::Mutex<T>);
+
+#[cfg(feature = "sync")]
+impl<T> Lock<T> {
+    fn borrow_mut(&self) -> std::sync::MutexGuard<'_, T> {
+        self.0.lock().unwrap()
+    }
+}
+
 #[cfg(not(feature = "std"))]
 macro_rules! println {
     () => {
@@ -45,7 +77,7 @@ macro_rules! println {
     };
 }
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "sync")))]
 arcadeum::bind!(Blank);
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
@@ -57,6 +89,7 @@ impl State for Blank {
     type Action = ();
     type Event = ();
     type Secret = ();
+    type Scheme = arcadeum::crypto::Secp256k1;
 
     fn version() -> &'static [u8] {
         "Blank".as_bytes()
@@ -87,7 +120,7 @@ impl State for Blank {
         _player: Option<crate::Player>,
         _action: &Self::Action,
         context: Context<Self::Secret, Self::Event>,
-    ) -> Pin<Box<dyn Future<Output = (Self, Context<Self::Secret, Self::Event>)>>> {
+    ) -> StateFuture<Self> {
         Box::pin(async move { (self, context) })
     }
 }
@@ -96,7 +129,7 @@ impl State for Blank {
 fn test_blank_game() {
     let mut tester = Tester::new(
         Blank::default(),
-        Default::default(),
+        vec![(); 2],
         Vec::new(),
         |player, _, _| println!("[{:?}: ready]", player),
         |player, target, event| println!("[{:?} (target {:?}): log] {:?}", player, target, event),
@@ -108,3 +141,561 @@ fn test_blank_game() {
     tester.apply(Some(0), &()).unwrap();
     tester.apply(Some(1), &()).unwrap();
 }
+
+#[test]
+fn test_blank_out_of_order_diffs() {
+    let (keys, root) = new_match();
+
+    let store = |player: Player| {
+        let key = keys[1 + usize::from(player)];
+
+        StoreBuilder::new(Hooks::new(key))
+            .player(Some(player))
+            .build(&root, vec![None; 2])
+            .unwrap()
+    };
+
+    let mut sender = store(0);
+    let mut receiver = store(1);
+
+    let mut diff = || {
+        let diff = sender
+            .diff(vec![ProofAction {
+                player: Some(0),
+                action: PlayerAction::Play(StoreAction::new(())),
+            }])
+            .unwrap();
+
+        sender.apply(&diff).unwrap();
+
+        diff
+    };
+
+    let first = diff();
+    let second = diff();
+    let third = diff();
+    let fourth = diff();
+    let fifth = diff();
+    let sixth = diff();
+
+    receiver.apply(&second).unwrap();
+
+    assert_eq!(receiver.pending().count(), 1);
+
+    receiver.apply(&first).unwrap();
+
+    assert_eq!(receiver.pending().count(), 0);
+    assert_eq!(receiver.hash(), third.proof());
+
+    // stale diffs are rejected instead of buffered
+
+    assert!(matches!(
+        receiver.apply(&first),
+        Err(Error::WrongProofHash { .. })
+    ));
+    assert_eq!(receiver.pending().count(), 0);
+
+    receiver.set_pending_capacity(0);
+
+    assert!(matches!(
+        receiver.apply(&fourth),
+        Err(Error::WrongProofHash { .. })
+    ));
+
+    // each author fills at most their share of the buffer
+
+    receiver.set_pending_capacity(3);
+    receiver.apply(&fourth).unwrap();
+
+    assert!(matches!(
+        receiver.apply(&fifth),
+        Err(Error::WrongProofHash { .. })
+    ));
+
+    receiver.set_pending_capacity(Store::<Blank>::DEFAULT_PENDING_CAPACITY);
+    receiver.apply(&fifth).unwrap();
+    receiver.expire_pending(|diff| diff.proof() == fourth.proof());
+
+    assert_eq!(receiver.pending().count(), 1);
+
+    receiver.apply(&third).unwrap();
+    receiver.apply(&fourth).unwrap();
+
+    assert_eq!(receiver.hash(), sixth.proof());
+
+    // diffs by unknown authors aren't buffered, so fresh keys can't flood the buffer
+    // (tampering only changes the recovered author with real cryptography)
+
+    #[cfg(not(feature = "no-crypto"))]
+    {
+        let mut forger = store(1);
+
+        for diff in [&first, &second, &third, &fourth, &fifth, &sixth].iter() {
+            forger.apply(diff).unwrap();
+        }
+
+        let data = forger
+            .diff(vec![ProofAction {
+                player: Some(1),
+                action: PlayerAction::Play(StoreAction::new(())),
+            }])
+            .unwrap()
+            .serialize();
+
+        for i in 0..Store::<Blank>::DEFAULT_PENDING_CAPACITY {
+            let mut flood = data.clone();
+
+            // tampering with the proof hash changes the recovered author
+
+            flood[0] = i.try_into().unwrap();
+            flood[1] ^= 1;
+
+            let flood =
+                Diff::<StoreState<Blank>>::deserialize(&flood, receiver.state().id()).unwrap();
+
+            assert!(matches!(
+                receiver.apply(&flood),
+                Err(Error::WrongProofHash { .. })
+            ));
+        }
+
+        assert_eq!(receiver.pending().count(), 0);
+    }
+
+    // buffered diffs that fail to apply are discarded without failing the diff that preceded them
+
+    let mut owner = StoreBuilder::new(Hooks::new(keys[0]))
+        .build(&root, vec![None; 2])
+        .unwrap();
+
+    for diff in [&first, &second, &third, &fourth, &fifth, &sixth].iter() {
+        owner.apply(diff).unwrap();
+    }
+
+    // a checkpoint that doesn't commit to the players' signatures fails to apply
+
+    let forged = owner
+        .diff(vec![ProofAction {
+            player: None,
+            action: PlayerAction::Checkpoint {
+                signatures: Default::default(),
+            },
+        }])
+        .unwrap();
+
+    let discarded: Shared<Lock<Vec<Error>>> = Default::default();
+
+    receiver.set_discard_hook({
+        let discarded = discarded.clone();
+
+        move |_, error| discarded.borrow_mut().push(error.clone())
+    });
+
+    receiver.apply(&forged).unwrap();
+    receiver.apply(&sixth).unwrap();
+
+    assert_eq!(receiver.hash(), sender.hash());
+    assert_eq!(receiver.pending().count(), 0);
+    assert_eq!(discarded.borrow_mut().len(), 1);
+}
+
+#[test]
+fn test_blank_match_status() {
+    let (keys, root) = new_match();
+
+    let sent: Shared<Lock<Vec<Diff<StoreState<Blank>>>>> = Default::default();
+
+    let store = |player: Player| {
+        let key = keys[1 + usize::from(player)];
+
+        StoreBuilder::new(Hooks {
+            sent: sent.clone(),
+            ..Hooks::new(key)
+        })
+        .player(Some(player))
+        .build(&root, vec![None; 2])
+        .unwrap()
+    };
+
+    let mut stores = [store(0), store(1)];
+
+    let deliver = |stores: &mut [Store<Blank>; 2], from: usize| {
+        for diff in sent.borrow_mut().drain(..) {
+            stores[1 - from].apply(&diff).unwrap();
+        }
+    };
+
+    // a draw offer alone doesn't end the match
+
+    stores[0].offer_draw().unwrap();
+    deliver(&mut stores, 0);
+
+    assert!(stores
+        .iter()
+        .all(|store| store.status() == MatchStatus::InProgress));
+
+    // a resignation does, and the status agrees with the outcome
+
+    stores[1].resign().unwrap();
+    deliver(&mut stores, 1);
+
+    for store in &stores {
+        assert_eq!(store.status(), MatchStatus::Resigned(1));
+        assert_eq!(store.outcome(), Outcome::Resigned(1));
+    }
+
+    assert!(matches!(stores[1].abort(), Err(Error::Terminated { .. })));
+    assert!(matches!(
+        stores[0].accept_draw(),
+        Err(Error::Terminated { .. })
+    ));
+}
+
+#[test]
+fn test_blank_equivocation() {
+    let (keys, root) = new_match();
+
+    let store = |player: Player| {
+        let key = keys[1 + usize::from(player)];
+
+        StoreBuilder::new(Hooks::new(key))
+            .player(Some(player))
+            .build(&root, vec![None; 2])
+            .unwrap()
+    };
+
+    // player 0 signs two different diffs on the same proof
+
+    let diff = |plays: usize| {
+        store(0)
+            .diff(
+                (0..plays)
+                    .map(|_| ProofAction {
+                        player: Some(0),
+                        action: PlayerAction::Play(StoreAction::new(())),
+                    })
+                    .collect(),
+            )
+            .unwrap()
+    };
+
+    let first = diff(1);
+    let second = diff(2);
+
+    let mut receiver = store(1);
+
+    receiver.apply(&first).unwrap();
+
+    // the receiver still detects the conflict after being reloaded
+
+    let mut receiver = StoreBuilder::new(Hooks::new(keys[2]))
+        .deserialize(&receiver.serialize(SecretKnowledge::None))
+        .unwrap();
+
+    let evidence: Shared<Lock<Option<EquivocationEvidence>>> = Default::default();
+
+    receiver.set_equivocation_hook({
+        let evidence = evidence.clone();
+        let first = first.clone();
+
+        move |equivocation| {
+            assert_eq!(equivocation.accepted(), &first.digest());
+            assert!(equivocation
+                .evidence(&equivocation.diff().clone())
+                .is_none());
+
+            *evidence.borrow_mut() = equivocation.evidence(&first);
+        }
+    });
+
+    match receiver.apply(&second) {
+        Err(Error::Slashable { author, rule, .. }) => {
+            assert_eq!(author, Secp256k1::address(&keys[1]));
+            assert_eq!(rule, Offence::Equivocation);
+        }
+        result => panic!("{:?}", result),
+    }
+
+    let evidence = evidence.borrow_mut().take().unwrap();
+
+    assert_eq!(evidence.offender(), &Secp256k1::address(&keys[1]));
+    evidence.verify::<StoreState<Blank>>().unwrap();
+}
+
+#[test]
+fn test_blank_checkpoint() {
+    let (keys, root) = new_match();
+
+    let sent: Shared<Lock<Vec<Diff<StoreState<Blank>>>>> = Default::default();
+
+    let store = |player: Option<Player>| {
+        let key = keys[player.map_or(0, |player| 1 + usize::from(player))];
+
+        StoreBuilder::new(Hooks {
+            sent: sent.clone(),
+            ..Hooks::new(key)
+        })
+        .player(player)
+        .build(&root, vec![None; 2])
+        .unwrap()
+    };
+
+    let mut stores = [store(None), store(Some(0)), store(Some(1))];
+
+    stores[0].set_checkpoint_threshold(0);
+
+    let mut play = |player: Player| {
+        let diff = stores[1 + usize::from(player)]
+            .diff(vec![ProofAction {
+                player: Some(player),
+                action: PlayerAction::Play(StoreAction::new(())),
+            }])
+            .unwrap();
+
+        for store in &mut stores {
+            store.apply(&diff).unwrap();
+        }
+
+        let sent: Vec<_> = sent.borrow_mut().drain(..).collect();
+
+        for diff in &sent {
+            for store in &mut stores[1..] {
+                store.apply(diff).unwrap();
+            }
+        }
+
+        assert!(stores.iter().all(|store| store.hash() == stores[0].hash()));
+
+        (sent.len(), stores[1].serialize(SecretKnowledge::None).len())
+    };
+
+    // the owner waits for every player to sign before checkpointing
+
+    let (checkpoints, _) = play(0);
+
+    assert_eq!(checkpoints, 0);
+
+    let (checkpoints, size) = play(1);
+
+    assert_eq!(checkpoints, 1);
+
+    // play continues on top of the checkpoint, and the next checkpoint compacts the proof again
+
+    let (checkpoints, grown) = play(0);
+
+    assert_eq!(checkpoints, 0);
+    assert!(grown > size);
+
+    assert_eq!(play(1), (1, size));
+}
+
+#[test]
+fn test_blank_decode_limits() {
+    let (keys, root) = new_match();
+
+    let store = |limits: DecodeLimits| {
+        StoreBuilder::new(Hooks::new(keys[1]))
+            .player(Some(0))
+            .limits(limits)
+            .build(&root, vec![None; 2])
+    };
+
+    let tight = DecodeLimits {
+        max_actions: 1,
+        max_state_size: 256,
+        max_secret_size: 0,
+        max_merkle_leaves: 2,
+    };
+
+    assert!(matches!(
+        store(DecodeLimits {
+            max_state_size: 0,
+            ..tight
+        }),
+        Err(Error::Decode { .. })
+    ));
+
+    let mut store = store(tight).unwrap();
+
+    assert_eq!(store.decode_limits(), &tight);
+
+    // a hostile action count is rejected before anything is allocated for it
+
+    let mut hostile = root.clone();
+    let size = u32::from_le_bytes(hostile[..4].try_into().unwrap()) as usize;
+
+    hostile[4 + size..][..4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    assert!(matches!(
+        RootProof::<StoreState<Blank>>::deserialize(&hostile, false),
+        Err(Error::Decode { .. })
+    ));
+
+    let diff = store
+        .diff(vec![
+            ProofAction {
+                player: Some(0),
+                action: PlayerAction::Play(StoreAction::new(())),
+            },
+            ProofAction {
+                player: Some(0),
+                action: PlayerAction::Play(StoreAction::new(())),
+            },
+        ])
+        .unwrap()
+        .serialize();
+
+    assert!(Diff::<StoreState<Blank>>::deserialize(&diff, store.state().id()).is_ok());
+
+    assert!(matches!(
+        Diff::<StoreState<Blank>>::deserialize_with_limits(
+            &diff,
+            store.state().id(),
+            store.decode_limits(),
+        ),
+        Err(Error::Decode { .. })
+    ));
+
+    store.set_decode_limits(Default::default());
+
+    assert!(Diff::<StoreState<Blank>>::deserialize_with_limits(
+        &diff,
+        store.state().id(),
+        store.decode_limits(),
+    )
+    .is_ok());
+
+    let tree = MerkleTree::new(vec![vec![0], vec![1], vec![2]]).serialize();
+
+    assert!(MerkleTree::<Vec<u8>>::deserialize(&tree).is_ok());
+
+    assert!(matches!(
+        MerkleTree::<Vec<u8>>::deserialize_with_limits(&tree, &tight),
+        Err(Error::Decode { .. })
+    ));
+
+    assert!(matches!(
+        MerkleTree::<Vec<u8>>::deserialize(&u32::MAX.to_le_bytes()),
+        Err(Error::Decode { .. })
+    ));
+}
+
+/// Generates the keys of an owner and two players, and the root proof of their match.
+fn new_match() -> (Vec<arcadeum::crypto::SecretKey>, Vec<u8>) {
+    let keys: Vec<_> = (1..=3)
+        .map(|i| Secp256k1::secret_key(&[i; 32]).unwrap())
+        .collect();
+
+    let state = ProofState::new(
+        Default::default(),
+        keys[1..].iter().map(Secp256k1::address).collect(),
+        StoreState::new(Blank, vec![None; 2], |_, _| ()),
+    )
+    .unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Secp256k1::sign(message, &keys[0]).map_err(|error| error.to_string())
+    })
+    .unwrap()
+    .serialize();
+
+    (keys, root)
+}
+
+struct Hooks {
+    key: arcadeum::crypto::SecretKey,
+    random: rand::rngs::StdRng,
+    ready: Arc<AtomicUsize>,
+    sent: Shared<Lock<Vec<Diff<StoreState<Blank>>>>>,
+}
+
+impl Hooks {
+    fn new(key: arcadeum::crypto::SecretKey) -> Self {
+        Self {
+            key,
+            random: rand::rngs::StdRng::from_seed([1; 32]),
+            ready: Default::default(),
+            sent: Default::default(),
+        }
+    }
+}
+
+impl StoreHooks<Blank> for Hooks {
+    fn on_ready(&mut self, _state: &Blank, _secrets: &[Option<&()>]) {
+        self.ready.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn sign(&mut self, message: &[u8]) -> Result<arcadeum::crypto::Signature, String> {
+        Secp256k1::sign(message, &self.key).map_err(|error| error.to_string())
+    }
+
+    fn send(&mut self, diff: &Diff<StoreState<Blank>>) {
+        self.sent.borrow_mut().push(diff.clone());
+    }
+
+    fn random(&mut self) -> &mut dyn rand::RngCore {
+        &mut self.random
+    }
+}
+
+#[test]
+fn test_blank_hooks() {
+    let (keys, root) = new_match();
+
+    let ready: Arc<AtomicUsize> = Default::default();
+    let sent: Shared<Lock<Vec<Diff<StoreState<Blank>>>>> = Default::default();
+
+    let hooks = |player: Player| Hooks {
+        key: keys[1 + usize::from(player)],
+        random: rand::rngs::StdRng::from_seed([player; 32]),
+        ready: ready.clone(),
+        sent: sent.clone(),
+    };
+
+    let mut sender = StoreBuilder::new(hooks(0))
+        .player(Some(0))
+        .build(&root, vec![None; 2])
+        .unwrap();
+
+    let mut receiver = StoreBuilder::new(hooks(1))
+        .player(Some(1))
+        .limits(DecodeLimits {
+            max_actions: 8,
+            ..Default::default()
+        })
+        .build(&root, vec![None; 2])
+        .unwrap();
+
+    assert_eq!(receiver.player(), Some(1));
+    assert_eq!(receiver.decode_limits().max_actions, 8);
+
+    let diff = sender
+        .diff(vec![ProofAction {
+            player: Some(0),
+            action: PlayerAction::Play(StoreAction::new(())),
+        }])
+        .unwrap();
+
+    sender.apply(&diff).unwrap();
+    receiver.apply(&diff).unwrap();
+
+    assert_eq!(ready.load(Ordering::SeqCst), 2);
+
+    // a deserialized store keeps the player it was serialized with
+
+    let mut receiver = StoreBuilder::new(hooks(1))
+        .player(None)
+        .deserialize(&receiver.serialize(SecretKnowledge::Some(1)))
+        .unwrap();
+
+    assert_eq!(receiver.player(), Some(1));
+    assert_eq!(receiver.hash(), sender.hash());
+
+    receiver.resign().unwrap();
+
+    assert_eq!(sent.borrow_mut().len(), 1);
+
+    sender.apply(&sent.borrow_mut()[0]).unwrap();
+
+    assert_eq!(sender.status(), MatchStatus::Resigned(1));
+}
@@ -27,10 +27,11 @@ use {
         vec::Vec,
     },
     arcadeum::{
-        store::{Context, State, Tester},
-        Player,
+        crypto::{Eip712, SignatureScheme},
+        inspect::{inspect, Kind},
+        store::{Context, State, StateFuture, StoreState, Tester},
+        Diff, Player, Proof, ProofState, RootProof,
     },
-    core::{future::Future, pin::Pin},
     rand::RngCore,
     serde::{Deserialize, Serialize},
 };
@@ -47,7 +48,7 @@ macro_rules! println {
     };
 }
 
-#[cfg(feature = "std")]
+#[cfg(all(feature = "std", not(feature = "sync")))]
 arcadeum::bind!(Coin);
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
@@ -62,6 +63,7 @@ impl State for Coin {
     type Action = bool;
     type Event = u32;
     type Secret = ();
+    type Scheme = arcadeum::crypto::Secp256k1;
 
     fn version() -> &'static [u8] {
         "Coin".as_bytes()
@@ -99,7 +101,7 @@ impl State for Coin {
         player: Option<crate::Player>,
         action: &Self::Action,
         mut context: Context<Self::Secret, Self::Event>,
-    ) -> Pin<Box<dyn Future<Output = (Self, Context<Self::Secret, Self::Event>)>>> {
+    ) -> StateFuture<Self> {
         Box::pin({
             let action = *action;
 
@@ -120,11 +122,59 @@ impl State for Coin {
     }
 }
 
+/// [Coin] signed with EIP 712 typed data
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct TypedCoin(Coin);
+
+impl State for TypedCoin {
+    type ID = [u8; 16];
+    type Nonce = u8;
+    type Action = bool;
+    type Event = u32;
+    type Secret = ();
+    type Scheme = Eip712;
+
+    fn version() -> &'static [u8] {
+        "TypedCoin".as_bytes()
+    }
+
+    fn deserialize(data: &[u8]) -> Result<Self, String> {
+        <Coin as State>::deserialize(data).map(Self)
+    }
+
+    fn is_serializable(&self) -> bool {
+        self.0.is_serializable()
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        State::serialize(&self.0)
+    }
+
+    fn verify(&self, player: Option<crate::Player>, action: &Self::Action) -> Result<(), String> {
+        self.0.verify(player, action)
+    }
+
+    fn apply(
+        self,
+        player: Option<crate::Player>,
+        action: &Self::Action,
+        context: Context<Self::Secret, Self::Event>,
+    ) -> StateFuture<Self> {
+        let apply = self.0.apply(player, action, context);
+
+        Box::pin(async move {
+            let (coin, context) = apply.await;
+
+            (Self(coin), context)
+        })
+    }
+}
+
 #[test]
 fn test_coin() {
     let mut tester = Tester::new(
         Coin::default(),
-        Default::default(),
+        vec![(); 2],
         Vec::new(),
         |player, _, _| println!("[{:?}: ready]", player),
         |player, target, event| println!("[{:?} (target {:?}): log] {:?}", player, target, event),
@@ -214,3 +264,249 @@ fn test_coin() {
     tester.apply(Some(1), &true).unwrap();
     tester.apply(Some(0), &true).unwrap();
 }
+
+#[test]
+fn test_coin_eip712() {
+    let mut tester = Tester::new(
+        TypedCoin::default(),
+        vec![(); 2],
+        Vec::new(),
+        |_, _, _| (),
+        |_, _, _| (),
+        false,
+    )
+    .unwrap();
+
+    for _ in 0..4 {
+        tester.apply(Some(0), &true).unwrap();
+        tester.apply(Some(1), &true).unwrap();
+    }
+
+    assert_eq!(tester.state().0.nonce, 8);
+
+    // root proofs are signed as typed data, not as personal messages
+
+    let owner = Eip712::secret_key(&[1; 32]).unwrap();
+
+    let state = ProofState::new(
+        Default::default(),
+        vec![[2; 20], [3; 20]],
+        StoreState::new(TypedCoin::default(), vec![None; 2], |_, _| ()),
+    )
+    .unwrap();
+
+    let root = RootProof::new(state.clone(), Vec::new(), &mut |message| {
+        assert!(Eip712::typed_data(message)
+            .unwrap()
+            .contains(&format!(r#""name":"{}""#, Eip712::NAME)));
+
+        Eip712::sign(message, &owner).map_err(|error| error.to_string())
+    })
+    .unwrap();
+
+    assert_eq!(root.author(), &Eip712::address(&owner));
+
+    // diffs are signed in the domain of their match, which isn't part of their encoding
+
+    let mut proof = Proof::new(root.clone());
+
+    let diff = proof
+        .diff(Vec::new(), &mut |message| {
+            Eip712::sign(message, &owner).map_err(|error| error.to_string())
+        })
+        .unwrap()
+        .serialize();
+
+    let other = Diff::<StoreState<TypedCoin>>::deserialize(&diff, &[1; 16]).unwrap();
+
+    #[cfg(not(feature = "no-crypto"))]
+    assert_ne!(other.author(), &Eip712::address(&owner));
+    assert!(proof.clone().apply(&other).is_err());
+
+    let diff = Diff::deserialize(&diff, proof.state().id()).unwrap();
+
+    assert_eq!(diff.author(), &Eip712::address(&owner));
+
+    // the inspector needs the match identifier to recover typed data signers
+
+    let mut context = arcadeum::inspect::Context::new(arcadeum::ID::serialize(root.state().id()));
+
+    let report = inspect::<Eip712>(&root.serialize(), Kind::RootProof, &context).unwrap();
+
+    assert!(report.is_valid());
+    assert_eq!(report.author(), Some(&Eip712::address(&owner)[..]));
+
+    context.learn(&report);
+
+    let report = inspect::<Eip712>(&diff.serialize(), Kind::Diff, &context).unwrap();
+
+    assert_eq!(report.author(), Some(&Eip712::address(&owner)[..]));
+
+    #[cfg(not(feature = "no-crypto"))]
+    {
+        let context = arcadeum::inspect::Context::new(vec![1; 16]);
+        let report = inspect::<Eip712>(&root.serialize(), Kind::RootProof, &context).unwrap();
+
+        assert_ne!(report.author(), Some(&Eip712::address(&owner)[..]));
+    }
+
+    proof.apply(&diff).unwrap();
+
+    #[cfg(not(feature = "no-crypto"))]
+    {
+        let root = RootProof::new(state, Vec::new(), &mut |message| {
+            arcadeum::crypto::Secp256k1::sign(message, &owner).map_err(|error| error.to_string())
+        });
+
+        assert_ne!(
+            root.as_ref().map(RootProof::author).ok(),
+            Some(&Eip712::address(&owner))
+        );
+    }
+}
+
+/// Stores encoded before stores had other than two players
+///
+/// Their signatures only verify with real cryptography.
+#[cfg(not(feature = "no-crypto"))]
+mod baseline {
+    use {
+        super::Coin,
+        alloc::string::{String, ToString},
+        arcadeum::{
+            store::{SecretKnowledge, StoreBuilder, StoreHooks, StoreState},
+            Diff, Format, RootProof,
+        },
+    };
+
+    /// Root proof of a store encoded before stores had other than two players
+    const BASELINE_ROOT: &str =
+        "0x0301000004000000436f696e900000000000000000000000000000000002ce9ad6a3801a902250b1ec940a\
+         34a607907e1a6212cd5f2391feecc711553b82278468e490de28a5020000001b4b5b02248af2e60ac4148a48\
+         9b46bac12300898f82729eb75cd3fee3fe7ad757c602ea5f2a4931b0e500b28bf21688d04662254af62abe78\
+         73f2554bcd51e7ac225affa1cf049d79a541498360bdd10a0157231c3033ede45e631bbe0aecce0d62cd771e\
+         52a45ed12d897293612247cdbb8407c939173b97af6069217503f9c63b44580cc9bb8e6d1167e75028d8e475\
+         c540fee01f6dc87b46a743bba0323fa392089ddce355eef21b00000000000000000000000000000000000000\
+         000000003aea573f6e97202859d8c7e7de9135ca9f900acb955869c2fd0414cb3279861c07bef9eedb835e7e\
+         e8aaaee36520d50fe31947dcd2406b3e71188f20f80450a91b";
+
+    /// Owner store of the same match after one action, knowing both secrets
+    const BASELINE_OWNER: &str =
+        "0x030101000000f61d000000a461781a0101010161791a01010101617a1a0101010161771a01010101010100\
+         0000f61d000000a461781a0202020261791a02020202617a1a0202020261771a020202024c01000003010000\
+         04000000436f696e900000000000000000000000000000000002ce9ad6a3801a902250b1ec940a34a607907e\
+         1a6212cd5f2391feecc711553b82278468e490de28a5020000001b4b5b02248af2e60ac4148a489b46bac123\
+         00898f82729eb75cd3fee3fe7ad757c602ea5f2a4931b0e500b28bf21688d04662254af62abe7873f2554bcd\
+         51e7ac225affa1cf049d79a541498360bdd10a0157231c3033ede45e631bbe0aecce0d62cd771e52a45ed12d\
+         897293612247cdbb8407c939173b97af6069217503f9c63b44580cc9bb8e6d1167e75028d8e475c540fee01f\
+         6dc87b46a743bba0323fa392089ddce355eef21b00000000000000000000000000000000000000000000003a\
+         ea573f6e97202859d8c7e7de9135ca9f900acb955869c2fd0414cb3279861c07bef9eedb835e7ee8aaaee365\
+         20d50fe31947dcd2406b3e71188f20f80450a91b0101000000f61d000000a461781a0101010161791a010101\
+         01617a1a0101010161771a010101010101000000f61d000000a461781a0202020261791a02020202617a1a02\
+         02020261771a02020202fd0100000301000004000000436f696e900000000000000000000000000000000002\
+         ce9ad6a3801a902250b1ec940a34a607907e1a6212cd5f2391feecc711553b82278468e490de28a502000000\
+         1b4b5b02248af2e60ac4148a489b46bac12300898f82729eb75cd3fee3fe7ad757c602ea5f2a4931b0e500b2\
+         8bf21688d04662254af62abe7873f2554bcd51e7ac225affa1cf049d79a541498360bdd10a0157231c3033ed\
+         e45e631bbe0aecce0d62cd771e52a45ed12d897293612247cdbb8407c939173b97af6069217503f9c63b4458\
+         0cc9bb8e6d1167e75028d8e475c540fee01f6dc87b46a743bba0323fa392089ddce355eef21b000000000000\
+         000000000000000000000000000400000004000000010000f523000000010001d211e399fbd1a96478e752bc\
+         cabc5438c98939b547493a0132d92f270163b249130000000200027b030318630003031b0003180303030313\
+         00000001000352020210420002021200021002020202000104000000040000008392598e607b5f670bf4512a\
+         b8176d351bfeeaf9919bc38439d4935915b1756544279461f7e857e41d6ec03637e750b6c6b733851f28e979\
+         007d79a423c2f5901b010000000003000000db0c81dc1a638d2fc55eb1d75fb795190edeca2814d24fe1b5aa\
+         225eb497f7fa565140723e68ee1e52374bb799fb68c3eea0066c00a85a23e96e5ea6c0fd3ab31c00";
+
+    /// Store of player 0 in the same match
+    const BASELINE_PLAYER: &str =
+        "0x010101000000f61d000000a461781a0101010161791a01010101617a1a0101010161771a01010101004c01\
+         00000301000004000000436f696e900000000000000000000000000000000002ce9ad6a3801a902250b1ec94\
+         0a34a607907e1a6212cd5f2391feecc711553b82278468e490de28a5020000001b4b5b02248af2e60ac4148a\
+         489b46bac12300898f82729eb75cd3fee3fe7ad757c602ea5f2a4931b0e500b28bf21688d04662254af62abe\
+         7873f2554bcd51e7ac225affa1cf049d79a541498360bdd10a0157231c3033ede45e631bbe0aecce0d62cd77\
+         1e52a45ed12d897293612247cdbb8407c939173b97af6069217503f9c63b44580cc9bb8e6d1167e75028d8e4\
+         75c540fee01f6dc87b46a743bba0323fa392089ddce355eef21b000000000000000000000000000000000000\
+         00000000003aea573f6e97202859d8c7e7de9135ca9f900acb955869c2fd0414cb3279861c07bef9eedb835e\
+         7ee8aaaee36520d50fe31947dcd2406b3e71188f20f80450a91b0101000000f61d000000a461781a01010101\
+         61791a01010101617a1a0101010161771a0101010100fd0100000301000004000000436f696e900000000000\
+         000000000000000000000002ce9ad6a3801a902250b1ec940a34a607907e1a6212cd5f2391feecc711553b82\
+         278468e490de28a5020000001b4b5b02248af2e60ac4148a489b46bac12300898f82729eb75cd3fee3fe7ad7\
+         57c602ea5f2a4931b0e500b28bf21688d04662254af62abe7873f2554bcd51e7ac225affa1cf049d79a54149\
+         8360bdd10a0157231c3033ede45e631bbe0aecce0d62cd771e52a45ed12d897293612247cdbb8407c939173b\
+         97af6069217503f9c63b44580cc9bb8e6d1167e75028d8e475c540fee01f6dc87b46a743bba0323fa392089d\
+         dce355eef21b000000000000000000000000000000000000000400000004000000010000f523000000010001\
+         d211e399fbd1a96478e752bccabc5438c98939b547493a0132d92f270163b249130000000200027b03031863\
+         0003031b00031803030303130000000100035202021042000202120002100202020200010400000004000000\
+         8392598e607b5f670bf4512ab8176d351bfeeaf9919bc38439d4935915b1756544279461f7e857e41d6ec036\
+         37e750b6c6b733851f28e979007d79a423c2f5901b010000000003000000db0c81dc1a638d2fc55eb1d75fb7\
+         95190edeca2814d24fe1b5aa225eb497f7fa565140723e68ee1e52374bb799fb68c3eea0066c00a85a23e96e\
+         5ea6c0fd3ab31c00";
+
+    /// Store hooks for stores that are only decoded and encoded
+    struct Replay(rand::rngs::StdRng);
+
+    impl StoreHooks<Coin> for Replay {
+        fn sign(&mut self, _message: &[u8]) -> Result<arcadeum::crypto::Signature, String> {
+            Err("replay".to_string())
+        }
+
+        fn send(&mut self, _diff: &Diff<StoreState<Coin>>) {}
+
+        fn random(&mut self) -> &mut dyn rand::RngCore {
+            &mut self.0
+        }
+    }
+
+    #[test]
+    fn test_coin_baseline() {
+        let data = arcadeum::utils::unhex(BASELINE_ROOT).unwrap();
+        let root = RootProof::<StoreState<Coin>>::deserialize(&data, false).unwrap();
+
+        assert_eq!(root.serialize(), data);
+        assert_eq!(root.state().players().len(), 2);
+        assert_eq!(
+            RootProof::<StoreState<Coin>>::player_count(&data).unwrap(),
+            2
+        );
+
+        let compact = root.serialize_with_format(Format::V2);
+
+        assert_eq!(
+            RootProof::<StoreState<Coin>>::deserialize(&compact, false)
+                .unwrap()
+                .serialize(),
+            data
+        );
+
+        // knowledge byte 3 is a store without a player, knowing all of the secrets
+
+        let data = arcadeum::utils::unhex(BASELINE_OWNER).unwrap();
+        let store = StoreBuilder::new(Replay(rand::SeedableRng::from_seed([1; 32])))
+            .deserialize(&data)
+            .unwrap();
+
+        assert_eq!(store.player(), None);
+        assert_eq!(store.state().state().state().unwrap().nonce, 1);
+        assert_eq!(store.serialize(SecretKnowledge::All), data);
+        assert!(SecretKnowledge::from(3) == SecretKnowledge::All);
+
+        let data = arcadeum::utils::unhex(BASELINE_PLAYER).unwrap();
+        let store = StoreBuilder::new(Replay(rand::SeedableRng::from_seed([1; 32])))
+            .deserialize(&data)
+            .unwrap();
+
+        assert_eq!(store.player(), Some(0));
+        assert_eq!(store.serialize(SecretKnowledge::Some(0)), data);
+
+        // the compact format carries the untagged layouts unchanged
+
+        let compact = store.serialize_with_format(SecretKnowledge::Some(0), Format::V2);
+
+        assert_eq!(Format::detect(&compact), Format::V2);
+
+        let store = StoreBuilder::new(Replay(rand::SeedableRng::from_seed([1; 32])))
+            .deserialize(&compact)
+            .unwrap();
+
+        assert_eq!(store.serialize(SecretKnowledge::Some(0)), data);
+    }
+}
@@ -0,0 +1,181 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use {
+    alloc::{
+        boxed::Box,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    },
+    arcadeum::{
+        store::{Context, State, StateFuture, Tester},
+        Player,
+    },
+    rand::RngCore,
+    serde::{Deserialize, Serialize},
+};
+
+#[cfg(not(feature = "std"))]
+macro_rules! println {
+    () => {
+        ()
+    };
+    ($($arg:tt),*) => {
+        {
+            $(drop($arg);)*
+        }
+    };
+}
+
+const PLAYERS: usize = 3;
+
+#[cfg(all(feature = "std", not(feature = "sync")))]
+arcadeum::bind!(Dice);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct Dice {
+    nonce: u8,
+    score: [u8; PLAYERS],
+}
+
+impl State for Dice {
+    type ID = [u8; 16];
+    type Nonce = u8;
+    type Action = ();
+    type Event = u8;
+    type Secret = u8;
+    type Scheme = arcadeum::crypto::Ed25519;
+
+    fn version() -> &'static [u8] {
+        "Dice".as_bytes()
+    }
+
+    fn deserialize(data: &[u8]) -> Result<Self, String> {
+        if data.len() != 1 + PLAYERS {
+            return Err("data.len() != 1 + PLAYERS".to_string());
+        }
+
+        let mut score = [0; PLAYERS];
+        score.copy_from_slice(&data[1..]);
+
+        Ok(Self {
+            nonce: data[0],
+            score,
+        })
+    }
+
+    fn is_serializable(&self) -> bool {
+        true
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        Some([&[self.nonce][..], &self.score].concat())
+    }
+
+    fn verify(&self, player: Option<crate::Player>, _action: &Self::Action) -> Result<(), String> {
+        if player != Some(self.nonce % PLAYERS as Player) {
+            return Err("player != Some(self.nonce % PLAYERS)".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn apply(
+        mut self,
+        player: Option<crate::Player>,
+        _action: &Self::Action,
+        mut context: Context<Self::Secret, Self::Event>,
+    ) -> StateFuture<Self> {
+        Box::pin(async move {
+            let player = player.unwrap();
+
+            let roll = 1 + (context.random().await.next_u32() % 6) as u8;
+
+            context.log(roll);
+
+            let bonus = context
+                .reveal_unique((player + 1) % PLAYERS as Player, |secret| *secret, |_| true)
+                .await;
+
+            self.score[usize::from(player)] += roll + bonus;
+            self.nonce += 1;
+
+            (self, context)
+        })
+    }
+}
+
+#[test]
+fn test_dice() {
+    let mut tester = Tester::new(
+        Dice::default(),
+        vec![1, 2, 3],
+        Vec::new(),
+        |player, _, _| println!("[{:?}: ready]", player),
+        |player, target, event| println!("[{:?} (target {:?}): log] {:?}", player, target, event),
+        false,
+    )
+    .unwrap();
+
+    for player in 0..PLAYERS as Player {
+        assert_eq!(**tester.secret(player), 1 + player);
+    }
+
+    for turn in 0..2 * PLAYERS {
+        tester.apply(Some((turn % PLAYERS) as Player), &()).unwrap();
+    }
+
+    assert_eq!(usize::from(tester.state().nonce), 2 * PLAYERS);
+
+    for (player, score) in tester.state().score.iter().enumerate() {
+        let bonus = 2 * (1 + (player + 1) % PLAYERS);
+
+        assert!(usize::from(*score) >= 2 + bonus);
+        assert!(usize::from(*score) <= 12 + bonus);
+    }
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn test_dice_threads() {
+    let mut tester = Tester::new(
+        Dice::default(),
+        vec![1, 2, 3],
+        Vec::new(),
+        |player, _, _| println!("[{:?}: ready]", player),
+        |player, target, event| println!("[{:?} (target {:?}): log] {:?}", player, target, event),
+        false,
+    )
+    .unwrap();
+
+    // each turn is played on a different thread
+
+    for turn in 0..2 * PLAYERS {
+        tester = std::thread::spawn(move || {
+            tester.apply(Some((turn % PLAYERS) as Player), &()).unwrap();
+            tester
+        })
+        .join()
+        .unwrap();
+    }
+
+    assert_eq!(usize::from(tester.state().nonce), 2 * PLAYERS);
+}
@@ -0,0 +1,666 @@
+/*
+ * Copyright 2019 Horizon Blockchain Games Inc.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+#[cfg(feature = "sync")]
+extern crate std;
+
+use {
+    alloc::{
+        boxed::Box,
+        string::{String, ToString},
+        sync::Arc,
+        vec,
+        vec::Vec,
+    },
+    arcadeum::{
+        crypto::{Secp256k1, SignatureScheme},
+        store::{
+            AsyncHooks, AsyncStore, Context, Deadlines, RevealTimeout, State, StateFuture, Store,
+            StoreAction, StoreBuilder, StoreEvent, StoreHooks, StoreState,
+        },
+        Action, Diff, MatchStatus, Outcome, Player, PlayerAction, ProofAction, ProofState,
+        RootProof,
+    },
+    core::{
+        sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+        time::Duration,
+    },
+    futures::{executor::block_on, FutureExt, StreamExt},
+    rand::SeedableRng,
+    serde::{Deserialize, Serialize},
+};
+
+#[cfg(not(feature = "sync"))]
+use {alloc::rc::Rc as Shared, core::cell::RefCell as Lock};
+
+#[cfg(feature = "sync")]
+use alloc::sync::Arc as Shared;
+
+/// Mutex with the subset of the [core::cell::RefCell] interface the tests use
+///
+/// With the `sync` feature, hooks must be [Send].
+#[cfg(feature = "sync")]
+#[derive(Default)]
+struct Lock<T>(std::sync::Mutex<T>);
+
+#[cfg(feature = "sync")]
+impl<T> Lock<T> {
+    fn borrow_mut(&self) -> std::sync::MutexGuard<'_, T> {
+        self.0.lock().unwrap()
+    }
+}
+
+#[cfg(all(feature = "std", not(feature = "sync")))]
+arcadeum::bind!(Sealed);
+
+/// Each player in turn opens the other player's sealed bid.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+struct Sealed {
+    nonce: u8,
+    bids: [u8; 2],
+}
+
+impl State for Sealed {
+    type ID = [u8; 16];
+    type Nonce = u8;
+    type Action = ();
+    type Event = ();
+    type Secret = u8;
+    type Scheme = arcadeum::crypto::Secp256k1;
+
+    fn version() -> &'static [u8] {
+        "Sealed".as_bytes()
+    }
+
+    fn deserialize(data: &[u8]) -> Result<Self, String> {
+        if data.len() != 1 + 2 {
+            return Err("data.len() != 1 + 2".to_string());
+        }
+
+        Ok(Self {
+            nonce: data[0],
+            bids: [data[1], data[2]],
+        })
+    }
+
+    fn is_serializable(&self) -> bool {
+        true
+    }
+
+    fn serialize(&self) -> Option<Vec<u8>> {
+        Some(vec![self.nonce, self.bids[0], self.bids[1]])
+    }
+
+    fn verify(&self, player: Option<crate::Player>, _action: &Self::Action) -> Result<(), String> {
+        if player != Some(self.nonce % 2) {
+            return Err("player != Some(self.nonce % 2)".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn apply(
+        mut self,
+        player: Option<crate::Player>,
+        _action: &Self::Action,
+        mut context: Context<Self::Secret, Self::Event>,
+    ) -> StateFuture<Self> {
+        Box::pin(async move {
+            let opponent = 1 - player.unwrap();
+
+            self.bids[usize::from(opponent)] = context
+                .reveal_unique(opponent, |bid| *bid, |bid| *bid <= 100)
+                .await;
+
+            self.nonce += 1;
+
+            (self, context)
+        })
+    }
+
+    fn reveal_timeout(player: crate::Player) -> RevealTimeout {
+        match player {
+            0 => RevealTimeout::Forfeit,
+            _ => RevealTimeout::Default,
+        }
+    }
+}
+
+/// Generates the keys of an owner and two players, and the root proof of their match.
+fn new_match() -> (Vec<arcadeum::crypto::SecretKey>, Vec<u8>) {
+    let keys: Vec<_> = (1..=3)
+        .map(|i| Secp256k1::secret_key(&[i; 32]).unwrap())
+        .collect();
+
+    let state = ProofState::new(
+        Default::default(),
+        keys[1..].iter().map(Secp256k1::address).collect(),
+        StoreState::new(Sealed::default(), vec![None; 2], |_, _| ()),
+    )
+    .unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Secp256k1::sign(message, &keys[0]).map_err(|error| error.to_string())
+    })
+    .unwrap()
+    .serialize();
+
+    (keys, root)
+}
+
+struct Hooks {
+    key: arcadeum::crypto::SecretKey,
+    offline: Arc<AtomicBool>,
+    random: rand::rngs::StdRng,
+    sent: Shared<Lock<Vec<Diff<StoreState<Sealed>>>>>,
+}
+
+impl Hooks {
+    fn new(
+        key: arcadeum::crypto::SecretKey,
+        sent: &Shared<Lock<Vec<Diff<StoreState<Sealed>>>>>,
+    ) -> Self {
+        Self {
+            key,
+            offline: Default::default(),
+            random: rand::rngs::StdRng::from_seed([1; 32]),
+            sent: sent.clone(),
+        }
+    }
+}
+
+impl StoreHooks<Sealed> for Hooks {
+    fn sign(&mut self, message: &[u8]) -> Result<arcadeum::crypto::Signature, String> {
+        if self.offline.load(Ordering::SeqCst) {
+            return Err("offline".to_string());
+        }
+
+        Secp256k1::sign(message, &self.key).map_err(|error| error.to_string())
+    }
+
+    fn send(&mut self, diff: &Diff<StoreState<Sealed>>) {
+        self.sent.borrow_mut().push(diff.clone());
+    }
+
+    fn random(&mut self) -> &mut dyn rand::RngCore {
+        &mut self.random
+    }
+}
+
+#[test]
+fn test_sealed_reveal_timeout() {
+    let (keys, root) = new_match();
+
+    let sent: Shared<Lock<Vec<Diff<StoreState<Sealed>>>>> = Default::default();
+
+    // the owner doesn't know any bids, and each player only knows their own
+
+    let store = |player: Option<Player>| {
+        let key = keys[player.map_or(0, |player| 1 + usize::from(player))];
+
+        let mut secrets = vec![None; 2];
+
+        if let Some(player) = player {
+            secrets[usize::from(player)] = Some((50, [player; 16]));
+        }
+
+        StoreBuilder::new(Hooks::new(key, &sent))
+            .player(player)
+            .build(&root, secrets)
+            .unwrap()
+    };
+
+    let mut stores = [store(None), store(Some(0)), store(Some(1))];
+
+    stores[0].set_reveal_default(|_| 0);
+
+    let play = |stores: &mut [Store<Sealed>; 3], player: Player| {
+        let diff = stores[1 + usize::from(player)]
+            .diff(vec![ProofAction {
+                player: Some(player),
+                action: PlayerAction::Play(StoreAction::new(())),
+            }])
+            .unwrap();
+
+        for store in stores.iter_mut() {
+            store.apply(&diff).unwrap();
+        }
+    };
+
+    let timeout = |stores: &mut [Store<Sealed>; 3]| {
+        stores[0].dispatch_timeout().unwrap();
+
+        for diff in sent.borrow_mut().drain(..) {
+            for store in &mut stores[1..] {
+                store.apply(&diff).unwrap();
+            }
+        }
+
+        assert!(stores.iter().all(|store| store.hash() == stores[0].hash()));
+    };
+
+    // player 1 stalls, so the owner reveals the default bid on their behalf
+
+    play(&mut stores, 0);
+
+    assert_eq!(stores[0].pending_player().unwrap(), Some(1));
+    assert!(stores[1].dispatch_timeout().is_err());
+
+    timeout(&mut stores);
+
+    assert!(stores
+        .iter()
+        .all(|store| store.state().state().state().is_some()));
+    assert_eq!(stores[0].state().state().state().unwrap().bids, [0, 0]);
+    assert_eq!(stores[0].outcome(), Outcome::InProgress);
+
+    // player 0 stalls, so they forfeit the match
+
+    play(&mut stores, 1);
+
+    assert_eq!(stores[0].pending_player().unwrap(), Some(0));
+
+    // the owner can't reveal a default bid for a player whose policy is to forfeit
+
+    assert!(stores[0]
+        .diff(vec![ProofAction {
+            player: None,
+            action: PlayerAction::Play(StoreAction::deserialize(&[6, 0]).unwrap()),
+        }])
+        .is_err());
+
+    timeout(&mut stores);
+
+    assert!(stores
+        .iter()
+        .all(|store| store.outcome() == Outcome::Resigned(0)
+            && store.status() == MatchStatus::Resigned(0)
+            && store.is_final()));
+}
+
+#[test]
+fn test_sealed_deadlines() {
+    let (keys, root) = new_match();
+
+    let sent: Shared<Lock<Vec<Diff<StoreState<Sealed>>>>> = Default::default();
+    let clock: Shared<Lock<Duration>> = Default::default();
+    let offline: Arc<AtomicBool> = Default::default();
+
+    let store = |player: Option<Player>| {
+        let key = keys[player.map_or(0, |player| 1 + usize::from(player))];
+        let clock = clock.clone();
+
+        let mut secrets = vec![None; 2];
+
+        if let Some(player) = player {
+            secrets[usize::from(player)] = Some((50, [player; 16]));
+        }
+
+        let mut store = StoreBuilder::new(Hooks {
+            offline: offline.clone(),
+            ..Hooks::new(key, &sent)
+        })
+        .player(player)
+        .build(&root, secrets)
+        .unwrap();
+
+        store.set_clock(move || *clock.borrow_mut());
+
+        store.set_deadlines(Deadlines {
+            reveal: Some(Duration::from_secs(30)),
+            ..Default::default()
+        });
+
+        store
+    };
+
+    let mut stores = [store(None), store(Some(0)), store(Some(1))];
+
+    stores[0].set_reveal_default(|_| 0);
+
+    // no deadline while waiting for a player to act
+
+    assert_eq!(stores[0].deadline(), None);
+
+    *clock.borrow_mut() = Duration::from_secs(10);
+
+    let diff = stores[1]
+        .diff(vec![ProofAction {
+            player: Some(0),
+            action: PlayerAction::Play(StoreAction::new(())),
+        }])
+        .unwrap();
+
+    for store in stores.iter_mut() {
+        store.apply(&diff).unwrap();
+    }
+
+    // player 1 has 30 seconds to reveal their bid
+
+    assert!(stores
+        .iter()
+        .all(|store| store.deadline() == Some(Duration::from_secs(40))));
+
+    assert!(!stores[0].poll_deadlines(Duration::from_secs(39)).unwrap());
+    assert!(!stores[2].poll_deadlines(Duration::from_secs(40)).unwrap());
+    assert!(sent.borrow_mut().is_empty());
+
+    // a timeout that fails to dispatch is retried
+
+    offline.store(true, Ordering::SeqCst);
+
+    assert!(stores[0].poll_deadlines(Duration::from_secs(40)).is_err());
+    assert_eq!(stores[0].deadline(), Some(Duration::from_secs(40)));
+
+    offline.store(false, Ordering::SeqCst);
+
+    // only the owner dispatches the timeout, and only once
+
+    assert!(stores[0].poll_deadlines(Duration::from_secs(40)).unwrap());
+    assert!(!stores[0].poll_deadlines(Duration::from_secs(41)).unwrap());
+
+    for diff in sent.borrow_mut().drain(..) {
+        for store in &mut stores[1..] {
+            store.apply(&diff).unwrap();
+        }
+    }
+
+    assert!(stores.iter().all(|store| store.hash() == stores[0].hash()));
+    assert_eq!(stores[0].state().state().state().unwrap().bids, [0, 0]);
+    assert!(stores.iter().all(|store| store.deadline().is_none()));
+}
+
+#[test]
+fn test_sealed_async() {
+    let (keys, root) = new_match();
+
+    let signed = Arc::new(AtomicUsize::new(0));
+    let offline: Arc<AtomicBool> = Default::default();
+
+    // each player reveals their own bid, signing asynchronously
+
+    let store = |player: Option<Player>| {
+        let key = keys[player.map_or(0, |player| 1 + usize::from(player))];
+        let signed = signed.clone();
+        let offline = offline.clone();
+
+        let mut secrets = vec![None; 2];
+
+        if let Some(player) = player {
+            secrets[usize::from(player)] = Some((50 + player, [player; 16]));
+        }
+
+        let hooks = AsyncHooks::new(rand::rngs::StdRng::from_seed([1; 32]));
+
+        AsyncStore::<Sealed>::new(
+            StoreBuilder::new(hooks).player(player).p2p(true),
+            &root,
+            secrets,
+            move |message| {
+                let message = message.to_vec();
+                let signed = signed.clone();
+                let offline = offline.load(Ordering::SeqCst);
+
+                Box::pin(async move {
+                    if offline {
+                        return Err("offline".to_string());
+                    }
+
+                    signed.fetch_add(1, Ordering::SeqCst);
+
+                    Secp256k1::sign(&message, &key).map_err(|error| error.to_string())
+                })
+            },
+        )
+        .unwrap()
+    };
+
+    let mut stores = [store(None), store(Some(0)), store(Some(1))];
+    let mut events: Vec<_> = stores.iter().map(AsyncStore::events).collect();
+
+    let mut drain = |store: usize| {
+        let mut diffs = Vec::new();
+        let mut ready = false;
+
+        while let Some(Some(event)) = events[store].next().now_or_never() {
+            match event {
+                StoreEvent::Send(diff) => diffs.push(diff),
+                StoreEvent::Ready(_) => ready = true,
+                StoreEvent::Log(..) => (),
+            }
+        }
+
+        (diffs, ready)
+    };
+
+    let diff = block_on(stores[1].diff(vec![ProofAction {
+        player: Some(0),
+        action: PlayerAction::Play(StoreAction::new(())),
+    }]))
+    .unwrap();
+
+    // a diff is signed twice, and each message is only signed once
+
+    assert_eq!(signed.load(Ordering::SeqCst), 2);
+
+    for store in &mut stores[..2] {
+        block_on(store.apply(&diff)).unwrap();
+    }
+
+    // a store whose signer fails is unchanged until it flushes again
+
+    offline.store(true, Ordering::SeqCst);
+
+    assert!(block_on(stores[2].apply(&diff)).is_err());
+    assert_eq!(stores[2].store().hash(), stores[0].store().hash());
+    assert!(drain(2).0.is_empty());
+
+    offline.store(false, Ordering::SeqCst);
+
+    block_on(stores[2].flush()).unwrap();
+
+    assert_eq!(signed.load(Ordering::SeqCst), 4);
+
+    // applying the play resolves only after player 1's reveal has been signed and queued
+
+    let (reveals, ready) = drain(2);
+
+    assert_eq!(reveals.len(), 1);
+    assert!(ready);
+    assert!(drain(0).0.is_empty() && drain(1).0.is_empty());
+
+    for store in &mut stores[..2] {
+        block_on(store.apply(&reveals[0])).unwrap();
+    }
+
+    assert!(stores
+        .iter()
+        .all(|store| store.store().hash() == stores[0].store().hash()));
+    assert_eq!(
+        stores[0].store().state().state().state().unwrap().bids,
+        [0, 51],
+    );
+    assert!((0..2).all(|store| drain(store).1));
+
+    // dropping the store wakes every pending stream, and ends them
+
+    struct Wake(AtomicUsize);
+
+    impl futures::task::ArcWake for Wake {
+        fn wake_by_ref(wake: &Arc<Self>) {
+            wake.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let wakes: Vec<_> = (0..2)
+        .map(|_| Arc::new(Wake(AtomicUsize::new(0))))
+        .collect();
+    let mut streams = [stores[0].events(), stores[0].events()];
+
+    for (stream, wake) in streams.iter_mut().zip(&wakes) {
+        let waker = futures::task::waker(wake.clone());
+
+        assert!(stream
+            .poll_next_unpin(&mut core::task::Context::from_waker(&waker))
+            .is_pending());
+    }
+
+    let [owner, _, _] = stores;
+
+    drop(owner);
+
+    assert!(wakes.iter().all(|wake| wake.0.load(Ordering::SeqCst) == 1));
+    assert!(block_on(events[0].next()).is_none());
+    assert!(streams
+        .iter_mut()
+        .all(|stream| block_on(stream.next()).is_none()));
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn test_sealed_threads() {
+    use std::{sync::mpsc, thread};
+
+    enum Command {
+        Play(Player),
+        Apply(Box<Diff<StoreState<Sealed>>>),
+    }
+
+    enum Message {
+        Send(usize, Box<Diff<StoreState<Sealed>>>),
+        Ready(usize, u8),
+    }
+
+    let (keys, root) = new_match();
+
+    let (messages, inbox) = mpsc::channel();
+    let mut commands = Vec::new();
+    let mut streams = Vec::new();
+    let mut stores = Vec::new();
+
+    // each store runs on its own thread, while its events are consumed on another
+
+    for (index, player) in [None, Some(0), Some(1)].iter().copied().enumerate() {
+        let key = keys[index];
+
+        let mut secrets = vec![None; 2];
+
+        if let Some(player) = player {
+            secrets[usize::from(player)] = Some((50 + player, [player; 16]));
+        }
+
+        let hooks = AsyncHooks::new(rand::rngs::StdRng::from_seed([1; 32]));
+
+        let mut store = AsyncStore::<Sealed>::new(
+            StoreBuilder::new(hooks).player(player).p2p(true),
+            &root,
+            secrets,
+            move |message| {
+                let message = message.to_vec();
+
+                Box::pin(async move {
+                    Secp256k1::sign(&message, &key).map_err(|error| error.to_string())
+                })
+            },
+        )
+        .unwrap();
+
+        let events = store.events();
+        let forward = messages.clone();
+
+        streams.push(thread::spawn(move || {
+            for event in futures::executor::block_on_stream(events) {
+                match event {
+                    StoreEvent::Send(diff) => forward.send(Message::Send(index, Box::new(diff))),
+                    StoreEvent::Ready(state) => forward.send(Message::Ready(index, state.nonce)),
+                    StoreEvent::Log(..) => Ok(()),
+                }
+                .unwrap();
+            }
+        }));
+
+        let (sender, receiver) = mpsc::channel();
+        let messages = messages.clone();
+
+        commands.push(sender);
+
+        stores.push(thread::spawn(move || {
+            for command in receiver {
+                match command {
+                    Command::Play(player) => {
+                        let diff = block_on(store.diff(vec![ProofAction {
+                            player: Some(player),
+                            action: PlayerAction::Play(StoreAction::new(())),
+                        }]))
+                        .unwrap();
+
+                        messages
+                            .send(Message::Send(index, Box::new(diff.clone())))
+                            .unwrap();
+
+                        block_on(store.apply(&diff)).unwrap();
+                    }
+                    Command::Apply(diff) => block_on(store.apply(&diff)).unwrap(),
+                }
+            }
+
+            store.store().state().state().state().unwrap().bids
+        }));
+    }
+
+    drop(messages);
+
+    // route diffs between the stores until every store is ready for the next turn
+
+    for turn in 0..2 {
+        let mut ready = [false; 3];
+
+        commands[1 + usize::from(turn)]
+            .send(Command::Play(turn))
+            .unwrap();
+
+        while !ready.iter().all(|ready| *ready) {
+            match inbox.recv().unwrap() {
+                Message::Send(sender, diff) => {
+                    for (index, commands) in commands.iter().enumerate() {
+                        if index != sender {
+                            commands.send(Command::Apply(diff.clone())).unwrap();
+                        }
+                    }
+                }
+                Message::Ready(index, nonce) => ready[index] = nonce == 1 + turn,
+            }
+        }
+    }
+
+    drop(commands);
+
+    let bids: Vec<_> = stores
+        .into_iter()
+        .map(|store| store.join().unwrap())
+        .collect();
+
+    assert_eq!(bids, [[50, 51]; 3]);
+
+    // dropping the stores ends their streams
+
+    for stream in streams {
+        stream.join().unwrap();
+    }
+}
@@ -27,10 +27,14 @@ use {
         vec::Vec,
     },
     arcadeum::{
-        crypto::{sign, Addressable, SecretKey},
-        Player, PlayerAction, Proof, ProofAction, ProofState, RootProof, State,
+        crypto::{sign, Addressable, Secp256k1, SecretKey},
+        inspect::{inspect, Context, Kind},
+        settlement::{self, Settlement},
+        verify_series, Diff, DiffRef, EquivocationEvidence, Error, Format, Link, MatchResult,
+        MatchStatus, Offence, Outcome, Player, PlayerAction, Proof, ProofAction, ProofArchive,
+        ProofState, RootProof, SlashingEvidence, State, Transcript,
     },
-    core::convert::TryInto,
+    core::{convert::TryInto, mem::size_of},
     libsecp256k1_rand::{rngs::StdRng, RngCore, SeedableRng},
 };
 
@@ -57,6 +61,7 @@ impl State for TTT {
     type ID = [u8; 16];
     type Nonce = u8;
     type Action = Action;
+    type Scheme = arcadeum::crypto::Secp256k1;
 
     fn version() -> &'static [u8] {
         "TTT".as_bytes()
@@ -142,6 +147,33 @@ impl State for TTT {
 
         Ok(())
     }
+
+    fn outcome(&self) -> Outcome {
+        let lines = [
+            [(0, 0), (0, 1), (0, 2)],
+            [(1, 0), (1, 1), (1, 2)],
+            [(2, 0), (2, 1), (2, 2)],
+            [(0, 0), (1, 0), (2, 0)],
+            [(0, 1), (1, 1), (2, 1)],
+            [(0, 2), (1, 2), (2, 2)],
+            [(0, 0), (1, 1), (2, 2)],
+            [(0, 2), (1, 1), (2, 0)],
+        ];
+
+        for line in &lines {
+            if let [Some(a), Some(b), Some(c)] = line.map(|(i, j)| self.board[i][j]) {
+                if a == b && b == c {
+                    return Outcome::Winner(a);
+                }
+            }
+        }
+
+        if self.nonce == 9 {
+            Outcome::Draw
+        } else {
+            Outcome::InProgress
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -256,13 +288,7 @@ fn test_ttt() {
     let mut id = <TTT as State>::ID::default();
     randoms[0].try_fill_bytes(&mut id).unwrap();
 
-    let players = keys[1..]
-        .iter()
-        .map(Addressable::address)
-        .collect::<Vec<_>>()
-        .as_slice()
-        .try_into()
-        .unwrap();
+    let players = keys[1..].iter().map(Addressable::address).collect();
 
     let state = ProofState::<Box<TTT>>::new(id, players, Default::default()).unwrap();
 
@@ -301,6 +327,7 @@ fn test_ttt() {
             action: PlayerAction::Certify {
                 address,
                 signature: sign(TTT::challenge(&address).as_bytes(), key),
+                expiry: None,
             },
         };
 
@@ -339,9 +366,12 @@ fn test_ttt() {
 
         println!("diff = {:?}\nproof = {}\n", diff, hex(&data));
 
+        assert_eq!(proof.serialized_size(), data.len());
+
         assert_eq!(data, {
             let mut proof = Proof::new(root.clone());
             proof.deserialize(&data, false).unwrap();
+            assert_eq!(proof.serialized_size(), data.len());
             proof.serialize()
         });
     };
@@ -353,4 +383,1711 @@ fn test_ttt() {
     apply(0, Action(2, 0));
     apply(1, Action(1, 0));
     apply(0, Action(2, 1));
+
+    let data = proof.serialize();
+
+    let verification = arcadeum::verify::<Box<TTT>>(&root.serialize(), &data, false).unwrap();
+
+    assert_eq!(verification.hash(), proof.hash());
+    assert_eq!(
+        verification.state().state().board,
+        proof.state().state().board
+    );
+    assert!(!verification.attestations().is_empty());
+
+    for attestation in verification.attestations() {
+        match attestation.player() {
+            None => assert_eq!(attestation.signer(), &keys[0].address()),
+            Some(player) => assert_eq!(
+                attestation.signer(),
+                &subkeys[usize::from(player)].address()
+            ),
+        }
+
+        assert!(attestation.actions().end <= verification.action_count());
+    }
+
+    let mut data = data;
+    let last = data.len() - 1;
+
+    data[last] ^= 1;
+
+    assert!(arcadeum::verify::<Box<TTT>>(&root.serialize(), &data, false).is_err());
+}
+
+#[test]
+fn test_ttt_errors() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, _) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let mut data = root.serialize();
+
+    // the state begins with its layout tag and version
+
+    data[size_of::<u32>() + 5 + size_of::<u32>() + "TT".len()] = b'X';
+
+    if cfg!(not(feature = "no-version-check")) {
+        assert_eq!(
+            RootProof::<Box<TTT>>::deserialize(&data, false).err(),
+            Some(Error::VersionMismatch {
+                expected: b"TTT".to_vec(),
+                actual: b"TTX".to_vec(),
+            }),
+        );
+    }
+
+    assert!(matches!(
+        Diff::<Box<TTT>>::deserialize(&[], root.state().id()),
+        Err(Error::Decode { .. })
+    ));
+
+    let mut proof = Proof::new(root);
+
+    let diff = |proof: &Proof<Box<TTT>>, player: Player, action| {
+        proof.diff(
+            vec![ProofAction {
+                player: Some(player),
+                action: PlayerAction::Play(action),
+            }],
+            &mut |message| Ok(sign(message, &keys[1 + usize::from(player)])),
+        )
+    };
+
+    assert!(matches!(
+        diff(&proof, 1, Action(0, 0)),
+        Err(Error::Rejected {
+            player: Some(1),
+            ..
+        })
+    ));
+
+    let first = diff(&proof, 0, Action(0, 0)).unwrap();
+
+    proof.apply(&first).unwrap();
+
+    assert_eq!(
+        proof.apply(&first),
+        Err(Error::WrongProofHash {
+            expected: *proof.hash(),
+            actual: *first.proof(),
+        }),
+    );
+
+    let error = proof
+        .diff(
+            vec![ProofAction {
+                player: Some(1),
+                action: PlayerAction::Play(Action(1, 1)),
+            }],
+            &mut |message| Ok(sign(message, &keys[1])),
+        )
+        .unwrap_err();
+
+    assert_eq!(error.kind(), "forbidden");
+    assert!(!error.is_slashable());
+}
+
+#[test]
+fn test_ttt_slashing_evidence() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, _) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let mut proof = Proof::new(root);
+
+    let diff = |proof: &Proof<Box<TTT>>, player: Player, action| {
+        proof
+            .diff(
+                vec![ProofAction {
+                    player: Some(player),
+                    action: PlayerAction::Play(action),
+                }],
+                &mut |message| Ok(sign(message, &keys[1 + usize::from(player)])),
+            )
+            .unwrap()
+    };
+
+    let first = diff(&proof, 0, Action(0, 0));
+
+    assert_eq!(SlashingEvidence::new(&proof, &first), None);
+
+    proof.apply(&first).unwrap();
+
+    let second = diff(&proof, 1, Action(1, 1));
+
+    // player 1 re-signs their diff with player 0's proof signature
+
+    let mut data = second.serialize();
+    let signatures = data.len() - 2 * size_of::<arcadeum::crypto::Signature>();
+    let signature = data.len() - size_of::<arcadeum::crypto::Signature>();
+
+    data[signatures..signature].copy_from_slice(&first.serialize()[signatures..signature]);
+
+    let forged = sign(&data[..signature], &keys[2]);
+
+    data[signature..].copy_from_slice(&forged);
+
+    let forged = Diff::<Box<TTT>>::deserialize(&data, proof.state().id()).unwrap();
+
+    let error = proof.clone().apply(&forged).unwrap_err();
+
+    assert!(error.is_slashable());
+
+    let evidence = SlashingEvidence::new(&proof, &forged).unwrap();
+
+    assert_eq!(evidence.offender(), &keys[2].address());
+    match error {
+        Error::Slashable { author, rule, .. } => {
+            assert_eq!(author, keys[2].address());
+            assert_eq!(rule, Offence::ProofSignature);
+            assert_eq!(rule, evidence.rule());
+        }
+        _ => unreachable!(),
+    }
+
+    evidence.verify::<Box<TTT>>(false).unwrap();
+
+    let data = evidence.serialize();
+
+    assert_eq!(SlashingEvidence::deserialize(&data).unwrap(), evidence);
+
+    SlashingEvidence::deserialize(&data)
+        .unwrap()
+        .verify::<Box<TTT>>(false)
+        .unwrap();
+
+    // evidence of a different offence doesn't verify
+
+    let mut tampered = data.clone();
+
+    *tampered.last_mut().unwrap() = 2;
+
+    let tampered = SlashingEvidence::deserialize(&tampered).unwrap();
+
+    assert_eq!(tampered.rule(), Offence::InvalidAction);
+    assert_eq!(
+        tampered.verify::<Box<TTT>>(false).unwrap_err().kind(),
+        "forbidden"
+    );
+
+    assert!(SlashingEvidence::deserialize(&[&data[..], &[0]].concat()).is_err());
+
+    assert!(SlashingEvidence::deserialize(&data[..data.len() / 2]).is_err());
+
+    proof.apply(&second).unwrap();
+}
+
+#[test]
+fn test_ttt_equivocation_evidence() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, _) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let mut proof = Proof::new(root);
+
+    let diff = |proof: &Proof<Box<TTT>>, player: Player, action| {
+        proof
+            .diff(
+                vec![ProofAction {
+                    player: Some(player),
+                    action: PlayerAction::Play(action),
+                }],
+                &mut |message| Ok(sign(message, &keys[1 + usize::from(player)])),
+            )
+            .unwrap()
+    };
+
+    let first = diff(&proof, 0, Action(0, 0));
+    let second = diff(&proof, 0, Action(1, 1));
+
+    assert_eq!(first.author(), &keys[1].address());
+    assert_eq!(EquivocationEvidence::new(&first, &first), None);
+
+    let evidence = EquivocationEvidence::new(&first, &second).unwrap();
+
+    assert_eq!(evidence.offender(), &keys[1].address());
+
+    evidence.verify::<Box<TTT>>().unwrap();
+
+    let data = evidence.serialize();
+
+    assert_eq!(EquivocationEvidence::deserialize(&data).unwrap(), evidence);
+    assert!(EquivocationEvidence::deserialize(&data[..data.len() - 1]).is_err());
+
+    proof.apply(&first).unwrap();
+
+    let third = diff(&proof, 1, Action(1, 1));
+
+    assert_eq!(EquivocationEvidence::new(&first, &third), None);
+
+    let mut data = data;
+    let offender = data.len() - keys[2].address().len();
+
+    data[offender..].copy_from_slice(&keys[2].address());
+
+    assert_eq!(
+        EquivocationEvidence::deserialize(&data)
+            .unwrap()
+            .verify::<Box<TTT>>()
+            .unwrap_err()
+            .kind(),
+        "forbidden"
+    );
+
+    // the same diff encoded in both formats isn't an equivocation
+
+    let mut data = Vec::new();
+
+    for bytes in [
+        &arcadeum::ID::serialize(first.id())[..],
+        &first.serialize(),
+        &first.serialize_with_format(Format::V2),
+        &keys[1].address(),
+    ]
+    .iter()
+    {
+        data.extend(&(bytes.len() as u32).to_le_bytes());
+        data.extend(bytes.iter());
+    }
+
+    assert_eq!(
+        EquivocationEvidence::deserialize(&data)
+            .unwrap()
+            .verify::<Box<TTT>>()
+            .unwrap_err()
+            .kind(),
+        "forbidden"
+    );
+}
+
+#[test]
+fn test_ttt_transcript() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, _) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let mut archive = ProofArchive::new(root.clone());
+
+    for (i, action) in [Action(0, 0), Action(1, 1), Action(2, 2), Action(0, 2)]
+        .iter()
+        .enumerate()
+    {
+        let player = (i % 2).try_into().unwrap();
+
+        let diff = archive
+            .proof()
+            .diff(
+                vec![ProofAction {
+                    player: Some(player),
+                    action: PlayerAction::Play(action.clone()),
+                }],
+                &mut |message| Ok(sign(message, &keys[1 + usize::from(player)])),
+            )
+            .unwrap();
+
+        archive.apply(&diff).unwrap();
+    }
+
+    assert_eq!(archive.diffs().len(), 4);
+
+    let transcript = archive.transcript();
+
+    assert_eq!(transcript.actions().count(), 4);
+    assert!(
+        arcadeum::verify::<Box<TTT>>(&root.serialize(), &archive.proof().serialize(), false)
+            .unwrap()
+            .action_count()
+            < 4
+    );
+
+    let data = transcript.serialize();
+
+    let transcript = Transcript::<Box<TTT>>::deserialize(&data, false).unwrap();
+
+    assert_eq!(transcript.serialize(), data);
+    assert_eq!(transcript.root().hash(), root.hash());
+
+    let proof = transcript.replay().unwrap();
+
+    assert_eq!(proof.hash(), archive.proof().hash());
+    assert_eq!(
+        proof.state().state().board,
+        archive.proof().state().state().board
+    );
+
+    assert!(Transcript::<Box<TTT>>::deserialize(&data[..data.len() - 1], false).is_err());
+}
+
+#[test]
+fn test_ttt_subkey_revocation() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, subkeys) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let mut proof = Proof::new(root.clone());
+
+    let apply = |proof: &mut Proof<Box<TTT>>, player, action, key: &SecretKey| {
+        let diff = proof.diff(vec![ProofAction { player, action }], &mut |message| {
+            Ok(sign(message, key))
+        })?;
+
+        proof.apply(&diff)?;
+
+        let data = proof.serialize();
+
+        assert_eq!(data, {
+            let mut proof = Proof::new(root.clone());
+            proof.deserialize(&data, false).unwrap();
+            proof.serialize()
+        });
+
+        Ok::<_, Error>(())
+    };
+
+    let certify =
+        |proof: &Proof<Box<TTT>>, subkey: &SecretKey, expiry, key| PlayerAction::Certify {
+            address: subkey.address(),
+            signature: sign(&proof.state().challenge(&subkey.address(), expiry), key),
+            expiry: expiry.cloned(),
+        };
+
+    // player 0's subkey is valid until the nonce exceeds 3
+
+    let action = certify(&proof, &subkeys[0], Some(&3), &keys[1]);
+    apply(&mut proof, Some(0), action, &keys[1]).unwrap();
+
+    let action = certify(&proof, &subkeys[1], None, &keys[2]);
+    apply(&mut proof, Some(1), action, &keys[2]).unwrap();
+
+    let owner = keys[0].address();
+
+    assert_eq!(proof.state().player(&subkeys[0].address(), &owner), Some(0));
+    assert_eq!(proof.state().player(&subkeys[1].address(), &owner), Some(1));
+
+    apply(
+        &mut proof,
+        Some(0),
+        PlayerAction::Play(Action(0, 0)),
+        &subkeys[0],
+    )
+    .unwrap();
+
+    assert_eq!(proof.state().nonce(), &3);
+    assert_eq!(proof.state().player(&subkeys[0].address(), &owner), Some(0));
+
+    // player 1 revokes their subkey with their own key
+
+    assert!(apply(
+        &mut proof,
+        Some(0),
+        PlayerAction::Revoke {
+            subkey: subkeys[1].address(),
+        },
+        &keys[1],
+    )
+    .is_err());
+
+    apply(
+        &mut proof,
+        Some(1),
+        PlayerAction::Revoke {
+            subkey: subkeys[1].address(),
+        },
+        &keys[2],
+    )
+    .unwrap();
+
+    assert_eq!(proof.state().player(&subkeys[0].address(), &owner), None);
+    assert_eq!(proof.state().player(&subkeys[1].address(), &owner), None);
+
+    assert!(apply(
+        &mut proof,
+        Some(1),
+        PlayerAction::Play(Action(1, 1)),
+        &subkeys[1],
+    )
+    .is_err());
+
+    let action = certify(&proof, &subkeys[1], None, &keys[2]);
+    assert!(apply(&mut proof, Some(1), action, &keys[2]).is_err());
+
+    apply(
+        &mut proof,
+        Some(1),
+        PlayerAction::Play(Action(1, 1)),
+        &keys[2],
+    )
+    .unwrap();
+
+    // the owner revokes an approved subkey
+
+    let player = keys[2].address();
+    let subkey = [1; 20];
+    let signature = sign(&proof.state().approval(&player, &subkey), &keys[0]);
+
+    apply(
+        &mut proof,
+        None,
+        PlayerAction::Approve {
+            player,
+            subkey,
+            signature,
+        },
+        &keys[0],
+    )
+    .unwrap();
+
+    assert_eq!(proof.state().player(&subkey, &owner), Some(1));
+
+    apply(&mut proof, None, PlayerAction::Revoke { subkey }, &keys[0]).unwrap();
+
+    assert_eq!(proof.state().player(&subkey, &owner), None);
+}
+
+#[test]
+fn test_ttt_match_status() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, _) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let apply = |proof: &mut Proof<Box<TTT>>, player: Player, action| {
+        let diff = proof.diff(
+            vec![ProofAction {
+                player: Some(player),
+                action,
+            }],
+            &mut |message| Ok(sign(message, &keys[1 + usize::from(player)])),
+        )?;
+
+        proof.apply(&diff)?;
+
+        let data = proof.serialize();
+
+        assert_eq!(data, {
+            let mut proof = Proof::new(root.clone());
+            proof.deserialize(&data, false).unwrap();
+            proof.serialize()
+        });
+
+        Ok::<_, Error>(())
+    };
+
+    // draw offers lapse on the next move
+
+    let mut proof = Proof::new(root.clone());
+
+    apply(&mut proof, 0, PlayerAction::Play(Action(0, 0))).unwrap();
+    apply(&mut proof, 0, PlayerAction::OfferDraw).unwrap();
+
+    assert!(proof.state().is_draw_offered(0));
+
+    apply(&mut proof, 1, PlayerAction::Play(Action(1, 1))).unwrap();
+
+    assert!(!proof.state().is_draw_offered(0));
+    assert!(apply(&mut proof, 1, PlayerAction::AcceptDraw).is_err());
+
+    apply(&mut proof, 0, PlayerAction::OfferDraw).unwrap();
+    apply(&mut proof, 1, PlayerAction::AcceptDraw).unwrap();
+
+    assert_eq!(proof.state().status(), MatchStatus::Drawn);
+
+    assert_eq!(
+        apply(&mut proof, 0, PlayerAction::Play(Action(2, 2))),
+        Err(Error::Terminated {
+            status: MatchStatus::Drawn,
+        })
+    );
+
+    // a resignation ends the match immediately
+
+    let mut proof = Proof::new(root.clone());
+
+    apply(&mut proof, 1, PlayerAction::Resign).unwrap();
+
+    assert_eq!(proof.state().status(), MatchStatus::Resigned(1));
+    assert!(apply(&mut proof, 0, PlayerAction::Resign).is_err());
+
+    // the other player can still co-sign the final state, and the owner can checkpoint it
+
+    assert_eq!(
+        apply(&mut proof, 0, PlayerAction::Play(Action(0, 0))),
+        Err(Error::Terminated {
+            status: MatchStatus::Resigned(1),
+        })
+    );
+
+    let diff = proof
+        .diff(Vec::new(), &mut |message| Ok(sign(message, &keys[1])))
+        .unwrap();
+
+    proof.apply(&diff).unwrap();
+
+    assert!(proof.can_checkpoint());
+
+    let checkpoint = proof
+        .checkpoint(&mut |message| Ok(sign(message, &keys[0])))
+        .unwrap();
+
+    proof.apply(&checkpoint).unwrap();
+
+    assert_eq!(proof.state().status(), MatchStatus::Resigned(1));
+
+    // aborts require every player
+
+    let mut proof = Proof::new(root.clone());
+
+    apply(&mut proof, 0, PlayerAction::Abort).unwrap();
+
+    assert!(proof.state().is_abort_requested(0));
+    assert!(apply(&mut proof, 0, PlayerAction::Abort).is_err());
+    assert_eq!(proof.state().status(), MatchStatus::InProgress);
+
+    apply(&mut proof, 1, PlayerAction::Abort).unwrap();
+
+    assert_eq!(proof.state().status(), MatchStatus::Aborted);
+}
+
+#[test]
+fn test_ttt_outcome() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, subkeys) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let mut proof = Proof::new(root);
+
+    let apply = |proof: &mut Proof<Box<TTT>>, player: Player, action| {
+        let diff = proof.diff(
+            vec![ProofAction {
+                player: Some(player),
+                action,
+            }],
+            &mut |message| Ok(sign(message, &keys[1 + usize::from(player)])),
+        )?;
+
+        proof.apply(&diff)
+    };
+
+    assert!(proof
+        .result(&mut |message| Ok(sign(message, &keys[0])))
+        .is_err());
+
+    for (player, i, j) in [(0, 0, 0), (1, 1, 0), (0, 0, 1), (1, 1, 1)] {
+        apply(&mut proof, player, PlayerAction::Play(Action(i, j))).unwrap();
+
+        assert_eq!(proof.outcome(), Outcome::InProgress);
+    }
+
+    apply(&mut proof, 0, PlayerAction::Play(Action(0, 2))).unwrap();
+
+    assert!(proof.is_final());
+    assert_eq!(proof.outcome(), Outcome::Winner(0));
+    assert_eq!(proof.state().status(), MatchStatus::Won(0));
+
+    assert!(apply(&mut proof, 1, PlayerAction::Play(Action(1, 2))).is_err());
+    assert!(apply(&mut proof, 1, PlayerAction::Resign).is_err());
+
+    let result = proof
+        .result(&mut |message| Ok(sign(message, &keys[1])))
+        .unwrap();
+
+    let data = result.serialize();
+
+    println!("result = {}", hex(&data));
+
+    let result = MatchResult::<Box<TTT>>::deserialize(&data).unwrap();
+
+    assert_eq!(result.id(), proof.state().id());
+    assert_eq!(result.proof(), proof.hash());
+    assert_eq!(result.outcome(), Outcome::Winner(0));
+    assert_eq!(result.author(), &keys[1].address());
+    assert!(result.verify(&proof).is_ok());
+
+    // only the owner and the players can sign results
+
+    assert!(proof
+        .result(&mut |message| Ok(sign(message, &subkeys[0])))
+        .is_err());
+}
+
+fn settled_proof() -> ([SecretKey; 3], Proof<Box<TTT>>) {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, _) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let mut proof = Proof::new(root);
+
+    for (player, i, j) in [(0, 1, 1), (1, 0, 0), (0, 1, 0), (1, 2, 0), (0, 1, 2)] {
+        assert!(Settlement::new(&proof).is_err());
+
+        let diff = proof
+            .diff(
+                vec![ProofAction {
+                    player: Some(player),
+                    action: PlayerAction::Play(Action(i, j)),
+                }],
+                &mut |message| Ok(sign(message, &keys[1 + usize::from(player)])),
+            )
+            .unwrap();
+
+        proof.apply(&diff).unwrap();
+    }
+
+    assert_eq!(proof.outcome(), Outcome::Winner(0));
+
+    (keys, proof)
+}
+
+/// Settlements tampered with, and the reason [settlement::VERIFIER] reverts, if it does
+fn tampered_settlements(data: &[u8]) -> Vec<(Vec<u8>, Option<&'static str>)> {
+    let word = |offset: usize| {
+        u32::from_be_bytes(data[offset + 28..offset + 32].try_into().unwrap()) as usize
+    };
+
+    // the tuple of the last range, and its signature
+
+    let ranges = word(2 * 32);
+    let range = ranges + 32 + word(ranges + 32 * word(ranges));
+    let v = range + word(range + 3 * 32) + 32 + 64;
+
+    let mut state = data.to_vec();
+    state[4 * 32] ^= 1;
+
+    let mut recovery = data.to_vec();
+    recovery[v] -= 27;
+
+    let mut signature = data.to_vec();
+    signature[v] = 29;
+
+    let mut end = data.to_vec();
+    end[range + 2 * 32 - 1] = 0xff;
+
+    vec![
+        (state, None),
+        (recovery, Some("signature")),
+        (signature, Some("signature")),
+        (end, Some("range")),
+    ]
+}
+
+/// Settlements only accept signatures with recovery IDs 27 and 28.
+#[cfg(not(feature = "no-crypto"))]
+#[test]
+fn test_ttt_settlement() {
+    let (keys, proof) = settled_proof();
+
+    let data = Settlement::new(&proof).unwrap().encode();
+
+    println!("settlement = {}", hex(&data));
+
+    // abi.encode(bytes, bytes[], Range[]) starts with three offsets
+
+    assert_eq!(data[..32], [[0; 31].as_ref(), &[0x60]].concat()[..]);
+
+    let (hash, signers) = settlement::verify(&data).unwrap();
+
+    assert_eq!(&hash, proof.hash());
+    assert_eq!(
+        signers,
+        [None, Some(keys[1].address()), Some(keys[2].address())]
+    );
+
+    for (tampered, reason) in tampered_settlements(&data) {
+        match settlement::verify(&tampered) {
+            Ok((tampered_hash, tampered_signers)) => {
+                assert_eq!(reason, None);
+
+                // the head state is signed
+
+                assert_ne!(&tampered_hash, proof.hash());
+                assert_eq!(tampered_signers.len(), signers.len());
+            }
+            Err(error) => {
+                assert!(reason.is_some(), "{:?}", error);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+#[ignore = "requires solc, run by the verifier workflow"]
+fn test_ttt_settlement_evm() {
+    let (_, proof) = settled_proof();
+
+    let data = Settlement::new(&proof).unwrap().encode();
+
+    let verifier = evm::Verifier::compile();
+
+    // absent signers are the zero address
+
+    let expected = |data: &[u8]| {
+        settlement::verify(data).map(|(hash, signers)| {
+            (
+                hash,
+                signers
+                    .into_iter()
+                    .map(Option::unwrap_or_default)
+                    .collect::<Vec<_>>(),
+            )
+        })
+    };
+
+    assert_eq!(verifier.verify(&data), Ok(expected(&data).unwrap()));
+    assert_eq!(&verifier.verify(&data).unwrap().0, proof.hash());
+
+    for (tampered, reason) in tampered_settlements(&data) {
+        match reason {
+            None => assert_eq!(verifier.verify(&tampered), Ok(expected(&tampered).unwrap())),
+            Some(reason) => {
+                assert!(expected(&tampered).is_err());
+                assert_eq!(verifier.verify(&tampered), Err(reason.to_string()));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_ttt_series() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, _) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let play = |predecessors: Vec<Link>, seats: [usize; 2], moves: &[(Player, usize, usize)]| {
+        let players = seats.iter().map(|&seat| keys[seat].address()).collect();
+
+        let state = ProofState::<Box<TTT>>::with_predecessors(
+            [predecessors.len() as u8; 16],
+            players,
+            Default::default(),
+            predecessors,
+        )
+        .unwrap();
+
+        let root = RootProof::new(state, Vec::new(), &mut |message| {
+            Ok(sign(message, &keys[0]))
+        })
+        .unwrap();
+
+        let mut proof = Proof::new(root.clone());
+
+        for &(player, i, j) in moves {
+            let diff = proof
+                .diff(
+                    vec![ProofAction {
+                        player: Some(player),
+                        action: PlayerAction::Play(Action(i, j)),
+                    }],
+                    &mut |message| Ok(sign(message, &keys[seats[usize::from(player)]])),
+                )
+                .unwrap();
+
+            proof.apply(&diff).unwrap();
+        }
+
+        (root, proof)
+    };
+
+    // the first game is won by the player in seat 0
+
+    let (first_root, first) = play(
+        Vec::new(),
+        [1, 2],
+        &[(0, 0, 0), (1, 1, 0), (0, 0, 1), (1, 1, 1), (0, 0, 2)],
+    );
+
+    assert!(Link::new(&first).is_ok());
+
+    // the rematch swaps seats, and is won by the same player
+
+    let (second_root, second) = play(
+        vec![Link::new(&first).unwrap()],
+        [2, 1],
+        &[
+            (0, 0, 0),
+            (1, 1, 0),
+            (0, 0, 1),
+            (1, 1, 1),
+            (0, 2, 2),
+            (1, 1, 2),
+        ],
+    );
+
+    assert_eq!(second.state().predecessors(), &[Link::new(&first).unwrap()]);
+    assert!(Link::new(&Proof::new(second_root.clone())).is_err());
+
+    let matches = [
+        (first_root.serialize(), first.serialize()),
+        (second_root.serialize(), second.serialize()),
+    ];
+
+    let matches: Vec<_> = matches
+        .iter()
+        .map(|(root, proof)| (root.as_slice(), proof.as_slice()))
+        .collect();
+
+    let series = verify_series::<Box<TTT>>(&matches, false).unwrap();
+
+    assert_eq!(series.outcomes(), [Outcome::Winner(0), Outcome::Winner(1)]);
+
+    assert_eq!(series.wins(&keys[1].address()), 2);
+    assert_eq!(series.wins(&keys[2].address()), 0);
+
+    // an unlinked match breaks the series
+
+    let (third_root, third) = play(Vec::new(), [1, 2], &[(0, 1, 1)]);
+
+    let third = (third_root.serialize(), third.serialize());
+
+    assert!(verify_series::<Box<TTT>>(
+        &[matches[1], (third.0.as_slice(), third.1.as_slice())],
+        false
+    )
+    .is_err());
+
+    assert!(verify_series::<Box<TTT>>(&[matches[1], matches[0]], false).is_err());
+}
+
+#[test]
+fn test_ttt_checkpoint() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, _) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let mut proof = Proof::new(root);
+
+    let play = |proof: &Proof<Box<TTT>>, player: Player, i, j| {
+        proof
+            .diff(
+                vec![ProofAction {
+                    player: Some(player),
+                    action: PlayerAction::Play(Action(i, j)),
+                }],
+                &mut |message| Ok(sign(message, &keys[1 + usize::from(player)])),
+            )
+            .unwrap()
+    };
+
+    proof.apply(&play(&proof, 0, 1, 1)).unwrap();
+
+    // every player must sign before the owner can checkpoint
+
+    assert!(!proof.can_checkpoint());
+    assert!(proof
+        .checkpoint(&mut |message| Ok(sign(message, &keys[0])))
+        .is_err());
+
+    let checkpoint = |proof: &Proof<Box<TTT>>, signatures| {
+        proof
+            .diff(
+                vec![ProofAction {
+                    player: None,
+                    action: PlayerAction::Checkpoint { signatures },
+                }],
+                &mut |message| Ok(sign(message, &keys[0])),
+            )
+            .unwrap()
+    };
+
+    let early = checkpoint(&proof, Default::default());
+
+    assert!(early.is_checkpoint());
+    assert!(matches!(
+        proof.clone().apply(&early),
+        Err(Error::Slashable {
+            rule: Offence::Checkpoint,
+            ..
+        })
+    ));
+
+    // an owner diff without actions isn't a checkpoint
+
+    let empty = proof
+        .diff(Vec::new(), &mut |message| Ok(sign(message, &keys[0])))
+        .unwrap();
+
+    assert!(!empty.is_checkpoint());
+    assert!(proof.clone().apply(&empty).is_ok());
+
+    proof.apply(&play(&proof, 1, 0, 0)).unwrap();
+
+    assert!(proof.can_checkpoint());
+    assert!(proof
+        .checkpoint(&mut |message| Ok(sign(message, &keys[1])))
+        .is_err());
+
+    // the checkpoint must commit to the players' signatures
+
+    assert!(matches!(
+        proof.clone().apply(&checkpoint(&proof, Default::default())),
+        Err(Error::Slashable {
+            rule: Offence::Checkpoint,
+            ..
+        })
+    ));
+
+    let checkpoint = proof
+        .checkpoint(&mut |message| Ok(sign(message, &keys[0])))
+        .unwrap();
+
+    proof.apply(&checkpoint).unwrap();
+
+    // the checkpoint carries the players' signatures, so this short history doesn't shrink
+
+    assert_eq!(proof.serialized_size(), proof.serialize().len());
+    assert!(!proof.can_checkpoint());
+
+    // peers can rebase onto an equivalent root proof
+
+    let mut rebased = Proof::new(proof.checkpoint_root().unwrap());
+
+    assert_eq!(rebased.hash(), proof.hash());
+
+    for (player, i, j) in [(0, 2, 2), (1, 0, 2)] {
+        let diff = play(&proof, player, i, j);
+
+        proof.apply(&diff).unwrap();
+        rebased.apply(&diff).unwrap();
+
+        assert_eq!(rebased.hash(), proof.hash());
+    }
+
+    assert!(proof.checkpoint_root().is_none());
+}
+
+#[test]
+fn test_ttt_diff_ref() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, _) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let proof = Proof::new(root);
+
+    let diff = proof
+        .diff(
+            vec![
+                ProofAction {
+                    player: Some(0),
+                    action: PlayerAction::Play(Action(1, 1)),
+                },
+                ProofAction {
+                    player: Some(0),
+                    action: PlayerAction::OfferDraw,
+                },
+            ],
+            &mut |message| Ok(sign(message, &keys[1])),
+        )
+        .unwrap();
+
+    let data = diff.serialize();
+
+    let view = DiffRef::<Box<TTT>>::new(&data).unwrap();
+
+    assert_eq!(view.proof(), proof.hash());
+    assert_eq!(view.action_count(), 2);
+    assert_eq!(
+        view.actions()
+            .map(|action| (action.player(), action.is_play()))
+            .collect::<Vec<_>>(),
+        [(Some(0), true), (Some(0), false)]
+    );
+    assert_eq!(view.author(proof.state().id()).unwrap(), *diff.author());
+    assert_eq!(view.decode(proof.state().id()).unwrap().serialize(), data);
+
+    let compact = diff.serialize_with_format(Format::V2);
+    let view = DiffRef::<Box<TTT>>::new(&compact).unwrap();
+
+    assert_eq!(view.proof(), proof.hash());
+    assert_eq!(
+        view.actions()
+            .map(|action| (action.player(), action.is_play(), action.decode().is_ok()))
+            .collect::<Vec<_>>(),
+        [(Some(0), true, true), (Some(0), false, true)]
+    );
+    assert_eq!(view.author(proof.state().id()).unwrap(), *diff.author());
+    assert_eq!(view.decode(proof.state().id()).unwrap().serialize(), data);
+    assert!(DiffRef::<Box<TTT>>::new(&compact[..compact.len() - 1]).is_err());
+
+    // the framing is checked eagerly, signatures lazily
+
+    assert!(DiffRef::<Box<TTT>>::new(&data[..data.len() - 1]).is_err());
+    assert!(DiffRef::<Box<TTT>>::new(&[data.as_slice(), &[0]].concat()).is_err());
+
+    let mut tampered = data.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+
+    let view = DiffRef::<Box<TTT>>::new(&tampered).unwrap();
+
+    assert_ne!(view.author(proof.state().id()).ok(), Some(*diff.author()));
+}
+
+#[test]
+fn test_ttt_format() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, _) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let mut proof = Proof::new(root.clone());
+
+    for (i, (x, y)) in [(1, 1), (0, 0), (2, 2)].iter().enumerate() {
+        let player = (i % 2).try_into().unwrap();
+
+        let diff = proof
+            .diff(
+                vec![ProofAction {
+                    player: Some(player),
+                    action: PlayerAction::Play(Action(*x, *y)),
+                }],
+                &mut |message| Ok(sign(message, &keys[1 + usize::from(player)])),
+            )
+            .unwrap();
+
+        let v1 = diff.serialize();
+        let v2 = diff.serialize_with_format(Format::V2);
+
+        assert_eq!(Format::detect(&v1), Format::V1);
+        assert_eq!(Format::detect(&v2), Format::V2);
+        assert!(v2.len() < v1.len());
+
+        // both formats decode to the same diff, signed over the canonical encoding
+
+        let decoded = Diff::<Box<TTT>>::deserialize(&v2, proof.state().id()).unwrap();
+
+        assert_eq!(decoded.serialize(), v1);
+        assert_eq!(decoded.author(), diff.author());
+
+        proof.apply(&decoded).unwrap();
+    }
+
+    // a V1 diff whose proof hash begins with the V2 tag still decodes as V1
+
+    let mut data = proof
+        .diff(
+            vec![ProofAction {
+                player: Some(1),
+                action: PlayerAction::Play(Action(0, 1)),
+            }],
+            &mut |message| Ok(sign(message, &keys[2])),
+        )
+        .unwrap()
+        .serialize();
+
+    data[..4].copy_from_slice(&[0xff, 0xff, 0xff, 0x02]);
+
+    assert_eq!(Format::detect(&data), Format::V2);
+
+    let decoded = Diff::<Box<TTT>>::deserialize(&data, proof.state().id()).unwrap();
+
+    assert_eq!(decoded.serialize(), data);
+    assert_eq!(decoded.proof()[..4], [0xff, 0xff, 0xff, 0x02]);
+    assert!(proof.apply(&decoded).is_err());
+
+    let v1 = proof.serialize();
+    let v2 = proof.serialize_with_format(Format::V2);
+
+    assert!(v2.len() < v1.len());
+
+    for data in [&v1, &v2] {
+        let mut decoded = Proof::new(root.clone());
+
+        decoded.deserialize(data, false).unwrap();
+
+        assert_eq!(decoded.hash(), proof.hash());
+    }
+
+    let data = root.serialize_with_format(Format::V2);
+
+    assert_eq!(
+        RootProof::<Box<TTT>>::deserialize(&data, false)
+            .unwrap()
+            .hash(),
+        root.hash()
+    );
+    assert_eq!(
+        RootProof::<Box<TTT>>::version(&data).unwrap(),
+        RootProof::<Box<TTT>>::version(&root.serialize()).unwrap()
+    );
+
+    // peers fall back to the newest format they both support
+
+    assert_eq!(Format::negotiate(&Format::SUPPORTED), Format::V2);
+    assert_eq!(Format::negotiate(&[Format::V1]), Format::V1);
+    assert_eq!(Format::negotiate(&[]), Format::V1);
+}
+
+#[cfg(feature = "serde-proofs")]
+#[test]
+fn test_ttt_serde() {
+    use serde_cbor::Value;
+
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, _) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let mut proof = Proof::new(root.clone());
+
+    let diff = proof
+        .diff(
+            vec![ProofAction {
+                player: Some(0),
+                action: PlayerAction::Play(Action(1, 1)),
+            }],
+            &mut |message| Ok(sign(message, &keys[1])),
+        )
+        .unwrap();
+
+    proof.apply(&diff).unwrap();
+
+    let data = serde_cbor::to_vec(&root).unwrap();
+    let decoded: RootProof<Box<TTT>> = serde_cbor::from_slice(&data).unwrap();
+
+    assert_eq!(decoded.hash(), root.hash());
+
+    let data = serde_cbor::to_vec(&proof).unwrap();
+    let decoded: Proof<Box<TTT>> = serde_cbor::from_slice(&data).unwrap();
+
+    assert_eq!(decoded.hash(), proof.hash());
+
+    let data = serde_cbor::to_vec(&diff).unwrap();
+    let decoded: Diff<Box<TTT>> = serde_cbor::from_slice(&data).unwrap();
+
+    assert_eq!(decoded.serialize(), diff.serialize());
+
+    // documents are structured, with decoded players, action types and authors
+
+    let field = |value: &Value, key: &str| match value {
+        Value::Map(map) => map[&Value::Text(key.to_string())].clone(),
+        _ => panic!("{:?} is not a map", value),
+    };
+
+    let document = serde_cbor::value::to_value(&diff).unwrap();
+
+    assert_eq!(
+        field(&document, "author"),
+        Value::Text(arcadeum::utils::hex(diff.author()))
+    );
+
+    let action = match field(&document, "actions") {
+        Value::Array(actions) => actions[0].clone(),
+        actions => panic!("{:?} is not an array", actions),
+    };
+
+    assert_eq!(field(&action, "player"), Value::Integer(0));
+    assert_eq!(field(&action, "type"), Value::Text("play".to_string()));
+
+    let document = serde_cbor::value::to_value(&proof).unwrap();
+
+    match field(&document, "ranges") {
+        Value::Array(ranges) => assert_eq!(ranges.len(), 3),
+        ranges => panic!("{:?} is not an array", ranges),
+    }
+
+    // authors that don't match the signatures are rejected
+
+    let mut document = serde_cbor::value::to_value(&diff).unwrap();
+
+    if let Value::Map(map) = &mut document {
+        map.insert(
+            Value::Text("author".to_string()),
+            Value::Text(arcadeum::utils::hex(&keys[2].address())),
+        );
+    }
+
+    assert!(serde_cbor::value::from_value::<Diff<Box<TTT>>>(document).is_err());
+
+    // players that don't fit the binary encoding are rejected
+
+    let mut document = serde_cbor::value::to_value(&ProofAction::<Box<TTT>> {
+        player: Some(0),
+        action: PlayerAction::Play(Action(1, 1)),
+    })
+    .unwrap();
+
+    if let Value::Map(map) = &mut document {
+        map.insert(Value::Text("player".to_string()), Value::Integer(255));
+    }
+
+    assert!(serde_cbor::value::from_value::<ProofAction<Box<TTT>>>(document).is_err());
+}
+
+#[test]
+fn test_ttt_inspect() {
+    let mut randoms = [
+        StdRng::from_seed([1; 32]),
+        StdRng::from_seed([2; 32]),
+        StdRng::from_seed([3; 32]),
+    ];
+
+    let (keys, subkeys) = generate_keys_and_subkeys(&mut randoms).unwrap();
+
+    let players = keys[1..].iter().map(Addressable::address).collect();
+
+    let state =
+        ProofState::<Box<TTT>>::new(Default::default(), players, Default::default()).unwrap();
+
+    let root = RootProof::new(state, Vec::new(), &mut |message| {
+        Ok(sign(message, &keys[0]))
+    })
+    .unwrap();
+
+    let mut context = Context::new(arcadeum::ID::serialize(root.state().id()));
+
+    let report = inspect::<Secp256k1>(&root.serialize(), Kind::RootProof, &context).unwrap();
+
+    assert!(report.is_valid());
+    assert_eq!(report.author(), Some(&keys[0].address()[..]));
+    assert_eq!(report.hash(), Some(root.hash()));
+    assert_eq!(report.version(), Some(TTT::version()));
+
+    context.learn(&report);
+
+    let mut proof = Proof::new(root.clone());
+
+    let address = subkeys[0].address();
+
+    let diff = proof
+        .diff(
+            vec![ProofAction {
+                player: Some(0),
+                action: PlayerAction::Certify {
+                    address,
+                    signature: sign(TTT::challenge(&address).as_bytes(), &keys[1]),
+                    expiry: None,
+                },
+            }],
+            &mut |message| Ok(sign(message, &keys[1])),
+        )
+        .unwrap();
+
+    proof.apply(&diff).unwrap();
+
+    let diff = proof
+        .diff(
+            vec![ProofAction {
+                player: Some(0),
+                action: PlayerAction::Play(Action(1, 2)),
+            }],
+            &mut |message| Ok(sign(message, &subkeys[0])),
+        )
+        .unwrap();
+
+    // diffs in either format apply to the proof they were constructed on
+
+    let report = inspect::<Secp256k1>(
+        &diff.serialize_with_format(Format::V2),
+        Kind::Diff,
+        &context,
+    )
+    .unwrap();
+
+    assert!(report.is_valid());
+    assert_eq!(report.lines()[0], "format: v2");
+    assert_eq!(report.proof(), Some(proof.hash()));
+    assert_eq!(report.author(), Some(&address[..]));
+
+    proof.apply(&diff).unwrap();
+
+    // player ranges after the first action are attributed to the diffs that signed them
+
+    let unattributed = inspect::<Secp256k1>(&proof.serialize(), Kind::Proof, &context).unwrap();
+
+    assert!(unattributed
+        .lines()
+        .iter()
+        .any(|line| line.trim() == "signer: unknown"));
+
+    context.learn(&report);
+
+    let report = inspect::<Secp256k1>(&proof.serialize(), Kind::Proof, &context).unwrap();
+
+    assert!(report.is_valid());
+    assert_eq!(report.hash(), Some(proof.hash()));
+    assert_eq!(report.owner(), Some(&keys[0].address()[..]));
+
+    let lines = report.lines().join("\n");
+
+    assert!(lines.contains(&format!("0: player 0 certify {}", address.eip55())));
+    assert!(lines.contains(&format!("signer: {}", keys[1].address().eip55())));
+    assert!(lines.contains("1: player 0 play 0x0102"));
+    assert!(lines.contains(&format!("signer: {} (diff author)", address.eip55())));
+    assert!(!lines.contains("signer: unknown"));
+
+    // a tampered signature is reported
+
+    let mut data = root.serialize();
+    *data.last_mut().unwrap() = u8::MAX;
+
+    let report = inspect::<Secp256k1>(&data, Kind::RootProof, &context).unwrap();
+
+    assert!(!report.is_valid());
+    assert_eq!(report.author(), None);
+
+    // framing errors are errors
+
+    let data = diff.serialize();
+
+    assert!(inspect::<Secp256k1>(&data[..data.len() - 1], Kind::Diff, &context).is_err());
+    assert!("transcript".parse::<Kind>().is_err());
+}
+
+/// Root proof of a match encoded before state layouts were tagged
+#[cfg(not(feature = "no-crypto"))]
+const BASELINE_ROOT: &str =
+    "0x610000000300000054545490181e18cc1836188618b60e18e318b8184b186c187d1832181d187018d518c000\
+     ce9ad6a3801a902250b1ec940a34a607907e1a6212cd5f2391feecc711553b82278468e490de28a500000000\
+     000000000000000000000000000000000038942544c3f2740b21009237751a84614ef3dea4287193322b9923\
+     a221dfc008042adea097a9763208db2f86e4e71225cc5190b2fff41bd31aa3fa3c752fcb781b";
+
+/// Final proof of the same match, won by player 0
+#[cfg(not(feature = "no-crypto"))]
+const BASELINE_PROOF: &str =
+    "0x0b0100000300000054545490181e18cc1836188618b60e18e318b8184b186c187d1832181d187018d518c008\
+     ce9ad6a3801a902250b1ec940a34a607907e1a6212cd5f2391feecc711553b82278468e490de28a502000000\
+     1b4b5b02248af2e60ac4148a489b46bac12300898f82729eb75cd3fee3fe7ad757c602ea5f2a4931b0e500b2\
+     8bf21688d04662254af62abe7873f2554bcd51e7ac225affa1cf049d79a541498360bdd10a0157231c3033ed\
+     e45e631bbe0aecce0d62cd771e52a45ed12d897293612247cdbb8407c939173b97af6069217503f9c63b4458\
+     0cc9bb8e6d1167e75028d8e475c540fee01f6dc87b46a743bba0323fa392089ddce355eef21b000000000100\
+     0202020001000101000000040000000100020100010100000001000000422e2bdf36beab47cd1bd6667dfcc7\
+     c9add7c6d1cf7bdf0edf9593b4beea64682f97184f0c1678099ee2e939409398a90578a1b01a6a15a47100cd\
+     cfae7e611f1b0100000000000000001ce22fec1c7559a6bd34ff14d5e75a9513ffa54e2aea0318169ab1d3ec\
+     83a64d22d3f55eacb4ba9854ebd41e1303168e9d756d6cf0877b1865f7211f317fd9e01b";
+
+/// Signatures of the baseline fixtures only verify with real cryptography.
+#[cfg(not(feature = "no-crypto"))]
+#[test]
+fn test_ttt_baseline() {
+    let data = arcadeum::utils::unhex(BASELINE_ROOT).unwrap();
+    let root = RootProof::<Box<TTT>>::deserialize(&data, false).unwrap();
+
+    assert_eq!(root.serialize(), data);
+
+    let data = arcadeum::utils::unhex(BASELINE_PROOF).unwrap();
+    let mut proof = Proof::new(root.clone());
+
+    proof.deserialize(&data, false).unwrap();
+
+    assert_eq!(proof.serialize(), data);
+    assert_eq!(proof.state().state().outcome(), Outcome::Winner(0));
+
+    // the compact format carries the untagged layout unchanged
+
+    let mut compact = Proof::new(root);
+
+    compact
+        .deserialize(&proof.serialize_with_format(Format::V2), false)
+        .unwrap();
+
+    assert_eq!(compact.serialize(), data);
+}
+
+/// Harness running [settlement::VERIFIER] in an EVM
+#[cfg(feature = "std")]
+mod evm {
+    use {
+        arcadeum::{
+            crypto::{keccak256, Address, Hash},
+            settlement,
+            utils::unhex,
+        },
+        core::convert::TryInto,
+        revm::{
+            db::{CacheDB, EmptyDB},
+            primitives::{self, AccountInfo, Bytecode, ExecutionResult, Output, TxKind},
+            Evm,
+        },
+        std::{
+            env,
+            io::Write,
+            process::{Command, Stdio},
+        },
+    };
+
+    const ADDRESS: primitives::Address = primitives::Address::repeat_byte(0xaa);
+
+    pub struct Verifier {
+        db: CacheDB<EmptyDB>,
+    }
+
+    impl Verifier {
+        /// Compiles [settlement::VERIFIER] using `$SOLC`, or `solc` if unset.
+        pub fn compile() -> Self {
+            let mut solc = Command::new(env::var("SOLC").unwrap_or_else(|_| "solc".into()))
+                .args(["--optimize", "--bin-runtime", "-"])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .expect("solc");
+
+            solc.stdin
+                .take()
+                .unwrap()
+                .write_all(settlement::VERIFIER.as_bytes())
+                .unwrap();
+
+            let output = solc.wait_with_output().unwrap();
+
+            assert!(output.status.success());
+
+            let output = String::from_utf8(output.stdout).unwrap();
+
+            let code = output
+                .lines()
+                .skip_while(|line| !line.starts_with("Binary of the runtime part:"))
+                .nth(1)
+                .expect("runtime bytecode");
+
+            Self::new(&unhex(code.trim()).unwrap())
+        }
+
+        /// Deploys the given runtime bytecode.
+        pub fn new(code: &[u8]) -> Self {
+            let mut db = CacheDB::new(EmptyDB::default());
+
+            let code = Bytecode::new_raw(code.to_vec().into());
+
+            db.insert_account_info(
+                ADDRESS,
+                AccountInfo::new(Default::default(), 0, code.hash_slow(), code),
+            );
+
+            Self { db }
+        }
+
+        /// Calls `verify(bytes)`, returning the revert reason on failure.
+        pub fn verify(&self, settlement: &[u8]) -> Result<(Hash, Vec<Address>), String> {
+            let mut input = keccak256(b"verify(bytes)")[..4].to_vec();
+
+            input.extend(word(32));
+            input.extend(word(settlement.len()));
+            input.extend(settlement);
+            input.resize(input.len() + (32 - settlement.len() % 32) % 32, 0);
+
+            let result = Evm::builder()
+                .with_db(self.db.clone())
+                .modify_tx_env(|tx| {
+                    tx.transact_to = TxKind::Call(ADDRESS);
+                    tx.data = input.into();
+                })
+                .build()
+                .transact()
+                .unwrap()
+                .result;
+
+            let output = match result {
+                ExecutionResult::Success {
+                    output: Output::Call(output),
+                    ..
+                } => output,
+                // Error(string)
+                ExecutionResult::Revert { output, .. } => {
+                    assert_eq!(output[..4], keccak256(b"Error(string)")[..4]);
+
+                    let reason = &output[4..];
+                    let length = read_word(reason, 32);
+
+                    return Err(String::from_utf8(reason[64..64 + length].to_vec()).unwrap());
+                }
+                result => panic!("{:?}", result),
+            };
+
+            let signers = read_word(&output, 32);
+
+            Ok((
+                output[..32].try_into().unwrap(),
+                (0..read_word(&output, signers))
+                    .map(|i| {
+                        let offset = signers + 32 + 32 * i;
+
+                        output[offset + 12..offset + 32].try_into().unwrap()
+                    })
+                    .collect(),
+            ))
+        }
+    }
+
+    fn word(value: usize) -> [u8; 32] {
+        let mut word = [0; 32];
+
+        word[24..].copy_from_slice(&(value as u64).to_be_bytes());
+
+        word
+    }
+
+    fn read_word(data: &[u8], offset: usize) -> usize {
+        u64::from_be_bytes(data[offset + 24..offset + 32].try_into().unwrap())
+            .try_into()
+            .unwrap()
+    }
 }